@@ -33,6 +33,9 @@ impl Engine {
             controller.process_input(&self.input);
             controller.update_camera(self.renderer.camera_mut(), dt);
         }
+        if let Some(camera) = self.renderer.camera_2d_mut() {
+            camera.update(dt);
+        }
         self.renderer.render();
         self.dt = dt;
     }
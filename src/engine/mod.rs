@@ -1,3 +1,4 @@
 pub mod render;
 pub mod input;
-pub mod engine;
\ No newline at end of file
+pub mod engine;
+pub mod spatial_index;
\ No newline at end of file
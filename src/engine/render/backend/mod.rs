@@ -0,0 +1,143 @@
+use std::{rc::Rc, fmt::Debug};
+
+use web_sys::HtmlImageElement;
+
+pub mod webgl;
+//only used by native tests
+#[cfg(test)]
+pub mod recording;
+#[cfg(test)]
+pub mod software;
+
+//all enums passed through the backend (targets, usages, data types, caps) are the raw GL values,
+//so WebGl2RenderingContext constants can be used on every backend.
+
+pub type Backend = Rc<dyn GraphicsBackend>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BufferHandle(pub(super) usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct VertexArrayHandle(pub(super) usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TextureHandle(pub(super) usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ShaderHandle(pub(super) usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ProgramHandle(pub(super) usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct UniformLocation(pub(super) usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FramebufferHandle(pub(super) usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RenderbufferHandle(pub(super) usize);
+
+//an attribute or uniform a linked program uses, data_type is the gl type enum (FLOAT_VEC2, SAMPLER_2D, ..)
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ActiveInfo {
+    pub name:String,
+    pub size:i32,
+    pub data_type:u32
+}
+
+pub trait GraphicsBackend: Debug {
+    //size of the surface that is drawn to, resizing it to match its display size first if the backend has one
+    fn drawable_size(&self) -> (u32,u32);
+
+    fn enable(&self, cap:u32);
+    fn disable(&self, cap:u32);
+    fn blend_func(&self, sfactor:u32, dfactor:u32);
+    fn blend_color(&self, r:f32, g:f32, b:f32, a:f32);
+    fn depth_mask(&self, flag:bool);
+    fn depth_func(&self, func:u32);
+    fn pixel_storei(&self, pname:u32, param:i32);
+    fn clear_color(&self, r:f32, g:f32, b:f32, a:f32);
+    fn clear(&self, mask:u32);
+    //clears a color attachment of an integer framebuffer, which clear leaves undefined
+    fn clear_bufferuiv(&self, buffer:u32, draw_buffer:i32, value:&[u32]);
+    fn viewport(&self, x:i32, y:i32, width:i32, height:i32);
+
+    fn create_buffer(&self) -> BufferHandle;
+    fn delete_buffer(&self, buffer:BufferHandle);
+    fn bind_buffer(&self, target:u32, buffer:Option<BufferHandle>);
+    //binds to an indexed binding point of target, which also binds it to target itself
+    fn bind_buffer_base(&self, target:u32, index:u32, buffer:Option<BufferHandle>);
+    fn buffer_data_with_size(&self, target:u32, size:i32, usage:u32);
+    fn buffer_data(&self, target:u32, data:&[u8], usage:u32);
+    fn buffer_sub_data(&self, target:u32, offset:i32, data:&[u8]);
+    fn get_buffer_sub_data(&self, target:u32, offset:i32, dst:&mut [u8]);
+    fn copy_buffer_sub_data(&self, read_target:u32, write_target:u32, read_offset:i32, write_offset:i32, size:i32);
+
+    fn create_vertex_array(&self) -> VertexArrayHandle;
+    fn delete_vertex_array(&self, vao:VertexArrayHandle);
+    fn bind_vertex_array(&self, vao:Option<VertexArrayHandle>);
+    fn enable_vertex_attrib_array(&self, location:u32);
+    fn vertex_attrib_pointer(&self, location:u32, size:i32, data_type:u32, normalized:bool, stride:i32, offset:i32);
+    fn vertex_attrib_i_pointer(&self, location:u32, size:i32, data_type:u32, stride:i32, offset:i32);
+    fn vertex_attrib_divisor(&self, location:u32, divisor:u32);
+
+    fn compile_shader(&self, shader_type:u32, source:&str) -> Result<ShaderHandle, String>;
+    fn delete_shader(&self, shader:ShaderHandle);
+    fn link_program(&self, vertex_shader:ShaderHandle, fragment_shader:ShaderHandle) -> Result<ProgramHandle, String>;
+    fn delete_program(&self, program:ProgramHandle);
+    fn use_program(&self, program:Option<ProgramHandle>);
+    fn get_attrib_location(&self, program:ProgramHandle, name:&str) -> i32;
+    fn get_uniform_location(&self, program:ProgramHandle, name:&str) -> Option<UniformLocation>;
+    fn get_active_attribs(&self, program:ProgramHandle) -> Vec<ActiveInfo>;
+    fn get_active_uniforms(&self, program:ProgramHandle) -> Vec<ActiveInfo>;
+    fn get_uniform_block_index(&self, program:ProgramHandle, name:&str) -> Option<u32>;
+    fn uniform_block_binding(&self, program:ProgramHandle, block_index:u32, binding:u32);
+    fn uniform1f(&self, location:UniformLocation, x:f32);
+    fn uniform2f(&self, location:UniformLocation, x:f32, y:f32);
+    fn uniform3f(&self, location:UniformLocation, x:f32, y:f32, z:f32);
+    fn uniform4f(&self, location:UniformLocation, x:f32, y:f32, z:f32, w:f32);
+    fn uniform1i(&self, location:UniformLocation, x:i32);
+    fn uniform1ui(&self, location:UniformLocation, x:u32);
+    fn uniform1fv(&self, location:UniformLocation, data:&[f32]);
+    fn uniform2fv(&self, location:UniformLocation, data:&[f32]);
+    fn uniform3fv(&self, location:UniformLocation, data:&[f32]);
+    fn uniform4fv(&self, location:UniformLocation, data:&[f32]);
+    fn uniform1iv(&self, location:UniformLocation, data:&[i32]);
+    fn uniform_matrix3fv(&self, location:UniformLocation, transpose:bool, data:&[f32]);
+    fn uniform_matrix4fv(&self, location:UniformLocation, transpose:bool, data:&[f32]);
+
+    fn create_texture(&self) -> TextureHandle;
+    fn delete_texture(&self, texture:TextureHandle);
+    fn active_texture(&self, unit:u32);
+    fn bind_texture(&self, target:u32, texture:Option<TextureHandle>);
+    fn tex_parameteri(&self, target:u32, pname:u32, param:i32);
+    fn tex_parameterf(&self, target:u32, pname:u32, param:f32);
+    fn tex_image_2d(&self, target:u32, level:i32, internal_format:i32, width:i32, height:i32, format:u32, data_type:u32, pixels:Option<&[u8]>);
+    fn tex_sub_image_2d(&self, target:u32, level:i32, x:i32, y:i32, width:i32, height:i32, format:u32, data_type:u32, pixels:&[u8]);
+    fn tex_sub_image_2d_with_image(&self, target:u32, level:i32, x:i32, y:i32, format:u32, data_type:u32, image:&HtmlImageElement);
+    //copies a rect of the bound framebuffer's color buffer to x_offset, y_offset in the bound texture
+    fn copy_tex_sub_image_2d(&self, target:u32, level:i32, x_offset:i32, y_offset:i32, x:i32, y:i32, width:i32, height:i32);
+    fn generate_mipmap(&self, target:u32);
+    //largest TEXTURE_MAX_ANISOTROPY_EXT allowed, None without EXT_texture_filter_anisotropic
+    fn max_anisotropy(&self) -> Option<f32>;
+
+    fn create_framebuffer(&self) -> FramebufferHandle;
+    fn delete_framebuffer(&self, framebuffer:FramebufferHandle);
+    //None binds the default framebuffer, the surface reported by drawable_size
+    fn bind_framebuffer(&self, target:u32, framebuffer:Option<FramebufferHandle>);
+    fn framebuffer_texture_2d(&self, target:u32, attachment:u32, tex_target:u32, texture:Option<TextureHandle>, level:i32);
+    fn framebuffer_renderbuffer(&self, target:u32, attachment:u32, renderbuffer_target:u32, renderbuffer:Option<RenderbufferHandle>);
+    fn check_framebuffer_status(&self, target:u32) -> u32;
+    //reads from the bound framebuffer, rows start at the bottom. Integer color buffers are read as RGBA_INTEGER and
+    //UNSIGNED_INT with 4 bytes per component, everything else as RGBA and UNSIGNED_BYTE
+    fn read_pixels(&self, x:i32, y:i32, width:i32, height:i32, format:u32, data_type:u32, dst:&mut [u8]);
+
+    fn create_renderbuffer(&self) -> RenderbufferHandle;
+    fn delete_renderbuffer(&self, renderbuffer:RenderbufferHandle);
+    fn bind_renderbuffer(&self, target:u32, renderbuffer:Option<RenderbufferHandle>);
+    fn renderbuffer_storage(&self, target:u32, internal_format:u32, width:i32, height:i32);
+
+    fn draw_elements(&self, mode:u32, count:i32, index_type:u32, offset:i32);
+    fn draw_elements_instanced(&self, mode:u32, count:i32, index_type:u32, offset:i32, instance_count:i32);
+}
@@ -0,0 +1,1052 @@
+use std::{cell::{Cell, RefCell, Ref, RefMut}, collections::{HashMap, HashSet}};
+
+use web_sys::{HtmlImageElement, WebGl2RenderingContext};
+
+use super::{GraphicsBackend, BufferHandle, VertexArrayHandle, TextureHandle, ShaderHandle, ProgramHandle, UniformLocation, ActiveInfo, FramebufferHandle, RenderbufferHandle};
+
+//Backend that never touches a gpu, it only records the calls made through it and keeps enough state
+//(buffer contents, vertex layouts, textures, uniform values) for native code to inspect what the renderer did.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GlCall {
+    Enable(u32),
+    Disable(u32),
+    BlendFunc(u32,u32),
+    BlendColor([f32; 4]),
+    DepthMask(bool),
+    DepthFunc(u32),
+    PixelStorei(u32,i32),
+    ClearColor([f32; 4]),
+    Clear(u32),
+    ClearBufferuiv { buffer:u32, draw_buffer:i32, value:Vec<u32> },
+    Viewport(i32,i32,i32,i32),
+    CreateBuffer(BufferHandle),
+    DeleteBuffer(BufferHandle),
+    BindBuffer(u32,Option<BufferHandle>),
+    BindBufferBase { target:u32, index:u32, buffer:Option<BufferHandle> },
+    BufferData { target:u32, size:usize, usage:u32 },
+    BufferSubData { target:u32, offset:i32, data:Vec<u8> },
+    CopyBufferSubData { read_target:u32, write_target:u32, read_offset:i32, write_offset:i32, size:i32 },
+    CreateVertexArray(VertexArrayHandle),
+    DeleteVertexArray(VertexArrayHandle),
+    BindVertexArray(Option<VertexArrayHandle>),
+    EnableVertexAttribArray(u32),
+    VertexAttribPointer { location:u32, size:i32, data_type:u32, normalized:bool, stride:i32, offset:i32 },
+    VertexAttribIPointer { location:u32, size:i32, data_type:u32, stride:i32, offset:i32 },
+    VertexAttribDivisor(u32,u32),
+    CompileShader(ShaderHandle),
+    DeleteShader(ShaderHandle),
+    LinkProgram(ProgramHandle),
+    DeleteProgram(ProgramHandle),
+    UseProgram(Option<ProgramHandle>),
+    Uniform(UniformLocation,UniformValue),
+    UniformBlockBinding { program:ProgramHandle, block_index:u32, binding:u32 },
+    CreateTexture(TextureHandle),
+    DeleteTexture(TextureHandle),
+    ActiveTexture(u32),
+    BindTexture(u32,Option<TextureHandle>),
+    TexParameteri(u32,u32,i32),
+    TexParameterf(u32,u32,f32),
+    TexImage2D { target:u32, level:i32, width:i32, height:i32, format:u32 },
+    TexSubImage2D { target:u32, level:i32, x:i32, y:i32, width:i32, height:i32 },
+    CopyTexSubImage2D { target:u32, level:i32, x_offset:i32, y_offset:i32, x:i32, y:i32, width:i32, height:i32 },
+    GenerateMipmap(u32),
+    CreateFramebuffer(FramebufferHandle),
+    DeleteFramebuffer(FramebufferHandle),
+    BindFramebuffer(u32,Option<FramebufferHandle>),
+    FramebufferTexture2D { attachment:u32, texture:Option<TextureHandle>, level:i32 },
+    FramebufferRenderbuffer { attachment:u32, renderbuffer:Option<RenderbufferHandle> },
+    CreateRenderbuffer(RenderbufferHandle),
+    DeleteRenderbuffer(RenderbufferHandle),
+    BindRenderbuffer(u32,Option<RenderbufferHandle>),
+    RenderbufferStorage { internal_format:u32, width:i32, height:i32 },
+    ReadPixels { x:i32, y:i32, width:i32, height:i32, format:u32, data_type:u32 },
+    DrawElements { mode:u32, count:i32, index_type:u32, offset:i32 },
+    DrawElementsInstanced { mode:u32, count:i32, index_type:u32, offset:i32, instance_count:i32 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UniformValue {
+    Float(f32),
+    Int(i32),
+    UInt(u32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Matrix3([f32; 9]),
+    Matrix4([f32; 16]),
+    //every element of an array uniform, flattened
+    FloatArray(Vec<f32>),
+    IntArray(Vec<i32>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttribPointer {
+    pub buffer:Option<BufferHandle>,
+    pub size:i32,
+    pub data_type:u32,
+    pub normalized:bool,
+    //set through vertexAttribIPointer, read without converting to float
+    pub integer:bool,
+    pub stride:i32,
+    pub offset:i32,
+    pub divisor:u32,
+    pub enabled:bool
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VertexArrayState {
+    pub element_buffer:Option<BufferHandle>,
+    pub attribs:HashMap<u32,AttribPointer>
+}
+
+#[derive(Clone, Debug)]
+pub struct TextureState {
+    pub width:i32,
+    pub height:i32,
+    pub format:u32,
+    pub pixels:Vec<u8>,
+    pub parameters:HashMap<u32,i32>,
+    pub float_parameters:HashMap<u32,f32>
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FramebufferState {
+    pub color:Option<TextureHandle>,
+    pub depth:Option<RenderbufferHandle>
+}
+
+#[derive(Clone, Debug)]
+pub struct RenderbufferState {
+    pub internal_format:u32,
+    pub width:i32,
+    pub height:i32
+}
+
+#[derive(Clone, Debug)]
+pub struct ProgramState {
+    pub vertex_source:String,
+    pub fragment_source:String,
+    pub attrib_locations:HashMap<String,i32>,
+    //locations taken by each attribute, matrices take one per column
+    pub attrib_columns:HashMap<String,i32>,
+    pub uniform_locations:HashMap<String,UniformLocation>,
+    pub uniform_values:HashMap<String,UniformValue>,
+    //uniform blocks in declaration order, a block's index is its position here
+    pub uniform_blocks:Vec<(String,Vec<(String,String)>)>,
+    pub uniform_block_bindings:HashMap<u32,u32>
+}
+
+#[derive(Debug, Default)]
+pub struct RecordedState {
+    pub buffers:HashMap<BufferHandle,Vec<u8>>,
+    pub vertex_arrays:HashMap<VertexArrayHandle,VertexArrayState>,
+    pub textures:HashMap<TextureHandle,TextureState>,
+    pub shaders:HashMap<ShaderHandle,(u32,String)>,
+    pub programs:HashMap<ProgramHandle,ProgramState>,
+    pub framebuffers:HashMap<FramebufferHandle,FramebufferState>,
+    pub renderbuffers:HashMap<RenderbufferHandle,RenderbufferState>,
+    //None is the default framebuffer
+    pub framebuffer:Option<FramebufferHandle>,
+    pub renderbuffer:Option<RenderbufferHandle>,
+    //bindings of every target except ELEMENT_ARRAY_BUFFER, which is part of the vertex array
+    pub buffer_bindings:HashMap<u32,BufferHandle>,
+    //indexed bindings from bindBufferBase, by target and index
+    pub indexed_buffer_bindings:HashMap<(u32,u32),BufferHandle>,
+    pub vertex_array:Option<VertexArrayHandle>,
+    pub default_vertex_array:VertexArrayState,
+    pub program:Option<ProgramHandle>,
+    pub active_texture:u32,
+    pub texture_units:HashMap<u32,TextureHandle>,
+    pub enabled:HashSet<u32>,
+    pub blend_func:(u32,u32),
+    pub blend_color:[f32; 4],
+    pub depth_mask:bool,
+    pub depth_func:u32,
+    pub clear_color:[f32; 4],
+    pub viewport:(i32,i32,i32,i32),
+    pub unpack_flip_y:bool,
+    uniform_owners:HashMap<UniformLocation,(ProgramHandle,String)>
+}
+
+impl RecordedState {
+    pub fn current_vertex_array(&self) -> &VertexArrayState {
+        match self.vertex_array {
+            Some(vao) => &self.vertex_arrays[&vao],
+            None => &self.default_vertex_array
+        }
+    }
+
+    fn current_vertex_array_mut(&mut self) -> &mut VertexArrayState {
+        match self.vertex_array {
+            Some(vao) => self.vertex_arrays.get_mut(&vao).expect("Bound vertex array was deleted"),
+            None => &mut self.default_vertex_array
+        }
+    }
+
+    fn bound_buffer(&self, target:u32) -> Option<BufferHandle> {
+        match target {
+            WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER => self.current_vertex_array().element_buffer,
+            _ => self.buffer_bindings.get(&target).copied()
+        }
+    }
+
+    fn bound_buffer_mut(&mut self, target:u32) -> &mut Vec<u8> {
+        let buffer = self.bound_buffer(target).expect("No buffer bound to target");
+        self.buffers.get_mut(&buffer).expect("Bound buffer was deleted")
+    }
+
+    fn bound_framebuffer_mut(&mut self) -> &mut FramebufferState {
+        let framebuffer = self.framebuffer.expect("No framebuffer bound");
+        self.framebuffers.get_mut(&framebuffer).expect("Bound framebuffer was deleted")
+    }
+
+    //status checkFramebufferStatus would report for the bound framebuffer, only color and depth attachments are tracked
+    pub fn framebuffer_status(&self) -> u32 {
+        let framebuffer = match self.framebuffer {
+            Some(framebuffer) => &self.framebuffers[&framebuffer],
+            None => return WebGl2RenderingContext::FRAMEBUFFER_COMPLETE
+        };
+        let color = match framebuffer.color.and_then(|x| self.textures.get(&x)) {
+            Some(color) if color.width > 0 && color.height > 0 => color,
+            _ => return WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT
+        };
+        match framebuffer.depth.and_then(|x| self.renderbuffers.get(&x)) {
+            Some(depth) if depth.width != color.width || depth.height != color.height => WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_DIMENSIONS,
+            _ => WebGl2RenderingContext::FRAMEBUFFER_COMPLETE
+        }
+    }
+
+    fn bound_texture_mut(&mut self) -> &mut TextureState {
+        let texture = self.texture_units[&self.active_texture];
+        self.textures.get_mut(&texture).expect("Bound texture was deleted")
+    }
+}
+
+#[derive(Debug)]
+pub struct RecordingBackend {
+    calls:RefCell<Vec<GlCall>>,
+    state:RefCell<RecordedState>,
+    size:Cell<(u32,u32)>,
+    max_anisotropy:Cell<Option<f32>>,
+    compile_errors:RefCell<Vec<String>>,
+    last_handle:Cell<usize>
+}
+
+impl RecordingBackend {
+    pub fn new(width:u32, height:u32) -> Self {
+        Self {
+            calls:RefCell::new(Vec::new()),
+            //gl starts with depth writes on and a LESS depth test
+            state:RefCell::new(RecordedState { depth_mask:true, depth_func:WebGl2RenderingContext::LESS, ..RecordedState::default() }),
+            size:Cell::new((width,height)),
+            max_anisotropy:Cell::new(None),
+            compile_errors:RefCell::new(Vec::new()),
+            last_handle:Cell::new(0)
+        }
+    }
+
+    pub fn set_drawable_size(&self, width:u32, height:u32) {
+        self.size.set((width,height));
+    }
+
+    //shaders whose source contains text fail to compile from now on
+    pub fn fail_compiles_containing(&self, text:&str) {
+        self.compile_errors.borrow_mut().push(text.to_owned());
+    }
+
+    //what max_anisotropy reports, as if EXT_texture_filter_anisotropic were supported. None by default
+    pub fn set_max_anisotropy(&self, max_anisotropy:Option<f32>) {
+        self.max_anisotropy.set(max_anisotropy);
+    }
+
+    pub fn calls(&self) -> Vec<GlCall> {
+        self.calls.borrow().clone()
+    }
+
+    pub fn take_calls(&self) -> Vec<GlCall> {
+        self.calls.borrow_mut().drain(..).collect()
+    }
+
+    pub fn draw_calls(&self) -> Vec<GlCall> {
+        self.calls.borrow().iter().filter(|x| matches!(x, GlCall::DrawElements {..} | GlCall::DrawElementsInstanced {..})).cloned().collect()
+    }
+
+    pub fn state(&self) -> Ref<'_, RecordedState> {
+        self.state.borrow()
+    }
+
+    pub(super) fn state_mut(&self) -> RefMut<'_, RecordedState> {
+        self.state.borrow_mut()
+    }
+
+    pub fn buffer_contents(&self, buffer:BufferHandle) -> Option<Vec<u8>> {
+        self.state.borrow().buffers.get(&buffer).cloned()
+    }
+
+    pub fn uniform_value(&self, program:ProgramHandle, name:&str) -> Option<UniformValue> {
+        self.state.borrow().programs.get(&program)?.uniform_values.get(name).cloned()
+    }
+
+    fn record(&self, call:GlCall) {
+        self.calls.borrow_mut().push(call);
+    }
+
+    fn next_handle(&self) -> usize {
+        self.last_handle.set(self.last_handle.get() + 1);
+        self.last_handle.get()
+    }
+
+    fn set_attrib_pointer(&self, location:u32, size:i32, data_type:u32, normalized:bool, integer:bool, stride:i32, offset:i32) {
+        let mut state = self.state.borrow_mut();
+        let buffer = state.bound_buffer(WebGl2RenderingContext::ARRAY_BUFFER);
+        let vao = state.current_vertex_array_mut();
+        let (enabled, divisor) = vao.attribs.get(&location).map(|x| (x.enabled, x.divisor)).unwrap_or((false, 0));
+        vao.attribs.insert(location, AttribPointer {
+            buffer:buffer,
+            size:size,
+            data_type:data_type,
+            normalized:normalized,
+            integer:integer,
+            stride:stride,
+            offset:offset,
+            divisor:divisor,
+            enabled:enabled
+        });
+    }
+
+    fn set_uniform(&self, location:UniformLocation, value:UniformValue) {
+        let mut state = self.state.borrow_mut();
+        let (program, name) = state.uniform_owners[&location].clone();
+        if let Some(program) = state.programs.get_mut(&program) {
+            program.uniform_values.insert(name, value.clone());
+        }
+        drop(state);
+        self.record(GlCall::Uniform(location, value));
+    }
+}
+
+fn strip_comments(source:&str) -> String {
+    source.lines().map(|l| match l.find("//") {
+        Some(i) => &l[..i],
+        None => l
+    }).collect::<Vec<_>>().join("\n")
+}
+
+//declarations of the form `[layout(..)] <qualifier> [precision] <type> <name>[array];`, comments are stripped first.
+//Members of uniform blocks aren't included
+pub(super) fn parse_declarations(source:&str, qualifier:&str) -> Vec<(String,String)> {
+    let stripped = strip_comments(source);
+
+    stripped.split(|c| c == ';' || c == '{' || c == '}').filter_map(|statement| {
+        let tokens:Vec<&str> = statement.split_whitespace().collect();
+        let i = tokens.iter().position(|x| *x == qualifier)?;
+        //function parameters also use in/out qualifiers
+        if tokens.len() < i + 3 || tokens[i..].iter().any(|x| x.contains('(') || x.contains(')')) { return None; }
+        let name = tokens[tokens.len()-1];
+        let name = match name.find('[') {
+            Some(b) => &name[..b],
+            None => name
+        };
+        Some((tokens[tokens.len()-2].to_owned(), name.to_owned()))
+    }).collect()
+}
+
+//blocks of the form `[layout(..)] uniform <Block> { <type> <name>; .. } [instance];`, with their members in order
+pub(super) fn parse_uniform_blocks(source:&str) -> Vec<(String,Vec<(String,String)>)> {
+    let stripped = strip_comments(source);
+    let mut blocks = Vec::new();
+    let mut rest = stripped.as_str();
+    while let Some(open) = rest.find('{') {
+        let close = match rest[open..].find('}') {
+            Some(x) => open + x,
+            None => break
+        };
+        let head:Vec<&str> = rest[..open].rsplit(|c| c == ';' || c == '}').next().unwrap_or("").split_whitespace().collect();
+        if head.len() >= 2 && head[head.len()-2] == "uniform" {
+            let members = rest[open+1..close].split(';').filter_map(|member| {
+                let tokens:Vec<&str> = member.split_whitespace().collect();
+                if tokens.len() < 2 { return None; }
+                Some((tokens[tokens.len()-2].to_owned(), tokens[tokens.len()-1].to_owned()))
+            }).collect();
+            blocks.push((head[head.len()-1].to_owned(), members));
+        }
+        rest = &rest[close+1..];
+    }
+    blocks
+}
+
+//byte offset of each member of a std140 block, arrays and structs aren't handled
+pub(super) fn std140_offsets(members:&[(String,String)]) -> Vec<usize> {
+    let mut offset = 0;
+    members.iter().map(|(glsl_type,_)| {
+        let (align, size) = match glsl_type.as_str() {
+            "vec2" | "ivec2" | "uvec2" | "bvec2" => (8, 8),
+            "vec3" | "ivec3" | "uvec3" | "bvec3" => (16, 12),
+            "vec4" | "ivec4" | "uvec4" | "bvec4" => (16, 16),
+            "mat2" => (16, 32),
+            "mat3" => (16, 48),
+            "mat4" => (16, 64),
+            _ => (4, 4)
+        };
+        let start = (offset + align - 1) / align * align;
+        offset = start + size;
+        start
+    }).collect()
+}
+
+fn attrib_columns_of(glsl_type:&str) -> i32 {
+    match glsl_type {
+        "mat2" => 2,
+        "mat3" => 3,
+        "mat4" => 4,
+        _ => 1
+    }
+}
+
+fn active_info(name:String, glsl_type:&str) -> ActiveInfo {
+    let data_type = match glsl_type {
+        "float" => WebGl2RenderingContext::FLOAT,
+        "vec2" => WebGl2RenderingContext::FLOAT_VEC2,
+        "vec3" => WebGl2RenderingContext::FLOAT_VEC3,
+        "vec4" => WebGl2RenderingContext::FLOAT_VEC4,
+        "int" => WebGl2RenderingContext::INT,
+        "ivec2" => WebGl2RenderingContext::INT_VEC2,
+        "ivec3" => WebGl2RenderingContext::INT_VEC3,
+        "ivec4" => WebGl2RenderingContext::INT_VEC4,
+        "uint" => WebGl2RenderingContext::UNSIGNED_INT,
+        "uvec2" => WebGl2RenderingContext::UNSIGNED_INT_VEC2,
+        "uvec3" => WebGl2RenderingContext::UNSIGNED_INT_VEC3,
+        "uvec4" => WebGl2RenderingContext::UNSIGNED_INT_VEC4,
+        "bool" => WebGl2RenderingContext::BOOL,
+        "mat2" => WebGl2RenderingContext::FLOAT_MAT2,
+        "mat3" => WebGl2RenderingContext::FLOAT_MAT3,
+        "mat4" => WebGl2RenderingContext::FLOAT_MAT4,
+        "sampler2D" => WebGl2RenderingContext::SAMPLER_2D,
+        "samplerCube" => WebGl2RenderingContext::SAMPLER_CUBE,
+        _ => 0
+    };
+    ActiveInfo { name:name, size:1, data_type:data_type }
+}
+
+//stored pixels always start at the bottom row like gl textures, so flipped uploads are reversed here
+fn unpack_rows(pixels:&[u8], height:usize, row:usize, flip_y:bool) -> Vec<u8> {
+    if !flip_y { return pixels[..height*row].to_vec(); }
+    pixels[..height*row].chunks_exact(row).rev().flatten().copied().collect()
+}
+
+pub(super) fn bytes_per_pixel(format:u32) -> usize {
+    match format {
+        WebGl2RenderingContext::RGB => 3,
+        _ => 4
+    }
+}
+
+//copies a rectangle out of pixels stored like TextureState::pixels, rows of pixels_width starting at the bottom.
+//RED_INTEGER pixels hold one u32 and are read as RGBA_INTEGER, anything else as RGBA bytes
+pub(super) fn read_pixel_rect(pixels:&[u8], pixels_format:u32, pixels_width:i32, x:i32, y:i32, width:i32, height:i32, format:u32, dst:&mut [u8]) {
+    let integer = pixels_format == WebGl2RenderingContext::RED_INTEGER;
+    assert_eq!(format, if integer { WebGl2RenderingContext::RGBA_INTEGER } else { WebGl2RenderingContext::RGBA }, "Unsupported read pixels format {:#x}", format);
+    let bpp = bytes_per_pixel(pixels_format);
+    let out = if integer { 16 } else { 4 };
+    for row in 0..height {
+        for column in 0..width {
+            let i = ((y + row) * pixels_width + x + column) as usize * bpp;
+            let o = (row * width + column) as usize * out;
+            let p = &pixels[i..i+bpp];
+            let dst = &mut dst[o..o+out];
+            if integer {
+                dst[..4].copy_from_slice(p);
+                dst[4..12].fill(0);
+                dst[12..].copy_from_slice(&1u32.to_ne_bytes());
+            } else {
+                dst[..3].copy_from_slice(&p[..3]);
+                dst[3] = if bpp == 4 { p[3] } else { 255 };
+            }
+        }
+    }
+}
+
+impl GraphicsBackend for RecordingBackend {
+    fn drawable_size(&self) -> (u32,u32) {
+        self.size.get()
+    }
+
+    fn enable(&self, cap:u32) {
+        self.state.borrow_mut().enabled.insert(cap);
+        self.record(GlCall::Enable(cap));
+    }
+
+    fn disable(&self, cap:u32) {
+        self.state.borrow_mut().enabled.remove(&cap);
+        self.record(GlCall::Disable(cap));
+    }
+
+    fn blend_func(&self, sfactor:u32, dfactor:u32) {
+        self.state.borrow_mut().blend_func = (sfactor,dfactor);
+        self.record(GlCall::BlendFunc(sfactor, dfactor));
+    }
+
+    fn blend_color(&self, r:f32, g:f32, b:f32, a:f32) {
+        self.state.borrow_mut().blend_color = [r, g, b, a];
+        self.record(GlCall::BlendColor([r, g, b, a]));
+    }
+
+    fn depth_mask(&self, flag:bool) {
+        self.state.borrow_mut().depth_mask = flag;
+        self.record(GlCall::DepthMask(flag));
+    }
+
+    fn depth_func(&self, func:u32) {
+        self.state.borrow_mut().depth_func = func;
+        self.record(GlCall::DepthFunc(func));
+    }
+
+    fn pixel_storei(&self, pname:u32, param:i32) {
+        if pname == WebGl2RenderingContext::UNPACK_FLIP_Y_WEBGL {
+            self.state.borrow_mut().unpack_flip_y = param != 0;
+        }
+        self.record(GlCall::PixelStorei(pname, param));
+    }
+
+    fn clear_color(&self, r:f32, g:f32, b:f32, a:f32) {
+        self.state.borrow_mut().clear_color = [r,g,b,a];
+        self.record(GlCall::ClearColor([r,g,b,a]));
+    }
+
+    fn clear(&self, mask:u32) {
+        self.record(GlCall::Clear(mask));
+    }
+
+    fn clear_bufferuiv(&self, buffer:u32, draw_buffer:i32, value:&[u32]) {
+        self.record(GlCall::ClearBufferuiv { buffer:buffer, draw_buffer:draw_buffer, value:value.to_vec() });
+    }
+
+    fn viewport(&self, x:i32, y:i32, width:i32, height:i32) {
+        self.state.borrow_mut().viewport = (x,y,width,height);
+        self.record(GlCall::Viewport(x, y, width, height));
+    }
+
+    fn create_buffer(&self) -> BufferHandle {
+        let buffer = BufferHandle(self.next_handle());
+        self.state.borrow_mut().buffers.insert(buffer, Vec::new());
+        self.record(GlCall::CreateBuffer(buffer));
+        buffer
+    }
+
+    fn delete_buffer(&self, buffer:BufferHandle) {
+        self.state.borrow_mut().buffers.remove(&buffer);
+        self.record(GlCall::DeleteBuffer(buffer));
+    }
+
+    fn bind_buffer(&self, target:u32, buffer:Option<BufferHandle>) {
+        let mut state = self.state.borrow_mut();
+        match target {
+            WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER => state.current_vertex_array_mut().element_buffer = buffer,
+            _ => match buffer {
+                Some(buffer) => { state.buffer_bindings.insert(target, buffer); },
+                None => { state.buffer_bindings.remove(&target); }
+            }
+        }
+        drop(state);
+        self.record(GlCall::BindBuffer(target, buffer));
+    }
+
+    fn buffer_data_with_size(&self, target:u32, size:i32, usage:u32) {
+        *self.state.borrow_mut().bound_buffer_mut(target) = vec![0; size as usize];
+        self.record(GlCall::BufferData { target:target, size:size as usize, usage:usage });
+    }
+
+    fn buffer_data(&self, target:u32, data:&[u8], usage:u32) {
+        *self.state.borrow_mut().bound_buffer_mut(target) = data.to_vec();
+        self.record(GlCall::BufferData { target:target, size:data.len(), usage:usage });
+    }
+
+    fn bind_buffer_base(&self, target:u32, index:u32, buffer:Option<BufferHandle>) {
+        let mut state = self.state.borrow_mut();
+        match buffer {
+            Some(buffer) => {
+                state.indexed_buffer_bindings.insert((target, index), buffer);
+                state.buffer_bindings.insert(target, buffer);
+            },
+            None => {
+                state.indexed_buffer_bindings.remove(&(target, index));
+                state.buffer_bindings.remove(&target);
+            }
+        }
+        drop(state);
+        self.record(GlCall::BindBufferBase { target:target, index:index, buffer:buffer });
+    }
+
+    fn buffer_sub_data(&self, target:u32, offset:i32, data:&[u8]) {
+        let mut state = self.state.borrow_mut();
+        let buffer = state.bound_buffer_mut(target);
+        let start = offset as usize;
+        assert!(start + data.len() <= buffer.len(), "buffer_sub_data out of range: {}..{} of {}", start, start + data.len(), buffer.len());
+        buffer[start..start+data.len()].copy_from_slice(data);
+        drop(state);
+        self.record(GlCall::BufferSubData { target:target, offset:offset, data:data.to_vec() });
+    }
+
+    fn get_buffer_sub_data(&self, target:u32, offset:i32, dst:&mut [u8]) {
+        let state = self.state.borrow();
+        let buffer = &state.buffers[&state.bound_buffer(target).expect("No buffer bound to target")];
+        let start = offset as usize;
+        dst.copy_from_slice(&buffer[start..start+dst.len()]);
+    }
+
+    fn copy_buffer_sub_data(&self, read_target:u32, write_target:u32, read_offset:i32, write_offset:i32, size:i32) {
+        let mut state = self.state.borrow_mut();
+        let (read, write) = (read_offset as usize, write_offset as usize);
+        let data = state.buffers[&state.bound_buffer(read_target).expect("No buffer bound to read target")][read..read+size as usize].to_vec();
+        let buffer = state.bound_buffer_mut(write_target);
+        assert!(write + data.len() <= buffer.len(), "copy_buffer_sub_data out of range: {}..{} of {}", write, write + data.len(), buffer.len());
+        buffer[write..write+data.len()].copy_from_slice(&data);
+        drop(state);
+        self.record(GlCall::CopyBufferSubData { read_target:read_target, write_target:write_target, read_offset:read_offset, write_offset:write_offset, size:size });
+    }
+
+    fn create_vertex_array(&self) -> VertexArrayHandle {
+        let vao = VertexArrayHandle(self.next_handle());
+        self.state.borrow_mut().vertex_arrays.insert(vao, VertexArrayState::default());
+        self.record(GlCall::CreateVertexArray(vao));
+        vao
+    }
+
+    fn delete_vertex_array(&self, vao:VertexArrayHandle) {
+        let mut state = self.state.borrow_mut();
+        state.vertex_arrays.remove(&vao);
+        if state.vertex_array == Some(vao) { state.vertex_array = None; }
+        drop(state);
+        self.record(GlCall::DeleteVertexArray(vao));
+    }
+
+    fn bind_vertex_array(&self, vao:Option<VertexArrayHandle>) {
+        self.state.borrow_mut().vertex_array = vao;
+        self.record(GlCall::BindVertexArray(vao));
+    }
+
+    fn enable_vertex_attrib_array(&self, location:u32) {
+        self.state.borrow_mut().current_vertex_array_mut().attribs.entry(location).or_insert(AttribPointer {
+            buffer:None,
+            size:4,
+            data_type:WebGl2RenderingContext::FLOAT,
+            normalized:false,
+            integer:false,
+            stride:0,
+            offset:0,
+            divisor:0,
+            enabled:true
+        }).enabled = true;
+        self.record(GlCall::EnableVertexAttribArray(location));
+    }
+
+    fn vertex_attrib_pointer(&self, location:u32, size:i32, data_type:u32, normalized:bool, stride:i32, offset:i32) {
+        self.set_attrib_pointer(location, size, data_type, normalized, false, stride, offset);
+        self.record(GlCall::VertexAttribPointer { location:location, size:size, data_type:data_type, normalized:normalized, stride:stride, offset:offset });
+    }
+
+    fn vertex_attrib_i_pointer(&self, location:u32, size:i32, data_type:u32, stride:i32, offset:i32) {
+        self.set_attrib_pointer(location, size, data_type, false, true, stride, offset);
+        self.record(GlCall::VertexAttribIPointer { location:location, size:size, data_type:data_type, stride:stride, offset:offset });
+    }
+
+    fn vertex_attrib_divisor(&self, location:u32, divisor:u32) {
+        if let Some(attrib) = self.state.borrow_mut().current_vertex_array_mut().attribs.get_mut(&location) {
+            attrib.divisor = divisor;
+        }
+        self.record(GlCall::VertexAttribDivisor(location, divisor));
+    }
+
+    fn compile_shader(&self, shader_type:u32, source:&str) -> Result<ShaderHandle, String> {
+        if let Some(text) = self.compile_errors.borrow().iter().find(|x| source.contains(x.as_str())) {
+            return Err(format!("ERROR: 0:1: '{}' : compile error", text));
+        }
+        let shader = ShaderHandle(self.next_handle());
+        self.state.borrow_mut().shaders.insert(shader, (shader_type, source.to_owned()));
+        self.record(GlCall::CompileShader(shader));
+        Ok(shader)
+    }
+
+    fn delete_shader(&self, shader:ShaderHandle) {
+        self.state.borrow_mut().shaders.remove(&shader);
+        self.record(GlCall::DeleteShader(shader));
+    }
+
+    fn link_program(&self, vertex_shader:ShaderHandle, fragment_shader:ShaderHandle) -> Result<ProgramHandle, String> {
+        let program = ProgramHandle(self.next_handle());
+        let mut state = self.state.borrow_mut();
+        let vertex_source = state.shaders.get(&vertex_shader).ok_or_else(|| String::from("Invalid vertex shader"))?.1.clone();
+        let fragment_source = state.shaders.get(&fragment_shader).ok_or_else(|| String::from("Invalid fragment shader"))?.1.clone();
+
+        let mut attrib_locations = HashMap::new();
+        let mut attrib_columns = HashMap::new();
+        let mut next_location = 0;
+        for (glsl_type,name) in parse_declarations(&vertex_source, "in") {
+            let columns = attrib_columns_of(&glsl_type);
+            attrib_locations.insert(name.clone(), next_location);
+            attrib_columns.insert(name, columns);
+            next_location += columns;
+        }
+
+        let uniform_blocks = parse_uniform_blocks(&vertex_source).into_iter().chain(parse_uniform_blocks(&fragment_source))
+            .fold(Vec::new(), |mut acc:Vec<(String,Vec<(String,String)>)>, x| {
+                if !acc.iter().any(|y| y.0 == x.0) { acc.push(x); }
+                acc
+            });
+
+        let mut uniform_locations = HashMap::new();
+        for (_,name) in parse_declarations(&vertex_source, "uniform").into_iter().chain(parse_declarations(&fragment_source, "uniform")) {
+            if uniform_locations.contains_key(&name) { continue; }
+            let location = UniformLocation(self.next_handle());
+            state.uniform_owners.insert(location, (program, name.clone()));
+            uniform_locations.insert(name, location);
+        }
+
+        state.programs.insert(program, ProgramState {
+            vertex_source:vertex_source,
+            fragment_source:fragment_source,
+            attrib_locations:attrib_locations,
+            attrib_columns:attrib_columns,
+            uniform_locations:uniform_locations,
+            uniform_values:HashMap::new(),
+            uniform_blocks:uniform_blocks,
+            uniform_block_bindings:HashMap::new()
+        });
+        drop(state);
+        self.record(GlCall::LinkProgram(program));
+        Ok(program)
+    }
+
+    fn delete_program(&self, program:ProgramHandle) {
+        let mut state = self.state.borrow_mut();
+        state.programs.remove(&program);
+        state.uniform_owners.retain(|_,(p,_)| *p != program);
+        if state.program == Some(program) { state.program = None; }
+        drop(state);
+        self.record(GlCall::DeleteProgram(program));
+    }
+
+    fn use_program(&self, program:Option<ProgramHandle>) {
+        self.state.borrow_mut().program = program;
+        self.record(GlCall::UseProgram(program));
+    }
+
+    fn get_attrib_location(&self, program:ProgramHandle, name:&str) -> i32 {
+        self.state.borrow().programs.get(&program).and_then(|x| x.attrib_locations.get(name).copied()).unwrap_or(-1)
+    }
+
+    fn get_uniform_location(&self, program:ProgramHandle, name:&str) -> Option<UniformLocation> {
+        self.state.borrow().programs.get(&program).and_then(|x| x.uniform_locations.get(name).copied())
+    }
+
+    //every declaration counts as active, unlike gl nothing is optimized out
+    fn get_active_attribs(&self, program:ProgramHandle) -> Vec<ActiveInfo> {
+        let state = self.state.borrow();
+        let program = match state.programs.get(&program) {
+            Some(x) => x,
+            None => return Vec::new()
+        };
+        parse_declarations(&program.vertex_source, "in").into_iter().map(|(data_type,name)| active_info(name, &data_type)).collect()
+    }
+
+    fn get_active_uniforms(&self, program:ProgramHandle) -> Vec<ActiveInfo> {
+        let state = self.state.borrow();
+        let program = match state.programs.get(&program) {
+            Some(x) => x,
+            None => return Vec::new()
+        };
+        let mut result:Vec<ActiveInfo> = Vec::new();
+        for (data_type,name) in parse_declarations(&program.vertex_source, "uniform").into_iter().chain(parse_declarations(&program.fragment_source, "uniform")) {
+            if result.iter().any(|x| x.name == name) { continue; }
+            result.push(active_info(name, &data_type));
+        }
+        result
+    }
+
+    fn get_uniform_block_index(&self, program:ProgramHandle, name:&str) -> Option<u32> {
+        let state = self.state.borrow();
+        state.programs.get(&program)?.uniform_blocks.iter().position(|x| x.0 == name).map(|x| x as u32)
+    }
+
+    fn uniform_block_binding(&self, program:ProgramHandle, block_index:u32, binding:u32) {
+        if let Some(state) = self.state.borrow_mut().programs.get_mut(&program) {
+            state.uniform_block_bindings.insert(block_index, binding);
+        }
+        self.record(GlCall::UniformBlockBinding { program:program, block_index:block_index, binding:binding });
+    }
+
+    fn uniform1f(&self, location:UniformLocation, x:f32) {
+        self.set_uniform(location, UniformValue::Float(x));
+    }
+
+    fn uniform2f(&self, location:UniformLocation, x:f32, y:f32) {
+        self.set_uniform(location, UniformValue::Vec2([x, y]));
+    }
+
+    fn uniform3f(&self, location:UniformLocation, x:f32, y:f32, z:f32) {
+        self.set_uniform(location, UniformValue::Vec3([x, y, z]));
+    }
+
+    fn uniform4f(&self, location:UniformLocation, x:f32, y:f32, z:f32, w:f32) {
+        self.set_uniform(location, UniformValue::Vec4([x, y, z, w]));
+    }
+
+    fn uniform1i(&self, location:UniformLocation, x:i32) {
+        self.set_uniform(location, UniformValue::Int(x));
+    }
+
+    fn uniform1ui(&self, location:UniformLocation, x:u32) {
+        self.set_uniform(location, UniformValue::UInt(x));
+    }
+
+    fn uniform1fv(&self, location:UniformLocation, data:&[f32]) {
+        self.set_uniform(location, UniformValue::FloatArray(data.to_vec()));
+    }
+
+    fn uniform2fv(&self, location:UniformLocation, data:&[f32]) {
+        self.set_uniform(location, UniformValue::FloatArray(data.to_vec()));
+    }
+
+    fn uniform3fv(&self, location:UniformLocation, data:&[f32]) {
+        self.set_uniform(location, UniformValue::FloatArray(data.to_vec()));
+    }
+
+    fn uniform4fv(&self, location:UniformLocation, data:&[f32]) {
+        self.set_uniform(location, UniformValue::FloatArray(data.to_vec()));
+    }
+
+    fn uniform1iv(&self, location:UniformLocation, data:&[i32]) {
+        self.set_uniform(location, UniformValue::IntArray(data.to_vec()));
+    }
+
+    fn uniform_matrix3fv(&self, location:UniformLocation, _transpose:bool, data:&[f32]) {
+        match data.len() {
+            9 => {
+                let mut matrix = [0f32; 9];
+                matrix.copy_from_slice(data);
+                self.set_uniform(location, UniformValue::Matrix3(matrix));
+            },
+            _ => self.set_uniform(location, UniformValue::FloatArray(data.to_vec()))
+        }
+    }
+
+    fn uniform_matrix4fv(&self, location:UniformLocation, _transpose:bool, data:&[f32]) {
+        match data.len() {
+            16 => {
+                let mut matrix = [0f32; 16];
+                matrix.copy_from_slice(data);
+                self.set_uniform(location, UniformValue::Matrix4(matrix));
+            },
+            _ => self.set_uniform(location, UniformValue::FloatArray(data.to_vec()))
+        }
+    }
+
+    fn create_texture(&self) -> TextureHandle {
+        let texture = TextureHandle(self.next_handle());
+        self.state.borrow_mut().textures.insert(texture, TextureState {
+            width:0,
+            height:0,
+            format:WebGl2RenderingContext::RGBA,
+            pixels:Vec::new(),
+            parameters:HashMap::new(),
+            float_parameters:HashMap::new()
+        });
+        self.record(GlCall::CreateTexture(texture));
+        texture
+    }
+
+    fn delete_texture(&self, texture:TextureHandle) {
+        let mut state = self.state.borrow_mut();
+        state.textures.remove(&texture);
+        state.texture_units.retain(|_,x| *x != texture);
+        drop(state);
+        self.record(GlCall::DeleteTexture(texture));
+    }
+
+    fn active_texture(&self, unit:u32) {
+        self.state.borrow_mut().active_texture = unit - WebGl2RenderingContext::TEXTURE0;
+        self.record(GlCall::ActiveTexture(unit));
+    }
+
+    fn bind_texture(&self, target:u32, texture:Option<TextureHandle>) {
+        let mut state = self.state.borrow_mut();
+        let unit = state.active_texture;
+        match texture {
+            Some(texture) => state.texture_units.insert(unit, texture),
+            None => state.texture_units.remove(&unit)
+        };
+        drop(state);
+        self.record(GlCall::BindTexture(target, texture));
+    }
+
+    fn tex_parameteri(&self, target:u32, pname:u32, param:i32) {
+        self.state.borrow_mut().bound_texture_mut().parameters.insert(pname, param);
+        self.record(GlCall::TexParameteri(target, pname, param));
+    }
+
+    fn tex_parameterf(&self, target:u32, pname:u32, param:f32) {
+        self.state.borrow_mut().bound_texture_mut().float_parameters.insert(pname, param);
+        self.record(GlCall::TexParameterf(target, pname, param));
+    }
+
+    fn tex_image_2d(&self, target:u32, level:i32, _internal_format:i32, width:i32, height:i32, format:u32, _data_type:u32, pixels:Option<&[u8]>) {
+        if level == 0 {
+            let mut state = self.state.borrow_mut();
+            let flip_y = state.unpack_flip_y;
+            let texture = state.bound_texture_mut();
+            texture.width = width;
+            texture.height = height;
+            texture.format = format;
+            texture.pixels = match pixels {
+                Some(pixels) => unpack_rows(pixels, height as usize, width as usize * bytes_per_pixel(format), flip_y),
+                None => vec![0; width as usize * height as usize * bytes_per_pixel(format)]
+            };
+        }
+        self.record(GlCall::TexImage2D { target:target, level:level, width:width, height:height, format:format });
+    }
+
+    fn tex_sub_image_2d(&self, target:u32, level:i32, x:i32, y:i32, width:i32, height:i32, format:u32, _data_type:u32, pixels:&[u8]) {
+        if level == 0 {
+            let mut state = self.state.borrow_mut();
+            let flip_y = state.unpack_flip_y;
+            let texture = state.bound_texture_mut();
+            let bpp = bytes_per_pixel(format);
+            let row = width as usize * bpp;
+            let pixels = unpack_rows(pixels, height as usize, row, flip_y);
+            for r in 0..height as usize {
+                let dst = ((y as usize + r) * texture.width as usize + x as usize) * bpp;
+                texture.pixels[dst..dst+row].copy_from_slice(&pixels[r*row..(r+1)*row]);
+            }
+        }
+        self.record(GlCall::TexSubImage2D { target:target, level:level, x:x, y:y, width:width, height:height });
+    }
+
+    fn tex_sub_image_2d_with_image(&self, target:u32, level:i32, x:i32, y:i32, _format:u32, _data_type:u32, _image:&HtmlImageElement) {
+        //image elements cannot be read outside of a browser, only the upload itself is recorded
+        self.record(GlCall::TexSubImage2D { target:target, level:level, x:x, y:y, width:-1, height:-1 });
+    }
+
+    //copies row by row from the bound framebuffer's color texture, the default framebuffer has no recorded pixels and copies 0
+    fn copy_tex_sub_image_2d(&self, target:u32, level:i32, x_offset:i32, y_offset:i32, x:i32, y:i32, width:i32, height:i32) {
+        if level == 0 {
+            let mut state = self.state.borrow_mut();
+            let src = state.framebuffer.and_then(|x| state.framebuffers[&x].color);
+            let texture = state.texture_units[&state.active_texture];
+            let bpp = bytes_per_pixel(state.textures[&texture].format);
+            let row = width as usize * bpp;
+            for r in 0..height as usize {
+                let pixels = match src.and_then(|x| state.textures.get(&x)) {
+                    Some(src) => {
+                        let start = ((y as usize + r) * src.width as usize + x as usize) * bpp;
+                        src.pixels[start..start+row].to_vec()
+                    },
+                    None => vec![0; row]
+                };
+                let dst = state.textures.get_mut(&texture).expect("Bound texture was deleted");
+                let start = ((y_offset as usize + r) * dst.width as usize + x_offset as usize) * bpp;
+                dst.pixels[start..start+row].copy_from_slice(&pixels);
+            }
+        }
+        self.record(GlCall::CopyTexSubImage2D { target:target, level:level, x_offset:x_offset, y_offset:y_offset, x:x, y:y, width:width, height:height });
+    }
+
+    //levels above 0 aren't kept, only the call is recorded
+    fn generate_mipmap(&self, target:u32) {
+        self.record(GlCall::GenerateMipmap(target));
+    }
+
+    fn max_anisotropy(&self) -> Option<f32> {
+        self.max_anisotropy.get()
+    }
+
+    fn create_framebuffer(&self) -> FramebufferHandle {
+        let framebuffer = FramebufferHandle(self.next_handle());
+        self.state.borrow_mut().framebuffers.insert(framebuffer, FramebufferState::default());
+        self.record(GlCall::CreateFramebuffer(framebuffer));
+        framebuffer
+    }
+
+    fn delete_framebuffer(&self, framebuffer:FramebufferHandle) {
+        let mut state = self.state.borrow_mut();
+        state.framebuffers.remove(&framebuffer);
+        if state.framebuffer == Some(framebuffer) {
+            state.framebuffer = None;
+        }
+        drop(state);
+        self.record(GlCall::DeleteFramebuffer(framebuffer));
+    }
+
+    fn bind_framebuffer(&self, target:u32, framebuffer:Option<FramebufferHandle>) {
+        self.state.borrow_mut().framebuffer = framebuffer;
+        self.record(GlCall::BindFramebuffer(target, framebuffer));
+    }
+
+    fn framebuffer_texture_2d(&self, _target:u32, attachment:u32, _tex_target:u32, texture:Option<TextureHandle>, level:i32) {
+        if attachment == WebGl2RenderingContext::COLOR_ATTACHMENT0 {
+            self.state.borrow_mut().bound_framebuffer_mut().color = texture;
+        }
+        self.record(GlCall::FramebufferTexture2D { attachment:attachment, texture:texture, level:level });
+    }
+
+    fn framebuffer_renderbuffer(&self, _target:u32, attachment:u32, _renderbuffer_target:u32, renderbuffer:Option<RenderbufferHandle>) {
+        if attachment == WebGl2RenderingContext::DEPTH_ATTACHMENT {
+            self.state.borrow_mut().bound_framebuffer_mut().depth = renderbuffer;
+        }
+        self.record(GlCall::FramebufferRenderbuffer { attachment:attachment, renderbuffer:renderbuffer });
+    }
+
+    fn check_framebuffer_status(&self, _target:u32) -> u32 {
+        self.state.borrow().framebuffer_status()
+    }
+
+    //reads the bound framebuffer's color texture, the default framebuffer has no recorded pixels and reads as 0
+    fn read_pixels(&self, x:i32, y:i32, width:i32, height:i32, format:u32, data_type:u32, dst:&mut [u8]) {
+        let state = self.state.borrow();
+        let color = state.framebuffer.and_then(|x| state.framebuffers[&x].color).and_then(|x| state.textures.get(&x));
+        match color {
+            Some(color) => read_pixel_rect(&color.pixels, color.format, color.width, x, y, width, height, format, dst),
+            None => dst.fill(0)
+        }
+        drop(state);
+        self.record(GlCall::ReadPixels { x:x, y:y, width:width, height:height, format:format, data_type:data_type });
+    }
+
+    fn create_renderbuffer(&self) -> RenderbufferHandle {
+        let renderbuffer = RenderbufferHandle(self.next_handle());
+        self.state.borrow_mut().renderbuffers.insert(renderbuffer, RenderbufferState { internal_format:0, width:0, height:0 });
+        self.record(GlCall::CreateRenderbuffer(renderbuffer));
+        renderbuffer
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer:RenderbufferHandle) {
+        let mut state = self.state.borrow_mut();
+        state.renderbuffers.remove(&renderbuffer);
+        if state.renderbuffer == Some(renderbuffer) {
+            state.renderbuffer = None;
+        }
+        drop(state);
+        self.record(GlCall::DeleteRenderbuffer(renderbuffer));
+    }
+
+    fn bind_renderbuffer(&self, target:u32, renderbuffer:Option<RenderbufferHandle>) {
+        self.state.borrow_mut().renderbuffer = renderbuffer;
+        self.record(GlCall::BindRenderbuffer(target, renderbuffer));
+    }
+
+    fn renderbuffer_storage(&self, _target:u32, internal_format:u32, width:i32, height:i32) {
+        let mut state = self.state.borrow_mut();
+        let renderbuffer = state.renderbuffer.expect("No renderbuffer bound");
+        state.renderbuffers.insert(renderbuffer, RenderbufferState { internal_format:internal_format, width:width, height:height });
+        drop(state);
+        self.record(GlCall::RenderbufferStorage { internal_format:internal_format, width:width, height:height });
+    }
+
+    fn draw_elements(&self, mode:u32, count:i32, index_type:u32, offset:i32) {
+        self.record(GlCall::DrawElements { mode:mode, count:count, index_type:index_type, offset:offset });
+    }
+
+    fn draw_elements_instanced(&self, mode:u32, count:i32, index_type:u32, offset:i32, instance_count:i32) {
+        self.record(GlCall::DrawElementsInstanced { mode:mode, count:count, index_type:index_type, offset:offset, instance_count:instance_count });
+    }
+}
@@ -0,0 +1,1033 @@
+use std::{cell::RefCell, collections::HashMap, fmt::{self, Debug}, fs::File, io::BufWriter, path::{Path, PathBuf}};
+
+use cgmath::{Matrix4, Vector2, Vector4, Zero};
+use web_sys::{HtmlImageElement, WebGl2RenderingContext};
+
+use super::{GraphicsBackend, BufferHandle, VertexArrayHandle, TextureHandle, ShaderHandle, ProgramHandle, UniformLocation, ActiveInfo, FramebufferHandle, RenderbufferHandle, recording::{RecordingBackend, RecordedState, ProgramState, VertexArrayState, TextureState, UniformValue, bytes_per_pixel, read_pixel_rect, std140_offsets}};
+use crate::engine::render::pick::PICK_ID;
+
+pub mod shaders;
+
+//Backend that rasterizes on the cpu, everything except clears and draws is handled by a recording backend
+//and its state is read back when drawing. Glsl cannot be run here, so every program drawn with needs a
+//SoftwareShader that reimplements it. Only TRIANGLES are rasterized, with the recorded depth func and no clipping
+//other than dropping triangles behind the camera. Offscreen framebuffers draw into the pixels of their recorded
+//color texture. Integer color textures are only drawn into by picking programs, which write their pick id.
+
+//directory checked in golden images are kept in, relative to the crate root
+pub const GOLDEN_DIR:&str = "src/tests/golden";
+//frames that didn't match their golden image are written here, out of the source tree
+pub const ACTUAL_DIR:&str = "target/golden";
+
+pub trait SoftwareShader: Debug {
+    //whether this shader reimplements the program linked from these sources
+    fn matches(&self, vertex_source:&str, fragment_source:&str) -> bool;
+    fn vertex(&self, ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput;
+    //None discards the fragment
+    fn fragment(&self, ctx:&ShaderContext, varyings:&[f32]) -> Option<Vector4<f32>>;
+}
+
+#[derive(Clone, Debug)]
+pub struct VertexOutput {
+    pub position:Vector4<f32>,
+    pub varyings:Vec<f32>
+}
+
+//attribute values of a single vertex, missing components are filled like gl does (0,0,0,1).
+//Matrix attributes have one value per column
+#[derive(Debug)]
+pub struct VertexInputs {
+    values:HashMap<String,Vec<[f32; 4]>>
+}
+
+impl VertexInputs {
+    pub fn get(&self, name:&str) -> [f32; 4] {
+        self.column(name, 0)
+    }
+
+    pub fn column(&self, name:&str, column:usize) -> [f32; 4] {
+        self.values.get(name).and_then(|x| x.get(column)).copied().unwrap_or([0.0,0.0,0.0,1.0])
+    }
+
+    pub fn matrix4(&self, name:&str) -> Matrix4<f32> {
+        Matrix4::from_cols(self.column(name, 0).into(), self.column(name, 1).into(), self.column(name, 2).into(), self.column(name, 3).into())
+    }
+
+    pub fn float(&self, name:&str) -> f32 {
+        self.get(name)[0]
+    }
+
+    pub fn vec2(&self, name:&str) -> Vector2<f32> {
+        let v = self.get(name);
+        Vector2::new(v[0], v[1])
+    }
+
+    pub fn vec4(&self, name:&str) -> Vector4<f32> {
+        self.get(name).into()
+    }
+}
+
+pub struct ShaderContext<'a> {
+    state:&'a RecordedState,
+    program:&'a ProgramState
+}
+
+impl<'a> ShaderContext<'a> {
+    pub fn vertex_source(&self) -> &str {
+        &self.program.vertex_source
+    }
+
+    pub fn uniform(&self, name:&str) -> Option<&UniformValue> {
+        self.program.uniform_values.get(name)
+    }
+
+    //bytes of a uniform block member onwards, in the buffer bound to the block's binding point
+    fn block_member(&self, name:&str) -> Option<&[u8]> {
+        for (index, (_, members)) in self.program.uniform_blocks.iter().enumerate() {
+            let member = match members.iter().position(|x| x.1 == name) {
+                Some(x) => x,
+                None => continue
+            };
+            let binding = self.program.uniform_block_bindings.get(&(index as u32)).copied().unwrap_or(0);
+            let buffer = self.state.indexed_buffer_bindings.get(&(WebGl2RenderingContext::UNIFORM_BUFFER, binding))?;
+            return self.state.buffers.get(buffer).map(|x| &x[std140_offsets(members)[member]..]);
+        }
+        None
+    }
+
+    fn block_floats(&self, name:&str, count:usize) -> Option<Vec<f32>> {
+        let bytes = self.block_member(name)?;
+        if bytes.len() < count * 4 { return None; }
+        Some(bytes.chunks_exact(4).take(count).map(|x| f32::from_ne_bytes([x[0], x[1], x[2], x[3]])).collect())
+    }
+
+    //unset uniforms are zero like in gl
+    pub fn float(&self, name:&str) -> f32 {
+        match self.uniform(name) {
+            Some(UniformValue::Float(x)) => *x,
+            _ => self.block_floats(name, 1).map(|x| x[0]).unwrap_or(0.0)
+        }
+    }
+
+    pub fn vec2(&self, name:&str) -> Vector2<f32> {
+        match self.uniform(name) {
+            Some(UniformValue::Vec2(x)) => Vector2::new(x[0], x[1]),
+            _ => self.block_floats(name, 2).map(|x| Vector2::new(x[0], x[1])).unwrap_or(Vector2::zero())
+        }
+    }
+
+    pub fn matrix4(&self, name:&str) -> Matrix4<f32> {
+        let m = match self.uniform(name) {
+            Some(UniformValue::Matrix4(m)) => m.to_vec(),
+            _ => match self.block_floats(name, 16) {
+                Some(m) => m,
+                None => return Matrix4::zero()
+            }
+        };
+        Matrix4::new(
+            m[0], m[1], m[2], m[3],
+            m[4], m[5], m[6], m[7],
+            m[8], m[9], m[10], m[11],
+            m[12], m[13], m[14], m[15]
+        )
+    }
+
+    //samples the texture bound to the unit the sampler uniform points at, filtered with its mag filter
+    pub fn sample(&self, sampler:&str, uv:Vector2<f32>) -> Vector4<f32> {
+        let unit = match self.uniform(sampler) {
+            Some(UniformValue::Int(x)) => *x as u32,
+            _ => 0
+        };
+        let texture = match self.state.texture_units.get(&unit).and_then(|x| self.state.textures.get(x)) {
+            Some(texture) if texture.width > 0 && texture.height > 0 => texture,
+            _ => return Vector4::new(0.0, 0.0, 0.0, 1.0)
+        };
+
+        let filter = texture.parameters.get(&WebGl2RenderingContext::TEXTURE_MAG_FILTER).copied().unwrap_or(WebGl2RenderingContext::LINEAR as i32);
+        let u = uv.x * texture.width as f32;
+        let v = uv.y * texture.height as f32;
+        if filter == WebGl2RenderingContext::NEAREST as i32 {
+            return texel(texture, u.floor() as i32, v.floor() as i32);
+        }
+
+        let (u, v) = (u - 0.5, v - 0.5);
+        let (x, y) = (u.floor() as i32, v.floor() as i32);
+        let (fx, fy) = (u - u.floor(), v - v.floor());
+        let bottom = texel(texture, x, y) * (1.0 - fx) + texel(texture, x+1, y) * fx;
+        let top = texel(texture, x, y+1) * (1.0 - fx) + texel(texture, x+1, y+1) * fx;
+        bottom * (1.0 - fy) + top * fy
+    }
+}
+
+fn wrap(i:i32, size:i32, mode:i32) -> i32 {
+    match mode as u32 {
+        WebGl2RenderingContext::CLAMP_TO_EDGE => i.clamp(0, size - 1),
+        WebGl2RenderingContext::MIRRORED_REPEAT => {
+            let m = i.rem_euclid(size * 2);
+            if m < size { m } else { size * 2 - 1 - m }
+        },
+        _ => i.rem_euclid(size)
+    }
+}
+
+fn texel(texture:&TextureState, x:i32, y:i32) -> Vector4<f32> {
+    let wrap_s = texture.parameters.get(&WebGl2RenderingContext::TEXTURE_WRAP_S).copied().unwrap_or(WebGl2RenderingContext::REPEAT as i32);
+    let wrap_t = texture.parameters.get(&WebGl2RenderingContext::TEXTURE_WRAP_T).copied().unwrap_or(WebGl2RenderingContext::REPEAT as i32);
+    let x = wrap(x, texture.width, wrap_s) as usize;
+    let y = wrap(y, texture.height, wrap_t) as usize;
+
+    let bpp = bytes_per_pixel(texture.format);
+    let i = (y * texture.width as usize + x) * bpp;
+    let p = &texture.pixels[i..i+bpp];
+    let a = if bpp == 4 { p[3] as f32 / 255.0 } else { 1.0 };
+    Vector4::new(p[0] as f32 / 255.0, p[1] as f32 / 255.0, p[2] as f32 / 255.0, a)
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    Io(std::io::Error),
+    Encoding(png::EncodingError),
+    Decoding(png::DecodingError),
+    //the golden image does not exist, UPDATE_GOLDEN writes it
+    Missing(PathBuf),
+    Format { path:PathBuf, color_type:png::ColorType, bit_depth:png::BitDepth },
+    SizeMismatch { expected:(u32,u32), actual:(u32,u32) },
+    //the current frame is written to ACTUAL_DIR as <name>.actual.png
+    Mismatch { mismatched_pixels:usize, max_difference:u8, actual_path:PathBuf }
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Encoding(e) => write!(f, "png encoding error: {}", e),
+            Self::Decoding(e) => write!(f, "png decoding error: {}", e),
+            Self::Missing(path) => write!(f, "golden image {} does not exist, run with UPDATE_GOLDEN=1 to write it from the current frame", path.display()),
+            Self::Format { path, color_type, bit_depth } => write!(f, "golden image {} is {:?} {:?}, expected 8 bit rgba", path.display(), color_type, bit_depth),
+            Self::SizeMismatch { expected, actual } => write!(f, "golden image is {:?}, frame is {:?}", expected, actual),
+            Self::Mismatch { mismatched_pixels, max_difference, actual_path } => write!(f, "{} pixels differ by up to {}, frame written to {}", mismatched_pixels, max_difference, actual_path.display())
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {}
+
+impl From<std::io::Error> for GoldenError {
+    fn from(e:std::io::Error) -> Self { Self::Io(e) }
+}
+
+impl From<png::EncodingError> for GoldenError {
+    fn from(e:png::EncodingError) -> Self { Self::Encoding(e) }
+}
+
+impl From<png::DecodingError> for GoldenError {
+    fn from(e:png::DecodingError) -> Self { Self::Decoding(e) }
+}
+
+#[derive(Debug)]
+struct Framebuffer {
+    width:u32,
+    height:u32,
+    //rgba rows starting at the bottom like gl, or one u32 per pixel for integer targets
+    color:Vec<u8>,
+    depth:Vec<f32>,
+    integer:bool
+}
+
+impl Framebuffer {
+    fn new(width:u32, height:u32) -> Self {
+        Self {
+            width:width,
+            height:height,
+            color:vec![0; (width * height * 4) as usize],
+            depth:vec![1.0; (width * height) as usize],
+            integer:false
+        }
+    }
+}
+
+struct ScreenVertex<'a> {
+    x:f32,
+    y:f32,
+    z:f32,
+    inv_w:f32,
+    varyings:&'a [f32]
+}
+
+fn edge(a:&ScreenVertex, b:&ScreenVertex, x:f32, y:f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+//pixels exactly on an edge belong to the triangle if it is a top or left edge, so shared edges are only drawn once
+fn covers(w:f32, a:&ScreenVertex, b:&ScreenVertex) -> bool {
+    w > 0.0 || (w == 0.0 && ((a.y == b.y && b.x < a.x) || b.y < a.y))
+}
+
+fn depth_passes(func:u32, z:f32, depth:f32) -> bool {
+    match func {
+        WebGl2RenderingContext::NEVER => false,
+        WebGl2RenderingContext::LESS => z < depth,
+        WebGl2RenderingContext::EQUAL => z == depth,
+        WebGl2RenderingContext::LEQUAL => z <= depth,
+        WebGl2RenderingContext::GREATER => z > depth,
+        WebGl2RenderingContext::NOTEQUAL => z != depth,
+        WebGl2RenderingContext::GEQUAL => z >= depth,
+        _ => true
+    }
+}
+
+fn blend_factor(factor:u32, src:Vector4<f32>, dst:Vector4<f32>, constant:Vector4<f32>) -> Vector4<f32> {
+    let one = Vector4::new(1.0, 1.0, 1.0, 1.0);
+    match factor {
+        WebGl2RenderingContext::ZERO => Vector4::new(0.0, 0.0, 0.0, 0.0),
+        WebGl2RenderingContext::SRC_COLOR => src,
+        WebGl2RenderingContext::ONE_MINUS_SRC_COLOR => one - src,
+        WebGl2RenderingContext::DST_COLOR => dst,
+        WebGl2RenderingContext::ONE_MINUS_DST_COLOR => one - dst,
+        WebGl2RenderingContext::SRC_ALPHA => one * src.w,
+        WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA => one * (1.0 - src.w),
+        WebGl2RenderingContext::DST_ALPHA => one * dst.w,
+        WebGl2RenderingContext::ONE_MINUS_DST_ALPHA => one * (1.0 - dst.w),
+        WebGl2RenderingContext::CONSTANT_COLOR => constant,
+        WebGl2RenderingContext::ONE_MINUS_CONSTANT_COLOR => one - constant,
+        WebGl2RenderingContext::CONSTANT_ALPHA => one * constant.w,
+        WebGl2RenderingContext::ONE_MINUS_CONSTANT_ALPHA => one * (1.0 - constant.w),
+        _ => one
+    }
+}
+
+fn component_size(data_type:u32) -> usize {
+    match data_type {
+        WebGl2RenderingContext::BYTE | WebGl2RenderingContext::UNSIGNED_BYTE => 1,
+        WebGl2RenderingContext::SHORT | WebGl2RenderingContext::UNSIGNED_SHORT => 2,
+        _ => 4
+    }
+}
+
+fn read_component(bytes:&[u8], data_type:u32, normalized:bool) -> f32 {
+    match data_type {
+        WebGl2RenderingContext::BYTE => {
+            let x = bytes[0] as i8 as f32;
+            if normalized { (x / 127.0).max(-1.0) } else { x }
+        },
+        WebGl2RenderingContext::UNSIGNED_BYTE => {
+            let x = bytes[0] as f32;
+            if normalized { x / 255.0 } else { x }
+        },
+        WebGl2RenderingContext::SHORT => {
+            let x = i16::from_ne_bytes([bytes[0], bytes[1]]) as f32;
+            if normalized { (x / 32767.0).max(-1.0) } else { x }
+        },
+        WebGl2RenderingContext::UNSIGNED_SHORT => {
+            let x = u16::from_ne_bytes([bytes[0], bytes[1]]) as f32;
+            if normalized { x / 65535.0 } else { x }
+        },
+        WebGl2RenderingContext::INT => i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32,
+        WebGl2RenderingContext::UNSIGNED_INT => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32,
+        _ => f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+}
+
+fn read_indicies(buffer:&[u8], index_type:u32, offset:i32, count:i32) -> Vec<u32> {
+    let size = component_size(index_type);
+    let start = offset as usize;
+    buffer[start..start + count as usize * size].chunks_exact(size).map(|x| match index_type {
+        WebGl2RenderingContext::UNSIGNED_BYTE => x[0] as u32,
+        WebGl2RenderingContext::UNSIGNED_SHORT => u16::from_ne_bytes([x[0], x[1]]) as u32,
+        _ => u32::from_ne_bytes([x[0], x[1], x[2], x[3]])
+    }).collect()
+}
+
+fn fetch_vertex(state:&RecordedState, program:&ProgramState, vao:&VertexArrayState, index:u32, instance:i32) -> VertexInputs {
+    let mut values = HashMap::new();
+    for (name, location) in program.attrib_locations.iter() {
+        let mut columns = Vec::new();
+        for column in 0..program.attrib_columns[name] {
+            let attrib = match vao.attribs.get(&((*location + column) as u32)) {
+                Some(attrib) if attrib.enabled => attrib,
+                _ => break
+            };
+            let buffer = &state.buffers[&attrib.buffer.expect("Enabled vertex attribute has no buffer")];
+            let size = component_size(attrib.data_type);
+            let stride = if attrib.stride == 0 { attrib.size as usize * size } else { attrib.stride as usize };
+            let element = if attrib.divisor == 0 { index as usize } else { instance as usize / attrib.divisor as usize };
+            let start = attrib.offset as usize + element * stride;
+
+            let mut value = [0.0, 0.0, 0.0, 1.0];
+            for c in 0..attrib.size as usize {
+                value[c] = read_component(&buffer[start + c*size..], attrib.data_type, attrib.normalized);
+            }
+            columns.push(value);
+        }
+        if !columns.is_empty() { values.insert(name.clone(), columns); }
+    }
+    VertexInputs { values:values }
+}
+
+pub struct SoftwareBackend {
+    recording:RecordingBackend,
+    shaders:RefCell<Vec<Box<dyn SoftwareShader>>>,
+    framebuffer:RefCell<Framebuffer>,
+    //depth of offscreen framebuffers by their depth renderbuffer
+    depth_buffers:RefCell<HashMap<RenderbufferHandle,Vec<f32>>>
+}
+
+impl Debug for SoftwareBackend {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        let framebuffer = self.framebuffer.borrow();
+        f.debug_struct("SoftwareBackend")
+            .field("recording", &self.recording)
+            .field("shaders", &self.shaders.borrow())
+            .field("size", &(framebuffer.width, framebuffer.height))
+            .finish()
+    }
+}
+
+impl SoftwareBackend {
+    //starts with software versions of the built in render types and effects registered
+    pub fn new(width:u32, height:u32) -> Self {
+        let backend = Self {
+            recording:RecordingBackend::new(width, height),
+            shaders:RefCell::new(Vec::new()),
+            framebuffer:RefCell::new(Framebuffer::new(width, height)),
+            depth_buffers:RefCell::new(HashMap::new())
+        };
+        backend.register_shader(Box::new(shaders::ColorShader));
+        backend.register_shader(Box::new(shaders::ImageShader));
+        backend.register_shader(Box::new(shaders::LineShader));
+        backend.register_shader(Box::new(shaders::QuadraticBezierShader));
+        backend.register_shader(Box::new(shaders::FallbackShader));
+        backend.register_shader(Box::new(shaders::BlurShader));
+        backend.register_shader(Box::new(shaders::ColorGradingShader));
+        backend.register_shader(Box::new(shaders::FxaaShader));
+        backend.register_shader(Box::new(shaders::VignetteShader));
+        backend
+    }
+
+    //shaders registered later take priority when several match a program
+    pub fn register_shader(&self, shader:Box<dyn SoftwareShader>) {
+        self.shaders.borrow_mut().push(shader);
+    }
+
+    pub fn recording(&self) -> &RecordingBackend {
+        &self.recording
+    }
+
+    //clears the frame to transparent black
+    pub fn set_drawable_size(&self, width:u32, height:u32) {
+        self.recording.set_drawable_size(width, height);
+        *self.framebuffer.borrow_mut() = Framebuffer::new(width, height);
+    }
+
+    //rgba rows starting at the top, the way images are stored
+    pub fn pixels(&self) -> Vec<u8> {
+        let framebuffer = self.framebuffer.borrow();
+        framebuffer.color.chunks_exact(framebuffer.width as usize * 4).rev().flatten().copied().collect()
+    }
+
+    //x and y from the top left
+    pub fn pixel(&self, x:u32, y:u32) -> [u8; 4] {
+        let framebuffer = self.framebuffer.borrow();
+        let i = (((framebuffer.height - 1 - y) * framebuffer.width + x) * 4) as usize;
+        [framebuffer.color[i], framebuffer.color[i+1], framebuffer.color[i+2], framebuffer.color[i+3]]
+    }
+
+    pub fn write_png(&self, path:impl AsRef<Path>) -> Result<(), GoldenError> {
+        let (width, height) = self.recording.drawable_size();
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels())?;
+        Ok(())
+    }
+
+    //compares the frame with GOLDEN_DIR/<name>.png, allowing each channel to differ by tolerance.
+    //setting UPDATE_GOLDEN writes the current frame as the golden image instead, missing ones are errors otherwise
+    pub fn compare_golden(&self, name:&str, tolerance:u8) -> Result<(), GoldenError> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(GOLDEN_DIR);
+        let path = dir.join(format!("{}.png", name));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(&dir)?;
+            return self.write_png(&path);
+        }
+        if !path.exists() {
+            return Err(GoldenError::Missing(path));
+        }
+
+        let mut reader = png::Decoder::new(File::open(&path)?).read_info()?;
+        let mut golden = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut golden)?;
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            return Err(GoldenError::Format { path:path, color_type:info.color_type, bit_depth:info.bit_depth });
+        }
+        let size = self.recording.drawable_size();
+        if (info.width, info.height) != size {
+            return Err(GoldenError::SizeMismatch { expected:(info.width, info.height), actual:size });
+        }
+
+        let pixels = self.pixels();
+        let mut mismatched_pixels = 0;
+        let mut max_difference = 0;
+        for (a, b) in pixels.chunks_exact(4).zip(golden.chunks_exact(4)) {
+            let difference = a.iter().zip(b).map(|(x, y)| x.abs_diff(*y)).max().unwrap_or(0);
+            if difference > tolerance { mismatched_pixels += 1; }
+            max_difference = max_difference.max(difference);
+        }
+        if mismatched_pixels == 0 { return Ok(()); }
+
+        let actual_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(ACTUAL_DIR);
+        std::fs::create_dir_all(&actual_dir)?;
+        let actual_path = actual_dir.join(format!("{}.actual.png", name));
+        self.write_png(&actual_path)?;
+        Err(GoldenError::Mismatch { mismatched_pixels:mismatched_pixels, max_difference:max_difference, actual_path:actual_path })
+    }
+
+    //copies the bound offscreen framebuffer out of the recorded state, None when the default one is bound
+    fn take_target(&self) -> Option<Framebuffer> {
+        let state = self.recording.state();
+        let framebuffer = &state.framebuffers[&state.framebuffer?];
+        let color = &state.textures[&framebuffer.color.expect("Drawing to a framebuffer without a color attachment")];
+        assert_eq!(bytes_per_pixel(color.format), 4, "Software backend only draws to RGBA textures");
+        let (width, height) = (color.width as u32, color.height as u32);
+        let size = (width * height) as usize;
+        let depth = framebuffer.depth.and_then(|x| self.depth_buffers.borrow_mut().remove(&x))
+            .filter(|x| x.len() == size)
+            .unwrap_or_else(|| vec![1.0; size]);
+        Some(Framebuffer { width:width, height:height, color:color.pixels.clone(), depth:depth, integer:color.format == WebGl2RenderingContext::RED_INTEGER })
+    }
+
+    fn store_target(&self, target:Framebuffer) {
+        let mut state = self.recording.state_mut();
+        let framebuffer = state.framebuffers[&state.framebuffer.expect("Framebuffer unbound while drawing")].clone();
+        if let Some(color) = framebuffer.color.and_then(|x| state.textures.get_mut(&x)) {
+            color.pixels = target.color;
+        }
+        if let Some(depth) = framebuffer.depth {
+            self.depth_buffers.borrow_mut().insert(depth, target.depth);
+        }
+    }
+
+    //runs f on whichever framebuffer is bound
+    fn with_target(&self, f:impl FnOnce(&mut Framebuffer)) {
+        match self.take_target() {
+            Some(mut target) => {
+                f(&mut target);
+                self.store_target(target);
+            },
+            None => f(&mut self.framebuffer.borrow_mut())
+        }
+    }
+
+    fn clear_framebuffer(&self, framebuffer:&mut Framebuffer, mask:u32) {
+        let state = self.recording.state();
+        if mask & WebGl2RenderingContext::COLOR_BUFFER_BIT != 0 {
+            let c = state.clear_color;
+            let color = [to_u8(c[0]), to_u8(c[1]), to_u8(c[2]), to_u8(c[3])];
+            for p in framebuffer.color.chunks_exact_mut(4) {
+                p.copy_from_slice(&color);
+            }
+        }
+        if mask & WebGl2RenderingContext::DEPTH_BUFFER_BIT != 0 {
+            framebuffer.depth.iter_mut().for_each(|x| *x = 1.0);
+        }
+    }
+
+    fn rasterize(&self, framebuffer:&mut Framebuffer, mode:u32, count:i32, index_type:u32, offset:i32, instance_count:i32) {
+        assert_eq!(mode, WebGl2RenderingContext::TRIANGLES, "Software backend only rasterizes TRIANGLES");
+        let state = self.recording.state();
+        let program = state.program.and_then(|x| state.programs.get(&x)).expect("Draw without a program in use");
+        let shaders = self.shaders.borrow();
+        let shader = shaders.iter().rev().find(|x| x.matches(&program.vertex_source, &program.fragment_source))
+            .expect("No software shader registered for the program in use");
+        let vao = state.current_vertex_array();
+        let element_buffer = &state.buffers[&vao.element_buffer.expect("Draw without an element buffer")];
+        let indicies = read_indicies(element_buffer, index_type, offset, count);
+        let ctx = ShaderContext { state:&state, program:program };
+
+        for instance in 0..instance_count {
+            let mut outputs:HashMap<u32,VertexOutput> = HashMap::new();
+            for triangle in indicies.chunks_exact(3) {
+                for i in triangle {
+                    if !outputs.contains_key(i) {
+                        outputs.insert(*i, shader.vertex(&ctx, &fetch_vertex(&state, program, vao, *i, instance)));
+                    }
+                }
+                draw_triangle(framebuffer, &state, shader.as_ref(), &ctx, [&outputs[&triangle[0]], &outputs[&triangle[1]], &outputs[&triangle[2]]], instance);
+            }
+        }
+    }
+}
+
+fn draw_triangle(framebuffer:&mut Framebuffer, state:&RecordedState, shader:&dyn SoftwareShader, ctx:&ShaderContext, verticies:[&VertexOutput; 3], instance:i32) {
+    let (vx, vy, vw, vh) = state.viewport;
+    let mut screen = Vec::with_capacity(3);
+    for v in verticies.iter() {
+        if v.position.w <= 0.0 { return; }
+        let inv_w = 1.0 / v.position.w;
+        screen.push(ScreenVertex {
+            x:vx as f32 + (v.position.x * inv_w + 1.0) * 0.5 * vw as f32,
+            y:vy as f32 + (v.position.y * inv_w + 1.0) * 0.5 * vh as f32,
+            z:(v.position.z * inv_w + 1.0) * 0.5,
+            inv_w:inv_w,
+            varyings:&v.varyings
+        });
+    }
+
+    //wind counter clockwise so inside is always positive
+    let area = edge(&screen[0], &screen[1], screen[2].x, screen[2].y);
+    if area == 0.0 { return; }
+    if area < 0.0 { screen.swap(1, 2); }
+    let area = area.abs();
+    let (a, b, c) = (&screen[0], &screen[1], &screen[2]);
+
+    let (width, height) = (framebuffer.width as i32, framebuffer.height as i32);
+    let min_x = a.x.min(b.x).min(c.x).floor().max(vx.max(0) as f32) as i32;
+    let min_y = a.y.min(b.y).min(c.y).floor().max(vy.max(0) as f32) as i32;
+    let max_x = (a.x.max(b.x).max(c.x).ceil() as i32).min((vx + vw).min(width));
+    let max_y = (a.y.max(b.y).max(c.y).ceil() as i32).min((vy + vh).min(height));
+
+    let depth_test = state.enabled.contains(&WebGl2RenderingContext::DEPTH_TEST);
+    let blend = state.enabled.contains(&WebGl2RenderingContext::BLEND);
+    let mut varyings = vec![0.0; a.varyings.len()];
+
+    for py in min_y..max_y {
+        for px in min_x..max_x {
+            let (x, y) = (px as f32 + 0.5, py as f32 + 0.5);
+            let (w0, w1, w2) = (edge(b, c, x, y), edge(c, a, x, y), edge(a, b, x, y));
+            if !covers(w0, b, c) || !covers(w1, c, a) || !covers(w2, a, b) { continue; }
+            let (l0, l1, l2) = (w0 / area, w1 / area, w2 / area);
+
+            let z = l0 * a.z + l1 * b.z + l2 * c.z;
+            if z < 0.0 || z > 1.0 { continue; }
+            let i = (py * width + px) as usize;
+            if depth_test && !depth_passes(state.depth_func, z, framebuffer.depth[i]) { continue; }
+
+            //perspective correct interpolation
+            let (p0, p1, p2) = (l0 * a.inv_w, l1 * b.inv_w, l2 * c.inv_w);
+            let sum = p0 + p1 + p2;
+            for (n, v) in varyings.iter_mut().enumerate() {
+                *v = (p0 * a.varyings[n] + p1 * b.varyings[n] + p2 * c.varyings[n]) / sum;
+            }
+
+            let src = match shader.fragment(ctx, &varyings) {
+                Some(color) => color,
+                None => continue
+            };
+            //pick shaders discard what their type leaves fully transparent
+            if framebuffer.integer && src.w <= 0.0 { continue; }
+            if depth_test && state.depth_mask { framebuffer.depth[i] = z; }
+
+            let p = &mut framebuffer.color[i*4..i*4+4];
+            if framebuffer.integer {
+                let id = match ctx.uniform(PICK_ID) {
+                    Some(UniformValue::UInt(x)) => *x,
+                    _ => 0
+                };
+                p.copy_from_slice(&(id + instance as u32).to_ne_bytes());
+                continue;
+            }
+            let result = if blend {
+                let dst = Vector4::new(p[0] as f32 / 255.0, p[1] as f32 / 255.0, p[2] as f32 / 255.0, p[3] as f32 / 255.0);
+                let constant = Vector4::from(state.blend_color);
+                let s = blend_factor(state.blend_func.0, src, dst, constant);
+                let d = blend_factor(state.blend_func.1, src, dst, constant);
+                Vector4::new(src.x * s.x + dst.x * d.x, src.y * s.y + dst.y * d.y, src.z * s.z + dst.z * d.z, src.w * s.w + dst.w * d.w)
+            } else { src };
+            p.copy_from_slice(&[to_u8(result.x), to_u8(result.y), to_u8(result.z), to_u8(result.w)]);
+        }
+    }
+}
+
+fn to_u8(x:f32) -> u8 {
+    (x.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+impl GraphicsBackend for SoftwareBackend {
+    fn drawable_size(&self) -> (u32,u32) { self.recording.drawable_size() }
+
+    fn enable(&self, cap:u32) { self.recording.enable(cap) }
+    fn disable(&self, cap:u32) { self.recording.disable(cap) }
+    fn blend_func(&self, sfactor:u32, dfactor:u32) { self.recording.blend_func(sfactor, dfactor) }
+    fn blend_color(&self, r:f32, g:f32, b:f32, a:f32) { self.recording.blend_color(r, g, b, a) }
+    fn depth_mask(&self, flag:bool) { self.recording.depth_mask(flag) }
+    fn depth_func(&self, func:u32) { self.recording.depth_func(func) }
+    fn pixel_storei(&self, pname:u32, param:i32) { self.recording.pixel_storei(pname, param) }
+    fn clear_color(&self, r:f32, g:f32, b:f32, a:f32) { self.recording.clear_color(r, g, b, a) }
+    fn clear(&self, mask:u32) {
+        self.recording.clear(mask);
+        self.with_target(|framebuffer| self.clear_framebuffer(framebuffer, mask));
+    }
+    fn clear_bufferuiv(&self, buffer:u32, draw_buffer:i32, value:&[u32]) {
+        self.recording.clear_bufferuiv(buffer, draw_buffer, value);
+        self.with_target(|framebuffer| for p in framebuffer.color.chunks_exact_mut(4) {
+            p.copy_from_slice(&value[0].to_ne_bytes());
+        });
+    }
+    fn viewport(&self, x:i32, y:i32, width:i32, height:i32) { self.recording.viewport(x, y, width, height) }
+
+    fn create_buffer(&self) -> BufferHandle { self.recording.create_buffer() }
+    fn delete_buffer(&self, buffer:BufferHandle) { self.recording.delete_buffer(buffer) }
+    fn bind_buffer(&self, target:u32, buffer:Option<BufferHandle>) { self.recording.bind_buffer(target, buffer) }
+    fn bind_buffer_base(&self, target:u32, index:u32, buffer:Option<BufferHandle>) { self.recording.bind_buffer_base(target, index, buffer) }
+    fn buffer_data_with_size(&self, target:u32, size:i32, usage:u32) { self.recording.buffer_data_with_size(target, size, usage) }
+    fn buffer_data(&self, target:u32, data:&[u8], usage:u32) { self.recording.buffer_data(target, data, usage) }
+    fn buffer_sub_data(&self, target:u32, offset:i32, data:&[u8]) { self.recording.buffer_sub_data(target, offset, data) }
+    fn get_buffer_sub_data(&self, target:u32, offset:i32, dst:&mut [u8]) { self.recording.get_buffer_sub_data(target, offset, dst) }
+    fn copy_buffer_sub_data(&self, read_target:u32, write_target:u32, read_offset:i32, write_offset:i32, size:i32) {
+        self.recording.copy_buffer_sub_data(read_target, write_target, read_offset, write_offset, size)
+    }
+
+    fn create_vertex_array(&self) -> VertexArrayHandle { self.recording.create_vertex_array() }
+    fn delete_vertex_array(&self, vao:VertexArrayHandle) { self.recording.delete_vertex_array(vao) }
+    fn bind_vertex_array(&self, vao:Option<VertexArrayHandle>) { self.recording.bind_vertex_array(vao) }
+    fn enable_vertex_attrib_array(&self, location:u32) { self.recording.enable_vertex_attrib_array(location) }
+    fn vertex_attrib_pointer(&self, location:u32, size:i32, data_type:u32, normalized:bool, stride:i32, offset:i32) {
+        self.recording.vertex_attrib_pointer(location, size, data_type, normalized, stride, offset)
+    }
+    fn vertex_attrib_i_pointer(&self, location:u32, size:i32, data_type:u32, stride:i32, offset:i32) {
+        self.recording.vertex_attrib_i_pointer(location, size, data_type, stride, offset)
+    }
+    fn vertex_attrib_divisor(&self, location:u32, divisor:u32) { self.recording.vertex_attrib_divisor(location, divisor) }
+
+    fn compile_shader(&self, shader_type:u32, source:&str) -> Result<ShaderHandle, String> { self.recording.compile_shader(shader_type, source) }
+    fn delete_shader(&self, shader:ShaderHandle) { self.recording.delete_shader(shader) }
+    fn link_program(&self, vertex_shader:ShaderHandle, fragment_shader:ShaderHandle) -> Result<ProgramHandle, String> {
+        self.recording.link_program(vertex_shader, fragment_shader)
+    }
+    fn delete_program(&self, program:ProgramHandle) { self.recording.delete_program(program) }
+    fn use_program(&self, program:Option<ProgramHandle>) { self.recording.use_program(program) }
+    fn get_attrib_location(&self, program:ProgramHandle, name:&str) -> i32 { self.recording.get_attrib_location(program, name) }
+    fn get_uniform_location(&self, program:ProgramHandle, name:&str) -> Option<UniformLocation> { self.recording.get_uniform_location(program, name) }
+    fn get_active_attribs(&self, program:ProgramHandle) -> Vec<ActiveInfo> { self.recording.get_active_attribs(program) }
+    fn get_active_uniforms(&self, program:ProgramHandle) -> Vec<ActiveInfo> { self.recording.get_active_uniforms(program) }
+    fn get_uniform_block_index(&self, program:ProgramHandle, name:&str) -> Option<u32> { self.recording.get_uniform_block_index(program, name) }
+    fn uniform_block_binding(&self, program:ProgramHandle, block_index:u32, binding:u32) { self.recording.uniform_block_binding(program, block_index, binding) }
+    fn uniform1f(&self, location:UniformLocation, x:f32) { self.recording.uniform1f(location, x) }
+    fn uniform2f(&self, location:UniformLocation, x:f32, y:f32) { self.recording.uniform2f(location, x, y) }
+    fn uniform3f(&self, location:UniformLocation, x:f32, y:f32, z:f32) { self.recording.uniform3f(location, x, y, z) }
+    fn uniform4f(&self, location:UniformLocation, x:f32, y:f32, z:f32, w:f32) { self.recording.uniform4f(location, x, y, z, w) }
+    fn uniform1i(&self, location:UniformLocation, x:i32) { self.recording.uniform1i(location, x) }
+    fn uniform1ui(&self, location:UniformLocation, x:u32) { self.recording.uniform1ui(location, x) }
+    fn uniform1fv(&self, location:UniformLocation, data:&[f32]) { self.recording.uniform1fv(location, data) }
+    fn uniform2fv(&self, location:UniformLocation, data:&[f32]) { self.recording.uniform2fv(location, data) }
+    fn uniform3fv(&self, location:UniformLocation, data:&[f32]) { self.recording.uniform3fv(location, data) }
+    fn uniform4fv(&self, location:UniformLocation, data:&[f32]) { self.recording.uniform4fv(location, data) }
+    fn uniform1iv(&self, location:UniformLocation, data:&[i32]) { self.recording.uniform1iv(location, data) }
+    fn uniform_matrix3fv(&self, location:UniformLocation, transpose:bool, data:&[f32]) { self.recording.uniform_matrix3fv(location, transpose, data) }
+    fn uniform_matrix4fv(&self, location:UniformLocation, transpose:bool, data:&[f32]) { self.recording.uniform_matrix4fv(location, transpose, data) }
+
+    fn create_texture(&self) -> TextureHandle { self.recording.create_texture() }
+    fn delete_texture(&self, texture:TextureHandle) { self.recording.delete_texture(texture) }
+    fn active_texture(&self, unit:u32) { self.recording.active_texture(unit) }
+    fn bind_texture(&self, target:u32, texture:Option<TextureHandle>) { self.recording.bind_texture(target, texture) }
+    fn tex_parameteri(&self, target:u32, pname:u32, param:i32) { self.recording.tex_parameteri(target, pname, param) }
+    fn tex_parameterf(&self, target:u32, pname:u32, param:f32) { self.recording.tex_parameterf(target, pname, param) }
+    fn tex_image_2d(&self, target:u32, level:i32, internal_format:i32, width:i32, height:i32, format:u32, data_type:u32, pixels:Option<&[u8]>) {
+        self.recording.tex_image_2d(target, level, internal_format, width, height, format, data_type, pixels)
+    }
+    fn tex_sub_image_2d(&self, target:u32, level:i32, x:i32, y:i32, width:i32, height:i32, format:u32, data_type:u32, pixels:&[u8]) {
+        self.recording.tex_sub_image_2d(target, level, x, y, width, height, format, data_type, pixels)
+    }
+    fn tex_sub_image_2d_with_image(&self, target:u32, level:i32, x:i32, y:i32, format:u32, data_type:u32, image:&HtmlImageElement) {
+        self.recording.tex_sub_image_2d_with_image(target, level, x, y, format, data_type, image)
+    }
+    fn copy_tex_sub_image_2d(&self, target:u32, level:i32, x_offset:i32, y_offset:i32, x:i32, y:i32, width:i32, height:i32) {
+        self.recording.copy_tex_sub_image_2d(target, level, x_offset, y_offset, x, y, width, height)
+    }
+    fn generate_mipmap(&self, target:u32) { self.recording.generate_mipmap(target) }
+    fn max_anisotropy(&self) -> Option<f32> { self.recording.max_anisotropy() }
+
+    fn create_framebuffer(&self) -> FramebufferHandle { self.recording.create_framebuffer() }
+    fn delete_framebuffer(&self, framebuffer:FramebufferHandle) { self.recording.delete_framebuffer(framebuffer) }
+    fn bind_framebuffer(&self, target:u32, framebuffer:Option<FramebufferHandle>) { self.recording.bind_framebuffer(target, framebuffer) }
+    fn framebuffer_texture_2d(&self, target:u32, attachment:u32, tex_target:u32, texture:Option<TextureHandle>, level:i32) {
+        self.recording.framebuffer_texture_2d(target, attachment, tex_target, texture, level)
+    }
+    fn framebuffer_renderbuffer(&self, target:u32, attachment:u32, renderbuffer_target:u32, renderbuffer:Option<RenderbufferHandle>) {
+        self.recording.framebuffer_renderbuffer(target, attachment, renderbuffer_target, renderbuffer)
+    }
+    fn check_framebuffer_status(&self, target:u32) -> u32 { self.recording.check_framebuffer_status(target) }
+    //offscreen framebuffers are read from their recorded color texture by the recording backend
+    fn read_pixels(&self, x:i32, y:i32, width:i32, height:i32, format:u32, data_type:u32, dst:&mut [u8]) {
+        self.recording.read_pixels(x, y, width, height, format, data_type, dst);
+        if self.recording.state().framebuffer.is_some() { return; }
+        let framebuffer = self.framebuffer.borrow();
+        read_pixel_rect(&framebuffer.color, WebGl2RenderingContext::RGBA, framebuffer.width as i32, x, y, width, height, format, dst);
+    }
+
+    fn create_renderbuffer(&self) -> RenderbufferHandle { self.recording.create_renderbuffer() }
+    fn delete_renderbuffer(&self, renderbuffer:RenderbufferHandle) {
+        self.recording.delete_renderbuffer(renderbuffer);
+        self.depth_buffers.borrow_mut().remove(&renderbuffer);
+    }
+    fn bind_renderbuffer(&self, target:u32, renderbuffer:Option<RenderbufferHandle>) { self.recording.bind_renderbuffer(target, renderbuffer) }
+    fn renderbuffer_storage(&self, target:u32, internal_format:u32, width:i32, height:i32) {
+        self.recording.renderbuffer_storage(target, internal_format, width, height)
+    }
+
+    fn draw_elements(&self, mode:u32, count:i32, index_type:u32, offset:i32) {
+        self.recording.draw_elements(mode, count, index_type, offset);
+        self.with_target(|framebuffer| self.rasterize(framebuffer, mode, count, index_type, offset, 1));
+    }
+
+    fn draw_elements_instanced(&self, mode:u32, count:i32, index_type:u32, offset:i32, instance_count:i32) {
+        self.recording.draw_elements_instanced(mode, count, index_type, offset, instance_count);
+        self.with_target(|framebuffer| self.rasterize(framebuffer, mode, count, index_type, offset, instance_count));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use cgmath::{Matrix4, Point3, Vector2, Vector3, Vector4, SquareMatrix};
+
+    use super::{SoftwareBackend, GoldenError};
+    use crate::engine::render::{renderer::{Renderer, VertexData, UniformData}, layer::LayerId, render_object::{RenderObject, BlendMode}, texture::{RawTextureSource, TextureFormat, TextureFilter}, types::{triangle::{self, Triangle}, line::{Line, EndBehavior}, quadratic_bezier::QuadraticBezier, image::Image}, effects::{blur, color_grading, fxaa, vignette}};
+
+    //antialiased edges can round differently between platforms
+    const TOLERANCE:u8 = 2;
+
+    fn renderer() -> (Rc<SoftwareBackend>, Renderer) {
+        let gl = Rc::new(SoftwareBackend::new(64, 64));
+        let renderer = Renderer::with_backend(gl.clone());
+        (gl, renderer)
+    }
+
+    fn assert_golden(gl:&SoftwareBackend, name:&str) {
+        if let Err(e) = gl.compare_golden(name, TOLERANCE) {
+            panic!("{}", e);
+        }
+    }
+
+    #[test]
+    fn missing_goldens_are_errors() {
+        if std::env::var_os("UPDATE_GOLDEN").is_some() { return; }
+        let (gl, _renderer) = renderer();
+        //nothing is written into the source tree
+        assert!(matches!(gl.compare_golden("missing", TOLERANCE), Err(GoldenError::Missing(path)) if !path.exists()));
+    }
+
+    #[test]
+    fn triangle() {
+        let (gl, mut renderer) = renderer();
+        let _triangle = Triangle::new(&mut renderer, [Vector2::new(-0.5, -0.5), Vector2::new(0.5, -0.5), Vector2::new(0.0, 0.5)], Vector4::new(0.0, 0.0, 1.0, 1.0)).unwrap();
+        renderer.render();
+        assert_golden(&gl, "triangle");
+    }
+
+    #[test]
+    fn line() {
+        let (gl, mut renderer) = renderer();
+        let _clipped = Line::new(&mut renderer, [Vector2::new(-0.8, -0.5), Vector2::new(0.8, -0.5)], Vector4::new(1.0, 0.0, 0.0, 1.0), 0.08, 0.02, EndBehavior::Clipped).unwrap();
+        let _rounded = Line::new(&mut renderer, [Vector2::new(-0.6, 0.0), Vector2::new(0.6, 0.6)], Vector4::new(0.0, 1.0, 0.0, 1.0), 0.08, 0.02, EndBehavior::Rounded).unwrap();
+        renderer.render();
+        assert_golden(&gl, "line");
+    }
+
+    #[test]
+    fn quadratic_bezier() {
+        let (gl, mut renderer) = renderer();
+        let _curve = QuadraticBezier::new(&mut renderer, [Vector2::new(-0.8, -0.6), Vector2::new(0.0, 1.2), Vector2::new(0.8, -0.6)], Vector4::new(1.0, 0.5, 0.0, 1.0), 0.05, 0.02).unwrap();
+        renderer.render();
+        assert_golden(&gl, "quadratic_bezier");
+    }
+
+    #[test]
+    fn image() {
+        let (gl, mut renderer) = renderer();
+        let data = [255u8,0,0,255, 0,255,0,255, 0,0,255,255, 255,255,255,255];
+        let texture = renderer.upload_texture(&RawTextureSource { data:&data, format:TextureFormat::RGBA, min_filter:TextureFilter::Nearest, mag_filter:TextureFilter::Nearest, width:2, height:2, unique:false });
+        let mut image = Image::from_mapped(&mut renderer, Matrix4::identity(), texture);
+        image.render(&mut renderer, Matrix4::from_scale(0.4)).unwrap();
+        renderer.render();
+        assert_golden(&gl, "image");
+    }
+
+    //color in the middle of the canvas after drawing color with blend_mode over opaque gray
+    fn blended_pixel(blend_mode:BlendMode, color:Vector4<f32>) -> [u8; 4] {
+        let (gl, mut renderer) = renderer();
+        let _gray = Triangle::new(&mut renderer, [Vector2::new(-1.0, -1.0), Vector2::new(3.0, -1.0), Vector2::new(-1.0, 3.0)], Vector4::new(0.5, 0.5, 0.5, 1.0)).unwrap();
+        let render_type = renderer.register_render_type("blended", triangle::render_type().with_blend_mode(blend_mode));
+        let mut object = RenderObject::new(render_type);
+        object.set_v_datas(0, "pos", vec![VertexData::FloatVec2(Vector2::new(-1.0, -1.0)), VertexData::FloatVec2(Vector2::new(3.0, -1.0)), VertexData::FloatVec2(Vector2::new(-1.0, 3.0))]);
+        object.set_v_datas(0, "vColor", (0..3).map(|_| VertexData::FloatVec4(color)).collect());
+        object.add_triangle([0, 1, 2]);
+        object.update(&mut renderer).unwrap();
+        renderer.render();
+        gl.pixel(32, 32)
+    }
+
+    fn assert_rgb(pixel:[u8; 4], rgb:[u8; 3]) {
+        assert!(pixel.iter().zip(rgb.iter()).all(|(a, b)| (*a as i16 - *b as i16).abs() <= TOLERANCE as i16), "Expected {:?}, got {:?}", rgb, pixel);
+    }
+
+    #[test]
+    fn blend_modes() {
+        assert_rgb(blended_pixel(BlendMode::Alpha, Vector4::new(1.0, 0.0, 0.0, 0.5)), [191, 64, 64]);
+        assert_rgb(blended_pixel(BlendMode::Additive, Vector4::new(0.25, 0.0, 0.0, 1.0)), [191, 128, 128]);
+        assert_rgb(blended_pixel(BlendMode::Multiply, Vector4::new(1.0, 0.5, 0.0, 1.0)), [128, 64, 0]);
+        assert_rgb(blended_pixel(BlendMode::Premultiplied, Vector4::new(0.5, 0.0, 0.0, 0.5)), [191, 64, 64]);
+    }
+
+    //covers the whole canvas
+    fn background(renderer:&mut Renderer, layer:LayerId, color:Vector4<f32>) -> Triangle {
+        let mut triangle = Triangle::new(renderer, [Vector2::new(-1.0, -1.0), Vector2::new(3.0, -1.0), Vector2::new(-1.0, 3.0)], color).unwrap();
+        triangle.set_layer(renderer, layer, 0).unwrap();
+        triangle
+    }
+
+    #[test]
+    fn layers_are_drawn_in_order() {
+        let (gl, mut renderer) = renderer();
+        let _overlay = background(&mut renderer, LayerId::OVERLAY, Vector4::new(0.0, 0.0, 1.0, 1.0));
+        let _annotations = background(&mut renderer, LayerId::ANNOTATIONS, Vector4::new(0.0, 1.0, 0.0, 1.0));
+        let _background = background(&mut renderer, LayerId::BACKGROUND, Vector4::new(0.5, 0.5, 0.5, 1.0));
+        renderer.render();
+        assert_rgb(gl.pixel(32, 32), [0, 0, 255]);
+
+        renderer.layer_mut(LayerId::OVERLAY).unwrap().visible = false;
+        renderer.render();
+        assert_rgb(gl.pixel(32, 32), [0, 255, 0]);
+    }
+
+    #[test]
+    fn layer_opacity() {
+        let (gl, mut renderer) = renderer();
+        let _background = background(&mut renderer, LayerId::BACKGROUND, Vector4::new(0.5, 0.5, 0.5, 1.0));
+        let _opaque = background(&mut renderer, LayerId::CONTENT, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        renderer.layer_mut(LayerId::CONTENT).unwrap().opacity = 0.5;
+        renderer.render();
+        assert_rgb(gl.pixel(32, 32), [191, 64, 64]);
+    }
+
+    #[test]
+    fn picks_objects_where_they_are_drawn() {
+        let (_gl, mut renderer) = renderer();
+        let triangle = Triangle::new(&mut renderer, [Vector2::new(-0.9, -0.9), Vector2::new(-0.1, -0.9), Vector2::new(-0.5, -0.1)], Vector4::new(0.0, 0.0, 1.0, 1.0)).unwrap();
+        let line = Line::new(&mut renderer, [Vector2::new(-0.6, 0.5), Vector2::new(0.6, 0.5)], Vector4::new(1.0, 0.0, 0.0, 1.0), 0.1, 0.1, EndBehavior::Clipped).unwrap();
+        assert_eq!(renderer.pick(16, 48).unwrap(), triangle.pick_id());
+        assert_eq!(renderer.pick(32, 16).unwrap(), line.pick_id());
+        //inside the line's quad but outside its stroke
+        assert_eq!(renderer.pick(32, 11).unwrap(), None);
+        assert_eq!(renderer.pick(48, 48).unwrap(), None);
+        assert_eq!(renderer.pick(64, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn picks_the_top_layer() {
+        let (_gl, mut renderer) = renderer();
+        let background = background(&mut renderer, LayerId::BACKGROUND, Vector4::new(0.5, 0.5, 0.5, 1.0));
+        let mut overlay = Triangle::new(&mut renderer, [Vector2::new(-0.5, -0.5), Vector2::new(0.5, -0.5), Vector2::new(0.0, 0.5)], Vector4::new(0.0, 0.0, 1.0, 1.0)).unwrap();
+        overlay.set_layer(&mut renderer, LayerId::OVERLAY, 0).unwrap();
+        assert_eq!(renderer.pick(32, 32).unwrap(), overlay.pick_id());
+        assert_eq!(renderer.pick(4, 4).unwrap(), background.pick_id());
+
+        renderer.layer_mut(LayerId::OVERLAY).unwrap().visible = false;
+        assert_eq!(renderer.pick(32, 32).unwrap(), background.pick_id());
+    }
+
+    #[test]
+    fn transparent_texels_are_not_picked() {
+        let (_gl, mut renderer) = renderer();
+        let data = [255u8,0,0,255, 0,255,0,0, 0,0,255,255, 255,255,255,255];
+        let texture = renderer.upload_texture(&RawTextureSource { data:&data, format:TextureFormat::RGBA, min_filter:TextureFilter::Nearest, mag_filter:TextureFilter::Nearest, width:2, height:2, unique:false });
+        let mut image = Image::from_mapped(&mut renderer, Matrix4::identity(), texture);
+        image.render(&mut renderer, Matrix4::from_scale(0.5)).unwrap();
+        //the first row is drawn at the top, its second texel is transparent
+        assert_eq!(renderer.pick(24, 24).unwrap(), image.pick_id());
+        assert_eq!(renderer.pick(40, 24).unwrap(), None);
+        assert_eq!(renderer.pick(40, 40).unwrap(), image.pick_id());
+    }
+
+    //pixel drawing the world point
+    fn screen_pixel(renderer:&Renderer, world:Point3<f32>) -> (u32, u32) {
+        let screen = renderer.world_to_screen(world).unwrap();
+        (((screen.x + 1.0) * 32.0) as u32, ((1.0 - screen.y) * 32.0) as u32)
+    }
+
+    #[test]
+    fn picks_translucent_objects_after_they_move() {
+        let (_gl, mut renderer) = renderer();
+        let data = [255u8,255,255,255];
+        let texture = renderer.upload_texture(&RawTextureSource { data:&data, format:TextureFormat::RGBA, min_filter:TextureFilter::Nearest, mag_filter:TextureFilter::Nearest, width:1, height:1, unique:false });
+        let place = |x:f32, y:f32, z:f32| Matrix4::from_translation(Vector3::new(x, y, z)) * Matrix4::from_scale(0.15);
+        let mut near = Image::from_mapped(&mut renderer, Matrix4::identity(), texture.clone());
+        near.render(&mut renderer, place(-0.4, 0.0, 0.0)).unwrap();
+        let mut far = Image::from_mapped(&mut renderer, Matrix4::identity(), texture);
+        far.render(&mut renderer, place(0.4, 0.0, -0.5)).unwrap();
+        //drawn back to front, which reverses them in the index buffer
+        renderer.render();
+
+        near.render(&mut renderer, place(-0.4, 0.1, 0.0)).unwrap();
+        let (x, y) = screen_pixel(&renderer, Point3::new(-0.4, 0.1, 0.0));
+        assert_eq!(renderer.pick(x, y).unwrap(), near.pick_id());
+        let (x, y) = screen_pixel(&renderer, Point3::new(0.4, 0.0, -0.5));
+        assert_eq!(renderer.pick(x, y).unwrap(), far.pick_id());
+    }
+
+    #[test]
+    fn effects_are_neutral_until_their_uniforms_are_set() {
+        let (gl, mut renderer) = renderer();
+        for name in [blur::NAME, color_grading::NAME, vignette::NAME].iter() {
+            renderer.add_effect(name).unwrap();
+        }
+        let _triangle = Triangle::new(&mut renderer, [Vector2::new(-0.5, -0.5), Vector2::new(0.5, -0.5), Vector2::new(0.0, 0.5)], Vector4::new(0.0, 0.0, 1.0, 1.0)).unwrap();
+        renderer.render();
+        assert_golden(&gl, "triangle");
+    }
+
+    #[test]
+    fn blur_effect() {
+        let (gl, mut renderer) = renderer();
+        let _white = background(&mut renderer, LayerId::BACKGROUND, Vector4::new(1.0, 1.0, 1.0, 1.0));
+        //covers the right half
+        let _black = Triangle::new(&mut renderer, [Vector2::new(0.0, -3.0), Vector2::new(4.0, 0.0), Vector2::new(0.0, 3.0)], Vector4::new(0.0, 0.0, 0.0, 1.0)).unwrap();
+        renderer.add_effect(blur::NAME).unwrap().set_uniform("radius", UniformData::Float(2.0));
+        renderer.render();
+        assert_rgb(gl.pixel(31, 32), [191, 191, 191]);
+        assert_rgb(gl.pixel(32, 32), [64, 64, 64]);
+        assert_rgb(gl.pixel(8, 32), [255, 255, 255]);
+        assert_rgb(gl.pixel(56, 32), [0, 0, 0]);
+    }
+
+    #[test]
+    fn color_grading_effect() {
+        let (gl, mut renderer) = renderer();
+        let _background = background(&mut renderer, LayerId::BACKGROUND, Vector4::new(0.5, 0.25, 0.0, 1.0));
+        let effect = renderer.add_effect(color_grading::NAME).unwrap();
+        effect.set_uniform("exposure", UniformData::Float(1.0));
+        effect.set_uniform("saturation", UniformData::Float(-1.0));
+        renderer.render();
+        //exposure doubles it to (1.0, 0.5, 0.0), which is then taken down to its luma
+        assert_rgb(gl.pixel(32, 32), [151, 151, 151]);
+    }
+
+    #[test]
+    fn fxaa_effect() {
+        let (gl, mut renderer) = renderer();
+        let _black = background(&mut renderer, LayerId::BACKGROUND, Vector4::new(0.0, 0.0, 0.0, 1.0));
+        let _white = Triangle::new(&mut renderer, [Vector2::new(-0.8, -0.8), Vector2::new(0.8, -0.4), Vector2::new(-0.4, 0.8)], Vector4::new(1.0, 1.0, 1.0, 1.0)).unwrap();
+        let blended = |gl:&SoftwareBackend| gl.pixels().chunks_exact(4).filter(|x| x[0] > TOLERANCE && x[0] < 255 - TOLERANCE).count();
+        renderer.render();
+        assert_eq!(blended(&gl), 0);
+
+        renderer.add_effect(fxaa::NAME).unwrap();
+        renderer.render();
+        assert!(blended(&gl) > 0);
+        //away from the edges
+        assert_rgb(gl.pixel(24, 40), [255, 255, 255]);
+        assert_rgb(gl.pixel(60, 4), [0, 0, 0]);
+    }
+
+    #[test]
+    fn vignette_effect() {
+        let (gl, mut renderer) = renderer();
+        let _white = background(&mut renderer, LayerId::BACKGROUND, Vector4::new(1.0, 1.0, 1.0, 1.0));
+        renderer.add_effect(vignette::NAME).unwrap().set_uniform("strength", UniformData::Float(1.0));
+        renderer.render();
+        assert_rgb(gl.pixel(32, 32), [255, 255, 255]);
+        assert_rgb(gl.pixel(0, 0), [8, 8, 8]);
+        assert_rgb(gl.pixel(63, 0), [8, 8, 8]);
+    }
+}
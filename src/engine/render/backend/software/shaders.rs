@@ -0,0 +1,349 @@
+use cgmath::{Vector2, Vector3, Vector4, InnerSpace, VectorSpace};
+
+use super::{SoftwareShader, ShaderContext, VertexInputs, VertexOutput};
+use super::super::recording::{parse_declarations, UniformValue};
+
+//Rust versions of the shaders used by the render types in render::types and the effects in render::effects, they are
+//matched against programs by their vertex inputs and the sdf or effect functions their fragment shaders use.
+
+fn has_inputs(vertex_source:&str, names:&[&str]) -> bool {
+    let inputs:Vec<String> = parse_declarations(vertex_source, "in").into_iter().map(|(_,name)| name).collect();
+    inputs.len() == names.len() && names.iter().all(|x| inputs.iter().any(|y| y == x))
+}
+
+fn smoothstep(edge0:f32, edge1:f32, x:f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn vec2(varyings:&[f32], i:usize) -> Vector2<f32> {
+    Vector2::new(varyings[i], varyings[i+1])
+}
+
+fn vec4(varyings:&[f32], i:usize) -> Vector4<f32> {
+    Vector4::new(varyings[i], varyings[i+1], varyings[i+2], varyings[i+3])
+}
+
+fn dot2(v:Vector2<f32>) -> f32 {
+    v.dot(v)
+}
+
+// https://www.iquilezles.org/www/articles/distfunctions2d/distfunctions2d.htm
+fn sd_segment(p:Vector2<f32>, a:Vector2<f32>, b:Vector2<f32>) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+    (pa - ba * h).magnitude()
+}
+
+fn sd_bezier(pos:Vector2<f32>, a_:Vector2<f32>, b_:Vector2<f32>, c_:Vector2<f32>) -> f32 {
+    let a = b_ - a_;
+    let b = a_ - b_ * 2.0 + c_;
+    let c = a * 2.0;
+    let d = a_ - pos;
+    let kk = 1.0 / b.dot(b);
+    let kx = kk * a.dot(b);
+    let ky = kk * (2.0 * a.dot(a) + d.dot(b)) / 3.0;
+    let kz = kk * d.dot(a);
+    let p = ky - kx * kx;
+    let p3 = p * p * p;
+    let q = kx * (2.0 * kx * kx - 3.0 * ky) + kz;
+    let h = q * q + 4.0 * p3;
+    let res = if h >= 0.0 {
+        let h = h.sqrt();
+        let x = Vector2::new((h - q) / 2.0, (-h - q) / 2.0);
+        let uv = Vector2::new(x.x.signum() * x.x.abs().powf(1.0 / 3.0), x.y.signum() * x.y.abs().powf(1.0 / 3.0));
+        let t = (uv.x + uv.y - kx).clamp(0.0, 1.0);
+        dot2(d + (c + b * t) * t)
+    } else {
+        let z = (-p).sqrt();
+        let v = (q / (p * z * 2.0)).acos() / 3.0;
+        let m = v.cos();
+        let n = v.sin() * 1.732050808;
+        let t = Vector3::new(m + m, -n - m, n - m) * z - Vector3::new(kx, kx, kx);
+        let (tx, ty) = (t.x.clamp(0.0, 1.0), t.y.clamp(0.0, 1.0));
+        dot2(d + (c + b * tx) * tx).min(dot2(d + (c + b * ty) * ty))
+    };
+    res.sqrt()
+}
+
+//layer opacity for the built in types, shaders without the uniform draw fully opaque
+fn opacity(ctx:&ShaderContext) -> f32 {
+    match ctx.uniform("opacity") {
+        Some(UniformValue::Float(x)) => *x,
+        _ => 1.0
+    }
+}
+
+//shared by the line and bezier shaders, fades the edge over smooth and discards outside the thickness
+fn sdf_fragment(distance:f32, thickness:f32, smooth:f32, color:Vector4<f32>, opacity:f32) -> Option<Vector4<f32>> {
+    let d = distance - thickness;
+    if d < 0.0 {
+        Some(Vector4::new(color.x, color.y, color.z, smoothstep(0.0, smooth, -d) * opacity))
+    } else {
+        None
+    }
+}
+
+//triangle
+#[derive(Debug)]
+pub struct ColorShader;
+
+impl SoftwareShader for ColorShader {
+    fn matches(&self, vertex_source:&str, _fragment_source:&str) -> bool {
+        has_inputs(vertex_source, &["pos", "vColor"])
+    }
+
+    fn vertex(&self, _ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput {
+        let pos = inputs.vec2("pos");
+        let color = inputs.vec4("vColor");
+        VertexOutput {
+            position:Vector4::new(pos.x, pos.y, 0.0, 1.0),
+            varyings:vec![color.x, color.y, color.z, color.w]
+        }
+    }
+
+    fn fragment(&self, ctx:&ShaderContext, varyings:&[f32]) -> Option<Vector4<f32>> {
+        let color = vec4(varyings, 0);
+        Some(Vector4::new(color.x, color.y, color.z, color.w * opacity(ctx)))
+    }
+}
+
+//image
+#[derive(Debug)]
+pub struct ImageShader;
+
+impl SoftwareShader for ImageShader {
+    fn matches(&self, vertex_source:&str, fragment_source:&str) -> bool {
+        has_inputs(vertex_source, &["position", "texCoord"]) && fragment_source.contains("texture0")
+    }
+
+    fn vertex(&self, ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput {
+        let position = inputs.get("position");
+        let tex_coord = inputs.vec2("texCoord");
+        VertexOutput {
+            position:ctx.matrix4("projection") * ctx.matrix4("view") * Vector4::new(position[0], position[1], position[2], 1.0),
+            varyings:vec![tex_coord.x, tex_coord.y]
+        }
+    }
+
+    fn fragment(&self, ctx:&ShaderContext, varyings:&[f32]) -> Option<Vector4<f32>> {
+        let color = ctx.sample("texture0", vec2(varyings, 0));
+        Some(Vector4::new(color.x, color.y, color.z, color.w * opacity(ctx)))
+    }
+}
+
+//varyings: posf, points, color, thickness, smooth
+fn sdf_vertex(inputs:&VertexInputs, points:&[&str]) -> VertexOutput {
+    let pos = inputs.vec2("pos");
+    let color = inputs.vec4("vColor");
+    let mut varyings = vec![pos.x, pos.y];
+    for name in points {
+        let p = inputs.vec2(name);
+        varyings.extend_from_slice(&[p.x, p.y]);
+    }
+    varyings.extend_from_slice(&[color.x, color.y, color.z, color.w, inputs.float("vThickness"), inputs.float("vSmooth")]);
+    VertexOutput {
+        position:Vector4::new(pos.x, pos.y, 0.0, 1.0),
+        varyings:varyings
+    }
+}
+
+//line
+#[derive(Debug)]
+pub struct LineShader;
+
+impl SoftwareShader for LineShader {
+    fn matches(&self, _vertex_source:&str, fragment_source:&str) -> bool {
+        fragment_source.contains("sdSegment")
+    }
+
+    fn vertex(&self, _ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput {
+        sdf_vertex(inputs, &["points1", "points2"])
+    }
+
+    fn fragment(&self, ctx:&ShaderContext, varyings:&[f32]) -> Option<Vector4<f32>> {
+        let d = sd_segment(vec2(varyings, 0), vec2(varyings, 2), vec2(varyings, 4));
+        sdf_fragment(d, varyings[10], varyings[11], vec4(varyings, 6), opacity(ctx))
+    }
+}
+
+//quadratic bezier
+#[derive(Debug)]
+pub struct QuadraticBezierShader;
+
+impl SoftwareShader for QuadraticBezierShader {
+    fn matches(&self, _vertex_source:&str, fragment_source:&str) -> bool {
+        fragment_source.contains("sdBezier")
+    }
+
+    fn vertex(&self, _ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput {
+        sdf_vertex(inputs, &["points1", "points2", "points3"])
+    }
+
+    fn fragment(&self, ctx:&ShaderContext, varyings:&[f32]) -> Option<Vector4<f32>> {
+        let d = sd_bezier(vec2(varyings, 0), vec2(varyings, 2), vec2(varyings, 4), vec2(varyings, 6));
+        sdf_fragment(d, varyings[12], varyings[13], vec4(varyings, 8), opacity(ctx))
+    }
+}
+
+//placeholder from RenderType::fallback_shaders, declares at most one position input and either view then projection
+//or the camera block
+#[derive(Debug)]
+pub struct FallbackShader;
+
+impl SoftwareShader for FallbackShader {
+    fn matches(&self, _vertex_source:&str, fragment_source:&str) -> bool {
+        fragment_source.contains("errorColor")
+    }
+
+    fn vertex(&self, ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput {
+        let mut position = match parse_declarations(ctx.vertex_source(), "in").first() {
+            Some((_,name)) => Vector4::from(inputs.get(name)),
+            None => Vector4::new(0.0, 0.0, 0.0, 1.0)
+        };
+        let uniforms = parse_declarations(ctx.vertex_source(), "uniform");
+        if let [(_,view),(_,projection)] = uniforms.as_slice() {
+            position = ctx.matrix4(projection) * ctx.matrix4(view) * position;
+        } else if ctx.vertex_source().contains("uniform Camera") {
+            position = ctx.matrix4("projection") * ctx.matrix4("view") * position;
+        }
+        VertexOutput {
+            position:position,
+            varyings:Vec::new()
+        }
+    }
+
+    fn fragment(&self, _ctx:&ShaderContext, _varyings:&[f32]) -> Option<Vector4<f32>> {
+        Some(Vector4::new(1.0, 0.0, 1.0, 1.0))
+    }
+}
+
+//varyings: uv
+fn effect_vertex(inputs:&VertexInputs) -> VertexOutput {
+    let position = inputs.vec2("position");
+    VertexOutput {
+        position:Vector4::new(position.x, position.y, 0.0, 1.0),
+        varyings:vec![position.x * 0.5 + 0.5, position.y * 0.5 + 0.5]
+    }
+}
+
+const LUMA:Vector3<f32> = Vector3::new(0.299, 0.587, 0.114);
+
+//blur effect
+#[derive(Debug)]
+pub struct BlurShader;
+
+impl SoftwareShader for BlurShader {
+    fn matches(&self, _vertex_source:&str, fragment_source:&str) -> bool {
+        fragment_source.contains("vec4 blur(")
+    }
+
+    fn vertex(&self, _ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput {
+        effect_vertex(inputs)
+    }
+
+    fn fragment(&self, ctx:&ShaderContext, varyings:&[f32]) -> Option<Vector4<f32>> {
+        let uv = vec2(varyings, 0);
+        let resolution = ctx.vec2("resolution");
+        let radius = ctx.float("radius");
+        let step = Vector2::new(radius / resolution.x, radius / resolution.y);
+        let mut result = Vector4::new(0.0, 0.0, 0.0, 0.0);
+        for x in -1..=1 {
+            for y in -1..=1 {
+                let weight = (2.0 - (x as f32).abs()) * (2.0 - (y as f32).abs()) / 16.0;
+                result += ctx.sample("source", uv + Vector2::new(x as f32 * step.x, y as f32 * step.y)) * weight;
+            }
+        }
+        Some(result)
+    }
+}
+
+//color grading effect
+#[derive(Debug)]
+pub struct ColorGradingShader;
+
+impl SoftwareShader for ColorGradingShader {
+    fn matches(&self, _vertex_source:&str, fragment_source:&str) -> bool {
+        fragment_source.contains("vec3 grade(")
+    }
+
+    fn vertex(&self, _ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput {
+        effect_vertex(inputs)
+    }
+
+    fn fragment(&self, ctx:&ShaderContext, varyings:&[f32]) -> Option<Vector4<f32>> {
+        let color = ctx.sample("source", vec2(varyings, 0));
+        let graded = color.truncate() * ctx.float("exposure").exp2();
+        let graded = (graded - Vector3::new(0.5, 0.5, 0.5)) * (1.0 + ctx.float("contrast")) + Vector3::new(0.5, 0.5, 0.5);
+        let luma = graded.dot(LUMA);
+        let graded = Vector3::new(luma, luma, luma).lerp(graded, 1.0 + ctx.float("saturation"));
+        Some(Vector4::new(graded.x.clamp(0.0, 1.0), graded.y.clamp(0.0, 1.0), graded.z.clamp(0.0, 1.0), color.w))
+    }
+}
+
+//fxaa effect
+#[derive(Debug)]
+pub struct FxaaShader;
+
+impl SoftwareShader for FxaaShader {
+    fn matches(&self, _vertex_source:&str, fragment_source:&str) -> bool {
+        fragment_source.contains("vec4 fxaa(")
+    }
+
+    fn vertex(&self, _ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput {
+        effect_vertex(inputs)
+    }
+
+    fn fragment(&self, ctx:&ShaderContext, varyings:&[f32]) -> Option<Vector4<f32>> {
+        const REDUCE_MIN:f32 = 1.0 / 128.0;
+        const REDUCE_MUL:f32 = 1.0 / 8.0;
+        const SPAN_MAX:f32 = 8.0;
+        let uv = vec2(varyings, 0);
+        let resolution = ctx.vec2("resolution");
+        let texel = Vector2::new(1.0 / resolution.x, 1.0 / resolution.y);
+        let sample = |offset:Vector2<f32>| ctx.sample("source", uv + offset).truncate();
+        let luma = |offset:Vector2<f32>| sample(Vector2::new(offset.x * texel.x, offset.y * texel.y)).dot(LUMA);
+        let (luma_nw, luma_ne) = (luma(Vector2::new(-1.0, -1.0)), luma(Vector2::new(1.0, -1.0)));
+        let (luma_sw, luma_se) = (luma(Vector2::new(-1.0, 1.0)), luma(Vector2::new(1.0, 1.0)));
+        let center = ctx.sample("source", uv);
+        let luma_m = center.truncate().dot(LUMA);
+        let luma_min = luma_m.min(luma_nw.min(luma_ne).min(luma_sw.min(luma_se)));
+        let luma_max = luma_m.max(luma_nw.max(luma_ne).max(luma_sw.max(luma_se)));
+
+        let dir = Vector2::new(-((luma_nw + luma_ne) - (luma_sw + luma_se)), (luma_nw + luma_sw) - (luma_ne + luma_se));
+        let dir_reduce = ((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL).max(REDUCE_MIN);
+        let rcp_dir_min = 1.0 / (dir.x.abs().min(dir.y.abs()) + dir_reduce);
+        let dir = Vector2::new(
+            (dir.x * rcp_dir_min).clamp(-SPAN_MAX, SPAN_MAX) * texel.x,
+            (dir.y * rcp_dir_min).clamp(-SPAN_MAX, SPAN_MAX) * texel.y
+        );
+
+        let rgb_a = (sample(dir * (1.0 / 3.0 - 0.5)) + sample(dir * (2.0 / 3.0 - 0.5))) * 0.5;
+        let rgb_b = rgb_a * 0.5 + (sample(dir * -0.5) + sample(dir * 0.5)) * 0.25;
+        let luma_b = rgb_b.dot(LUMA);
+        let rgb = if luma_b < luma_min || luma_b > luma_max { rgb_a } else { rgb_b };
+        Some(rgb.extend(center.w))
+    }
+}
+
+//vignette effect
+#[derive(Debug)]
+pub struct VignetteShader;
+
+impl SoftwareShader for VignetteShader {
+    fn matches(&self, _vertex_source:&str, fragment_source:&str) -> bool {
+        fragment_source.contains("float vignette(")
+    }
+
+    fn vertex(&self, _ctx:&ShaderContext, inputs:&VertexInputs) -> VertexOutput {
+        effect_vertex(inputs)
+    }
+
+    fn fragment(&self, ctx:&ShaderContext, varyings:&[f32]) -> Option<Vector4<f32>> {
+        let uv = vec2(varyings, 0);
+        let d = (uv - Vector2::new(0.5, 0.5)).magnitude() * 1.41421356;
+        let color = ctx.sample("source", uv);
+        Some((color.truncate() * (1.0 - ctx.float("strength") * d * d)).extend(color.w))
+    }
+}
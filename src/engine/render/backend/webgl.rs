@@ -0,0 +1,518 @@
+use std::{cell::RefCell, collections::HashMap, fmt::Debug};
+
+use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
+use web_sys::{WebGl2RenderingContext, HtmlCanvasElement, HtmlImageElement, WebGlBuffer, WebGlVertexArrayObject, WebGlTexture, WebGlShader, WebGlProgram, WebGlUniformLocation, WebGlActiveInfo, WebGlFramebuffer, WebGlRenderbuffer};
+
+use super::{GraphicsBackend, BufferHandle, VertexArrayHandle, TextureHandle, ShaderHandle, ProgramHandle, UniformLocation, ActiveInfo, FramebufferHandle, RenderbufferHandle, super::index_map::IndexMap};
+
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT:u32 = 0x84FF;
+
+//maps the opaque handles handed out to the renderer onto the js objects owned by the context
+pub struct WebGl2Backend {
+    gl:WebGl2RenderingContext,
+    canvas:HtmlCanvasElement,
+    buffers:RefCell<IndexMap<WebGlBuffer>>,
+    vertex_arrays:RefCell<IndexMap<WebGlVertexArrayObject>>,
+    textures:RefCell<IndexMap<WebGlTexture>>,
+    shaders:RefCell<IndexMap<WebGlShader>>,
+    programs:RefCell<IndexMap<WebGlProgram>>,
+    uniform_locations:RefCell<IndexMap<WebGlUniformLocation>>,
+    framebuffers:RefCell<IndexMap<WebGlFramebuffer>>,
+    renderbuffers:RefCell<IndexMap<WebGlRenderbuffer>>,
+    cached_uniform_locations:RefCell<HashMap<(ProgramHandle,String),Option<UniformLocation>>>
+}
+
+impl Debug for WebGl2Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebGl2Backend")
+            .field("gl", &self.gl)
+            .field("canvas", &self.canvas)
+            .field("buffers", &"..")
+            .field("textures", &"..")
+            .field("programs", &"..")
+        .finish()
+    }
+}
+
+impl WebGl2Backend {
+    pub fn new(canvas:HtmlCanvasElement) -> Self {
+        let gl = canvas.get_context("webgl2").unwrap().unwrap().dyn_into::<WebGl2RenderingContext>().unwrap();
+
+        Self {
+            gl:gl,
+            canvas:canvas,
+            buffers:RefCell::new(IndexMap::new()),
+            vertex_arrays:RefCell::new(IndexMap::new()),
+            textures:RefCell::new(IndexMap::new()),
+            shaders:RefCell::new(IndexMap::new()),
+            programs:RefCell::new(IndexMap::new()),
+            uniform_locations:RefCell::new(IndexMap::new()),
+            framebuffers:RefCell::new(IndexMap::new()),
+            renderbuffers:RefCell::new(IndexMap::new()),
+            cached_uniform_locations:RefCell::new(HashMap::new())
+        }
+    }
+
+    pub fn context(&self) -> &WebGl2RenderingContext {
+        &self.gl
+    }
+
+    fn buffer(&self, buffer:BufferHandle) -> WebGlBuffer {
+        self.buffers.borrow()[buffer.0].clone()
+    }
+
+    fn vertex_array(&self, vao:VertexArrayHandle) -> WebGlVertexArrayObject {
+        self.vertex_arrays.borrow()[vao.0].clone()
+    }
+
+    fn texture(&self, texture:TextureHandle) -> WebGlTexture {
+        self.textures.borrow()[texture.0].clone()
+    }
+
+    fn program(&self, program:ProgramHandle) -> WebGlProgram {
+        self.programs.borrow()[program.0].clone()
+    }
+
+    fn uniform_location(&self, location:UniformLocation) -> WebGlUniformLocation {
+        self.uniform_locations.borrow()[location.0].clone()
+    }
+
+    fn framebuffer(&self, framebuffer:FramebufferHandle) -> WebGlFramebuffer {
+        self.framebuffers.borrow()[framebuffer.0].clone()
+    }
+
+    fn renderbuffer(&self, renderbuffer:RenderbufferHandle) -> WebGlRenderbuffer {
+        self.renderbuffers.borrow()[renderbuffer.0].clone()
+    }
+}
+
+impl GraphicsBackend for WebGl2Backend {
+    fn drawable_size(&self) -> (u32,u32) {
+        let display_width = self.canvas.client_width() as u32;
+        let display_height = self.canvas.client_height() as u32;
+        if self.canvas.width() != display_width || self.canvas.height() != display_height {
+            self.canvas.set_width(display_width);
+            self.canvas.set_height(display_height);
+        }
+        (display_width, display_height)
+    }
+
+    fn enable(&self, cap:u32) { self.gl.enable(cap); }
+    fn disable(&self, cap:u32) { self.gl.disable(cap); }
+    fn blend_func(&self, sfactor:u32, dfactor:u32) { self.gl.blend_func(sfactor, dfactor); }
+    fn blend_color(&self, r:f32, g:f32, b:f32, a:f32) { self.gl.blend_color(r, g, b, a); }
+    fn depth_mask(&self, flag:bool) { self.gl.depth_mask(flag); }
+    fn depth_func(&self, func:u32) { self.gl.depth_func(func); }
+    fn pixel_storei(&self, pname:u32, param:i32) { self.gl.pixel_storei(pname, param); }
+    fn clear_color(&self, r:f32, g:f32, b:f32, a:f32) { self.gl.clear_color(r, g, b, a); }
+    fn clear(&self, mask:u32) { self.gl.clear(mask); }
+    fn clear_bufferuiv(&self, buffer:u32, draw_buffer:i32, value:&[u32]) { self.gl.clear_bufferuiv_with_u32_array(buffer, draw_buffer, value); }
+    fn viewport(&self, x:i32, y:i32, width:i32, height:i32) { self.gl.viewport(x, y, width, height); }
+
+    fn create_buffer(&self) -> BufferHandle {
+        let buffer = self.gl.create_buffer().expect_throw("Error creating WebGlBuffer");
+        BufferHandle(self.buffers.borrow_mut().push(buffer))
+    }
+
+    fn delete_buffer(&self, buffer:BufferHandle) {
+        if let Some(buffer) = self.buffers.borrow_mut().try_remove(buffer.0) {
+            self.gl.delete_buffer(Some(&buffer));
+        }
+    }
+
+    fn bind_buffer(&self, target:u32, buffer:Option<BufferHandle>) {
+        self.gl.bind_buffer(target, buffer.map(|x| self.buffer(x)).as_ref());
+    }
+
+    fn bind_buffer_base(&self, target:u32, index:u32, buffer:Option<BufferHandle>) {
+        self.gl.bind_buffer_base(target, index, buffer.map(|x| self.buffer(x)).as_ref());
+    }
+
+    fn buffer_data_with_size(&self, target:u32, size:i32, usage:u32) {
+        self.gl.buffer_data_with_i32(target, size, usage);
+    }
+
+    fn buffer_data(&self, target:u32, data:&[u8], usage:u32) {
+        unsafe {
+            let buffer_view = js_sys::Uint8Array::view(data);
+            self.gl.buffer_data_with_array_buffer_view(target, &buffer_view, usage);
+        }
+    }
+
+    fn buffer_sub_data(&self, target:u32, offset:i32, data:&[u8]) {
+        unsafe {
+            let buffer_view = js_sys::Uint8Array::view(data);
+            self.gl.buffer_sub_data_with_i32_and_array_buffer_view(target, offset, &buffer_view);
+        }
+    }
+
+    fn get_buffer_sub_data(&self, target:u32, offset:i32, dst:&mut [u8]) {
+        let dst_view = js_sys::Uint8Array::new_with_length(dst.len() as u32);
+        self.gl.get_buffer_sub_data_with_i32_and_array_buffer_view(target, offset, &dst_view);
+        dst_view.copy_to(dst);
+    }
+
+    fn copy_buffer_sub_data(&self, read_target:u32, write_target:u32, read_offset:i32, write_offset:i32, size:i32) {
+        self.gl.copy_buffer_sub_data_with_i32_and_i32_and_i32(read_target, write_target, read_offset, write_offset, size);
+    }
+
+    fn create_vertex_array(&self) -> VertexArrayHandle {
+        let vao = self.gl.create_vertex_array().expect_throw("Error creating VAO");
+        VertexArrayHandle(self.vertex_arrays.borrow_mut().push(vao))
+    }
+
+    fn delete_vertex_array(&self, vao:VertexArrayHandle) {
+        if let Some(vao) = self.vertex_arrays.borrow_mut().try_remove(vao.0) {
+            self.gl.delete_vertex_array(Some(&vao));
+        }
+    }
+
+    fn bind_vertex_array(&self, vao:Option<VertexArrayHandle>) {
+        self.gl.bind_vertex_array(vao.map(|x| self.vertex_array(x)).as_ref());
+    }
+
+    fn enable_vertex_attrib_array(&self, location:u32) {
+        self.gl.enable_vertex_attrib_array(location);
+    }
+
+    fn vertex_attrib_pointer(&self, location:u32, size:i32, data_type:u32, normalized:bool, stride:i32, offset:i32) {
+        self.gl.vertex_attrib_pointer_with_i32(location, size, data_type, normalized, stride, offset);
+    }
+
+    fn vertex_attrib_i_pointer(&self, location:u32, size:i32, data_type:u32, stride:i32, offset:i32) {
+        self.gl.vertex_attrib_i_pointer_with_i32(location, size, data_type, stride, offset);
+    }
+
+    fn vertex_attrib_divisor(&self, location:u32, divisor:u32) {
+        self.gl.vertex_attrib_divisor(location, divisor);
+    }
+
+    fn compile_shader(&self, shader_type:u32, source:&str) -> Result<ShaderHandle, String> {
+        let shader = self.gl
+            .create_shader(shader_type)
+            .ok_or_else(|| String::from("Unable to create shader object"))?;
+        self.gl.shader_source(&shader, source);
+        self.gl.compile_shader(&shader);
+
+        if self.gl
+            .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            Ok(ShaderHandle(self.shaders.borrow_mut().push(shader)))
+        } else {
+            let log = self.gl
+                .get_shader_info_log(&shader)
+                .unwrap_or_else(|| String::from("Unknown error creating shader"));
+            self.gl.delete_shader(Some(&shader));
+            Err(log)
+        }
+    }
+
+    fn delete_shader(&self, shader:ShaderHandle) {
+        if let Some(shader) = self.shaders.borrow_mut().try_remove(shader.0) {
+            self.gl.delete_shader(Some(&shader));
+        }
+    }
+
+    fn link_program(&self, vertex_shader:ShaderHandle, fragment_shader:ShaderHandle) -> Result<ProgramHandle, String> {
+        let program = self.gl
+            .create_program()
+            .ok_or_else(|| String::from("Unable to create shader object"))?;
+
+        {
+            let shaders = self.shaders.borrow();
+            self.gl.attach_shader(&program, &shaders[vertex_shader.0]);
+            self.gl.attach_shader(&program, &shaders[fragment_shader.0]);
+        }
+        self.gl.link_program(&program);
+
+        if self.gl
+            .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            Ok(ProgramHandle(self.programs.borrow_mut().push(program)))
+        } else {
+            let log = self.gl
+                .get_program_info_log(&program)
+                .unwrap_or_else(|| String::from("Unknown error creating program object"));
+            self.gl.delete_program(Some(&program));
+            Err(log)
+        }
+    }
+
+    fn delete_program(&self, program:ProgramHandle) {
+        let removed = self.programs.borrow_mut().try_remove(program.0);
+        if let Some(removed) = removed {
+            let mut uniform_locations = self.uniform_locations.borrow_mut();
+            self.cached_uniform_locations.borrow_mut().retain(|(p,_), location| {
+                if *p != program { return true; }
+                if let Some(location) = location { uniform_locations.try_remove(location.0); }
+                false
+            });
+            self.gl.delete_program(Some(&removed));
+        }
+    }
+
+    fn use_program(&self, program:Option<ProgramHandle>) {
+        self.gl.use_program(program.map(|x| self.program(x)).as_ref());
+    }
+
+    fn get_attrib_location(&self, program:ProgramHandle, name:&str) -> i32 {
+        self.gl.get_attrib_location(&self.program(program), name)
+    }
+
+    fn get_uniform_location(&self, program:ProgramHandle, name:&str) -> Option<UniformLocation> {
+        let key = (program, name.to_owned());
+        if let Some(cached) = self.cached_uniform_locations.borrow().get(&key) {
+            return *cached;
+        }
+
+        let location = self.gl.get_uniform_location(&self.program(program), name)
+            .map(|x| UniformLocation(self.uniform_locations.borrow_mut().push(x)));
+        self.cached_uniform_locations.borrow_mut().insert(key, location);
+        location
+    }
+
+    fn get_active_attribs(&self, program:ProgramHandle) -> Vec<ActiveInfo> {
+        let program = self.program(program);
+        let count = self.gl.get_program_parameter(&program, WebGl2RenderingContext::ACTIVE_ATTRIBUTES).as_f64().unwrap_or(0.0) as u32;
+        (0..count).filter_map(|i| self.gl.get_active_attrib(&program, i)).map(|x| active_info(&x)).collect()
+    }
+
+    fn get_active_uniforms(&self, program:ProgramHandle) -> Vec<ActiveInfo> {
+        let program = self.program(program);
+        let count = self.gl.get_program_parameter(&program, WebGl2RenderingContext::ACTIVE_UNIFORMS).as_f64().unwrap_or(0.0) as u32;
+        //members of uniform blocks are set through their buffer, not by location
+        let indices:js_sys::Array = (0..count).map(JsValue::from).collect();
+        let blocks = js_sys::Array::from(&self.gl.get_active_uniforms(&program, &indices, WebGl2RenderingContext::UNIFORM_BLOCK_INDEX));
+        (0..count).filter(|i| blocks.get(*i).as_f64() == Some(-1.0))
+            .filter_map(|i| self.gl.get_active_uniform(&program, i)).map(|x| active_info(&x)).collect()
+    }
+
+    fn get_uniform_block_index(&self, program:ProgramHandle, name:&str) -> Option<u32> {
+        match self.gl.get_uniform_block_index(&self.program(program), name) {
+            WebGl2RenderingContext::INVALID_INDEX => None,
+            index => Some(index)
+        }
+    }
+
+    fn uniform_block_binding(&self, program:ProgramHandle, block_index:u32, binding:u32) {
+        self.gl.uniform_block_binding(&self.program(program), block_index, binding);
+    }
+
+    fn uniform1f(&self, location:UniformLocation, x:f32) {
+        self.gl.uniform1f(Some(&self.uniform_location(location)), x);
+    }
+
+    fn uniform2f(&self, location:UniformLocation, x:f32, y:f32) {
+        self.gl.uniform2f(Some(&self.uniform_location(location)), x, y);
+    }
+
+    fn uniform3f(&self, location:UniformLocation, x:f32, y:f32, z:f32) {
+        self.gl.uniform3f(Some(&self.uniform_location(location)), x, y, z);
+    }
+
+    fn uniform4f(&self, location:UniformLocation, x:f32, y:f32, z:f32, w:f32) {
+        self.gl.uniform4f(Some(&self.uniform_location(location)), x, y, z, w);
+    }
+
+    fn uniform1i(&self, location:UniformLocation, x:i32) {
+        self.gl.uniform1i(Some(&self.uniform_location(location)), x);
+    }
+
+    fn uniform1ui(&self, location:UniformLocation, x:u32) {
+        self.gl.uniform1ui(Some(&self.uniform_location(location)), x);
+    }
+
+    fn uniform1fv(&self, location:UniformLocation, data:&[f32]) {
+        self.gl.uniform1fv_with_f32_array(Some(&self.uniform_location(location)), data);
+    }
+
+    fn uniform2fv(&self, location:UniformLocation, data:&[f32]) {
+        self.gl.uniform2fv_with_f32_array(Some(&self.uniform_location(location)), data);
+    }
+
+    fn uniform3fv(&self, location:UniformLocation, data:&[f32]) {
+        self.gl.uniform3fv_with_f32_array(Some(&self.uniform_location(location)), data);
+    }
+
+    fn uniform4fv(&self, location:UniformLocation, data:&[f32]) {
+        self.gl.uniform4fv_with_f32_array(Some(&self.uniform_location(location)), data);
+    }
+
+    fn uniform1iv(&self, location:UniformLocation, data:&[i32]) {
+        self.gl.uniform1iv_with_i32_array(Some(&self.uniform_location(location)), data);
+    }
+
+    fn uniform_matrix3fv(&self, location:UniformLocation, transpose:bool, data:&[f32]) {
+        self.gl.uniform_matrix3fv_with_f32_array(Some(&self.uniform_location(location)), transpose, data);
+    }
+
+    fn uniform_matrix4fv(&self, location:UniformLocation, transpose:bool, data:&[f32]) {
+        self.gl.uniform_matrix4fv_with_f32_array(Some(&self.uniform_location(location)), transpose, data);
+    }
+
+    fn create_texture(&self) -> TextureHandle {
+        let texture = self.gl.create_texture().expect_throw("Render Error: Unable to create texture");
+        TextureHandle(self.textures.borrow_mut().push(texture))
+    }
+
+    fn delete_texture(&self, texture:TextureHandle) {
+        if let Some(texture) = self.textures.borrow_mut().try_remove(texture.0) {
+            self.gl.delete_texture(Some(&texture));
+        }
+    }
+
+    fn active_texture(&self, unit:u32) {
+        self.gl.active_texture(unit);
+    }
+
+    fn bind_texture(&self, target:u32, texture:Option<TextureHandle>) {
+        self.gl.bind_texture(target, texture.map(|x| self.texture(x)).as_ref());
+    }
+
+    fn tex_parameteri(&self, target:u32, pname:u32, param:i32) {
+        self.gl.tex_parameteri(target, pname, param);
+    }
+
+    fn tex_parameterf(&self, target:u32, pname:u32, param:f32) {
+        self.gl.tex_parameterf(target, pname, param);
+    }
+
+    fn tex_image_2d(&self, target:u32, level:i32, internal_format:i32, width:i32, height:i32, format:u32, data_type:u32, pixels:Option<&[u8]>) {
+        self.gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            0,
+            format,
+            data_type,
+            pixels
+        ).expect_throw("Error uploading texture data");
+    }
+
+    fn tex_sub_image_2d(&self, target:u32, level:i32, x:i32, y:i32, width:i32, height:i32, format:u32, data_type:u32, pixels:&[u8]) {
+        unsafe {
+            let buffer_view = js_sys::Uint8Array::view(pixels);
+            self.gl.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
+                target,
+                level,
+                x,
+                y,
+                width,
+                height,
+                format,
+                data_type,
+                Some(&buffer_view)
+            ).expect_throw("Texture sub image upload failed.");
+        }
+    }
+
+    fn tex_sub_image_2d_with_image(&self, target:u32, level:i32, x:i32, y:i32, format:u32, data_type:u32, image:&HtmlImageElement) {
+        self.gl.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_html_image_element(
+            target,
+            level,
+            x,
+            y,
+            image.width() as i32,
+            image.height() as i32,
+            format,
+            data_type,
+            image
+        ).expect_throw("Error uploading HtmlImageElement to texture");
+    }
+
+    fn copy_tex_sub_image_2d(&self, target:u32, level:i32, x_offset:i32, y_offset:i32, x:i32, y:i32, width:i32, height:i32) {
+        self.gl.copy_tex_sub_image_2d(target, level, x_offset, y_offset, x, y, width, height);
+    }
+
+    fn generate_mipmap(&self, target:u32) {
+        self.gl.generate_mipmap(target);
+    }
+
+    //getting the extension is what enables it
+    fn max_anisotropy(&self) -> Option<f32> {
+        self.gl.get_extension("EXT_texture_filter_anisotropic").ok().flatten()?;
+        self.gl.get_parameter(MAX_TEXTURE_MAX_ANISOTROPY_EXT).ok()?.as_f64().map(|x| x as f32)
+    }
+
+    fn create_framebuffer(&self) -> FramebufferHandle {
+        let framebuffer = self.gl.create_framebuffer().expect_throw("Render Error: Unable to create framebuffer");
+        FramebufferHandle(self.framebuffers.borrow_mut().push(framebuffer))
+    }
+
+    fn delete_framebuffer(&self, framebuffer:FramebufferHandle) {
+        if let Some(framebuffer) = self.framebuffers.borrow_mut().try_remove(framebuffer.0) {
+            self.gl.delete_framebuffer(Some(&framebuffer));
+        }
+    }
+
+    fn bind_framebuffer(&self, target:u32, framebuffer:Option<FramebufferHandle>) {
+        self.gl.bind_framebuffer(target, framebuffer.map(|x| self.framebuffer(x)).as_ref());
+    }
+
+    fn framebuffer_texture_2d(&self, target:u32, attachment:u32, tex_target:u32, texture:Option<TextureHandle>, level:i32) {
+        self.gl.framebuffer_texture_2d(target, attachment, tex_target, texture.map(|x| self.texture(x)).as_ref(), level);
+    }
+
+    fn framebuffer_renderbuffer(&self, target:u32, attachment:u32, renderbuffer_target:u32, renderbuffer:Option<RenderbufferHandle>) {
+        self.gl.framebuffer_renderbuffer(target, attachment, renderbuffer_target, renderbuffer.map(|x| self.renderbuffer(x)).as_ref());
+    }
+
+    fn check_framebuffer_status(&self, target:u32) -> u32 {
+        self.gl.check_framebuffer_status(target)
+    }
+
+    //the array read into has to match data_type
+    fn read_pixels(&self, x:i32, y:i32, width:i32, height:i32, format:u32, data_type:u32, dst:&mut [u8]) {
+        match data_type {
+            WebGl2RenderingContext::UNSIGNED_INT => {
+                let dst_view = js_sys::Uint32Array::new_with_length((dst.len() / 4) as u32);
+                self.gl.read_pixels_with_opt_array_buffer_view(x, y, width, height, format, data_type, Some(&dst_view)).expect_throw("Error reading pixels");
+                for (bytes, value) in dst.chunks_exact_mut(4).zip(dst_view.to_vec()) {
+                    bytes.copy_from_slice(&value.to_ne_bytes());
+                }
+            },
+            _ => {
+                let dst_view = js_sys::Uint8Array::new_with_length(dst.len() as u32);
+                self.gl.read_pixels_with_opt_array_buffer_view(x, y, width, height, format, data_type, Some(&dst_view)).expect_throw("Error reading pixels");
+                dst_view.copy_to(dst);
+            }
+        }
+    }
+
+    fn create_renderbuffer(&self) -> RenderbufferHandle {
+        let renderbuffer = self.gl.create_renderbuffer().expect_throw("Render Error: Unable to create renderbuffer");
+        RenderbufferHandle(self.renderbuffers.borrow_mut().push(renderbuffer))
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer:RenderbufferHandle) {
+        if let Some(renderbuffer) = self.renderbuffers.borrow_mut().try_remove(renderbuffer.0) {
+            self.gl.delete_renderbuffer(Some(&renderbuffer));
+        }
+    }
+
+    fn bind_renderbuffer(&self, target:u32, renderbuffer:Option<RenderbufferHandle>) {
+        self.gl.bind_renderbuffer(target, renderbuffer.map(|x| self.renderbuffer(x)).as_ref());
+    }
+
+    fn renderbuffer_storage(&self, target:u32, internal_format:u32, width:i32, height:i32) {
+        self.gl.renderbuffer_storage(target, internal_format, width, height);
+    }
+
+    fn draw_elements(&self, mode:u32, count:i32, index_type:u32, offset:i32) {
+        self.gl.draw_elements_with_i32(mode, count, index_type, offset);
+    }
+
+    fn draw_elements_instanced(&self, mode:u32, count:i32, index_type:u32, offset:i32, instance_count:i32) {
+        self.gl.draw_elements_instanced_with_i32(mode, count, index_type, offset, instance_count);
+    }
+}
+
+fn active_info(info:&WebGlActiveInfo) -> ActiveInfo {
+    ActiveInfo { name:info.name(), size:info.size(), data_type:info.type_() }
+}
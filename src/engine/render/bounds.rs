@@ -0,0 +1,148 @@
+use cgmath::{Point2, Point3, Vector2, Vector3, Vector4, Matrix4, InnerSpace};
+
+//axis aligned rectangle on the xy plane, min is the bottom left corner
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rect {
+    pub min:Point2<f32>,
+    pub max:Point2<f32>
+}
+
+impl Rect {
+    pub fn new(min:Point2<f32>, max:Point2<f32>) -> Self {
+        Self { min:min, max:max }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    pub fn size(&self) -> Vector2<f32> {
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Point2<f32> {
+        self.min + self.size() / 2.0
+    }
+
+    //edges count as inside for all of these
+    pub fn contains_point(&self, point:Point2<f32>) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x && point.y >= self.min.y && point.y <= self.max.y
+    }
+
+    pub fn contains(&self, other:&Rect) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    pub fn intersects(&self, other:&Rect) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x && self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
+
+    //0 for points inside
+    pub fn distance_to(&self, point:Point2<f32>) -> f32 {
+        let dx = (self.min.x - point.x).max(point.x - self.max.x).max(0.0);
+        let dy = (self.min.y - point.y).max(point.y - self.max.y).max(0.0);
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+//axis aligned box, min is the corner with the smallest coordinates
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aabb {
+    pub min:Point3<f32>,
+    pub max:Point3<f32>
+}
+
+impl Aabb {
+    pub fn new(min:Point3<f32>, max:Point3<f32>) -> Self {
+        Self { min:min, max:max }
+    }
+
+    //smallest box holding every point, None without any
+    pub fn from_points(points:impl IntoIterator<Item = Point3<f32>>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |acc, x| acc.union(&Self::new(x, x))))
+    }
+
+    pub fn union(&self, other:&Aabb) -> Self {
+        Self::new(
+            Point3::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y), self.min.z.min(other.min.z)),
+            Point3::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y), self.max.z.max(other.max.z))
+        )
+    }
+
+    //extent on the xy plane
+    pub fn rect(&self) -> Rect {
+        Rect::new(Point2::new(self.min.x, self.min.y), Point2::new(self.max.x, self.max.y))
+    }
+
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Point3::new(a.x, a.y, a.z), Point3::new(b.x, a.y, a.z), Point3::new(a.x, b.y, a.z), Point3::new(b.x, b.y, a.z),
+            Point3::new(a.x, a.y, b.z), Point3::new(b.x, a.y, b.z), Point3::new(a.x, b.y, b.z), Point3::new(b.x, b.y, b.z)
+        ]
+    }
+
+    //whether the box is entirely outside the frustum transform maps into clip space. Only checks the corners against
+    //one plane at a time, so boxes near the frustum's edges can count as inside without being seen
+    pub fn outside_clip(&self, transform:&Matrix4<f32>) -> bool {
+        let corners:Vec<Vector4<f32>> = self.corners().iter().map(|x| transform * x.to_homogeneous()).collect();
+        (0..3).any(|axis| corners.iter().all(|c| c[axis] < -c.w) || corners.iter().all(|c| c[axis] > c.w))
+    }
+}
+
+//half line from origin along direction, which is normalized
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ray {
+    pub origin:Point3<f32>,
+    pub direction:Vector3<f32>
+}
+
+impl Ray {
+    pub fn new(origin:Point3<f32>, direction:Vector3<f32>) -> Self {
+        Self { origin:origin, direction:direction.normalize() }
+    }
+
+    pub fn at(&self, distance:f32) -> Point3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    //where the ray crosses the plane at height z, None if it runs parallel to it or points away
+    pub fn intersect_z(&self, z:f32) -> Option<Point3<f32>> {
+        if self.direction.z.abs() <= f32::EPSILON { return None; }
+        let distance = (z - self.origin.z) / self.direction.z;
+        match distance >= 0.0 {
+            true => Some(self.at(distance)),
+            false => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Point3, Vector3, InnerSpace};
+
+    use super::Ray;
+
+    #[test]
+    fn intersects_planes_ahead_of_it() {
+        let ray = Ray::new(Point3::new(1.0, 2.0, 10.0), Vector3::new(1.0, 0.0, -1.0));
+        let hit = ray.intersect_z(4.0).unwrap();
+        assert!((hit - Point3::new(7.0, 2.0, 4.0)).magnitude() < 1e-5);
+        assert_eq!(ray.intersect_z(10.0), Some(ray.origin));
+        //behind the origin
+        assert_eq!(ray.intersect_z(12.0), None);
+    }
+
+    #[test]
+    fn parallel_rays_miss() {
+        let ray = Ray::new(Point3::new(0.0, 0.0, 1.0), Vector3::new(1.0, 1.0, 0.0));
+        assert_eq!(ray.intersect_z(0.0), None);
+        assert_eq!(ray.intersect_z(1.0), None);
+    }
+}
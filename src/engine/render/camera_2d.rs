@@ -0,0 +1,240 @@
+use cgmath::{Point2, Vector2, Matrix4, EuclideanSpace, ortho};
+use instant::Duration;
+
+use super::bounds::Rect;
+
+//how far in front of and behind the xy plane content is still drawn. z orders overlapping content like it does
+//under the perspective camera, larger is closer
+const DEPTH:f32 = 1000.0;
+
+#[derive(Clone, Debug)]
+struct Transition {
+    from_center:Point2<f32>,
+    from_zoom:f32,
+    to_center:Point2<f32>,
+    to_zoom:f32,
+    elapsed:f32,
+    duration:f32
+}
+
+//orthographic camera looking down -z at the xy plane, for boards and other 2D content. +y is up in the world,
+//screen positions are in pixels from the top left of the viewport and zoom is in pixels per world unit
+#[derive(Clone, Debug)]
+pub struct Camera2D {
+    center:Point2<f32>,
+    zoom:f32,
+    min_zoom:f32,
+    max_zoom:f32,
+    viewport:Vector2<f32>,
+    transition:Option<Transition>
+}
+
+impl Camera2D {
+    pub fn new(center:Point2<f32>, zoom:f32, width:u32, height:u32) -> Self {
+        Self {
+            center:center,
+            zoom:zoom,
+            min_zoom:f32::MIN_POSITIVE,
+            max_zoom:f32::MAX,
+            viewport:Vector2::new(width as f32, height as f32),
+            transition:None
+        }
+    }
+
+    //world point at the middle of the viewport
+    pub fn center(&self) -> Point2<f32> {
+        self.center
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    pub fn viewport(&self) -> Vector2<f32> {
+        self.viewport
+    }
+
+    //setting the view directly stops any transition
+    pub fn set_center(&mut self, center:Point2<f32>) {
+        self.transition = None;
+        self.center = center;
+    }
+
+    pub fn set_zoom(&mut self, zoom:f32) {
+        self.transition = None;
+        self.zoom = self.clamp_zoom(zoom);
+    }
+
+    pub fn set_zoom_limits(&mut self, min_zoom:f32, max_zoom:f32) {
+        assert!(min_zoom > 0.0 && min_zoom <= max_zoom, "Camera2D zoom limits {}..{} are not positive and ordered", min_zoom, max_zoom);
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+        self.zoom = self.clamp_zoom(self.zoom);
+    }
+
+    //kept in sync with the canvas by the renderer
+    pub fn resize(&mut self, width:u32, height:u32) {
+        self.viewport = Vector2::new(width as f32, height as f32);
+    }
+
+    //moves the view by a drag of delta pixels so the content follows the cursor
+    pub fn pan(&mut self, delta:Vector2<f32>) {
+        self.transition = None;
+        self.center += Vector2::new(-delta.x, delta.y) / self.zoom;
+    }
+
+    //multiplies the zoom while keeping the world point under cursor where it is on screen
+    pub fn zoom_at(&mut self, factor:f32, cursor:Point2<f32>) {
+        let before = self.screen_to_world(cursor);
+        self.set_zoom(self.zoom * factor);
+        let after = self.screen_to_world(cursor);
+        self.center += before - after;
+    }
+
+    pub fn screen_to_world(&self, screen:Point2<f32>) -> Point2<f32> {
+        self.center + Vector2::new(screen.x - self.viewport.x / 2.0, self.viewport.y / 2.0 - screen.y) / self.zoom
+    }
+
+    pub fn world_to_screen(&self, world:Point2<f32>) -> Point2<f32> {
+        let offset = (world - self.center) * self.zoom;
+        Point2::new(self.viewport.x / 2.0 + offset.x, self.viewport.y / 2.0 - offset.y)
+    }
+
+    //centers on bounds, zoomed so they fill the viewport less padding pixels on every side
+    pub fn fit_bounds(&mut self, bounds:&Rect, padding:f32) {
+        let (center, zoom) = self.fitted(bounds, padding);
+        self.set_center(center);
+        self.set_zoom(zoom);
+    }
+
+    //eases from the current view to the new one over duration, advanced by update
+    pub fn animate_to(&mut self, center:Point2<f32>, zoom:f32, duration:Duration) {
+        self.transition = Some(Transition {
+            from_center:self.center,
+            from_zoom:self.zoom,
+            to_center:center,
+            to_zoom:self.clamp_zoom(zoom),
+            elapsed:0.0,
+            duration:duration.as_secs_f32()
+        });
+    }
+
+    pub fn animate_fit_bounds(&mut self, bounds:&Rect, padding:f32, duration:Duration) {
+        let (center, zoom) = self.fitted(bounds, padding);
+        self.animate_to(center, zoom, duration);
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    //call once per frame
+    pub fn update(&mut self, dt:Duration) {
+        let transition = match &mut self.transition {
+            Some(transition) => transition,
+            None => return
+        };
+        transition.elapsed += dt.as_secs_f32();
+        let t = match transition.duration > 0.0 {
+            true => (transition.elapsed / transition.duration).min(1.0),
+            false => 1.0
+        };
+        let eased = t * t * (3.0 - 2.0 * t);
+        //zoom is interpolated geometrically so zooming in and out feel equally fast
+        self.zoom = transition.from_zoom * (transition.to_zoom / transition.from_zoom).powf(eased);
+        self.center = transition.from_center + (transition.to_center - transition.from_center) * eased;
+        if t >= 1.0 {
+            self.transition = None;
+        }
+    }
+
+    pub fn calc_view(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(-self.center.to_vec().extend(0.0))
+    }
+
+    pub fn calc_projection(&self) -> Matrix4<f32> {
+        let half = self.viewport / (2.0 * self.zoom);
+        ortho(-half.x, half.x, -half.y, half.y, -DEPTH, DEPTH)
+    }
+
+    fn fitted(&self, bounds:&Rect, padding:f32) -> (Point2<f32>, f32) {
+        let available = (self.viewport - Vector2::new(padding, padding) * 2.0).map(|x| x.max(1.0));
+        let zoom = match bounds.width() > 0.0 || bounds.height() > 0.0 {
+            true => (available.x / bounds.width()).min(available.y / bounds.height()),
+            false => self.zoom
+        };
+        (bounds.center(), self.clamp_zoom(zoom))
+    }
+
+    fn clamp_zoom(&self, zoom:f32) -> f32 {
+        zoom.max(self.min_zoom).min(self.max_zoom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Point2, Vector2, InnerSpace};
+    use instant::Duration;
+
+    use super::Camera2D;
+    use crate::engine::render::bounds::Rect;
+
+    fn camera() -> Camera2D {
+        Camera2D::new(Point2::new(0.0, 0.0), 10.0, 200, 100)
+    }
+
+    #[test]
+    fn zoom_is_kept_within_its_limits() {
+        let mut camera = camera();
+        camera.set_zoom_limits(2.0, 20.0);
+        camera.set_zoom(50.0);
+        assert_eq!(camera.zoom(), 20.0);
+        camera.set_zoom(0.5);
+        assert_eq!(camera.zoom(), 2.0);
+
+        //narrowing the limits clamps the current zoom
+        camera.set_zoom(15.0);
+        camera.set_zoom_limits(1.0, 5.0);
+        assert_eq!(camera.zoom(), 5.0);
+    }
+
+    #[test]
+    fn zooming_at_the_limit_keeps_the_cursor_in_place() {
+        let mut camera = camera();
+        camera.set_zoom_limits(1.0, 20.0);
+        let cursor = Point2::new(150.0, 25.0);
+        let under = camera.screen_to_world(cursor);
+        camera.zoom_at(4.0, cursor);
+        assert_eq!(camera.zoom(), 20.0);
+        assert!((camera.screen_to_world(cursor) - under).magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn fitting_and_animating_respect_the_limits() {
+        let mut camera = camera();
+        camera.set_zoom_limits(1.0, 20.0);
+        let small = Rect::new(Point2::new(1.0, 1.0), Point2::new(2.0, 2.0));
+        camera.fit_bounds(&small, 0.0);
+        assert_eq!(camera.center(), Point2::new(1.5, 1.5));
+        assert_eq!(camera.zoom(), 20.0);
+
+        camera.animate_to(Point2::new(0.0, 0.0), 0.1, Duration::from_secs(1));
+        camera.update(Duration::from_secs(2));
+        assert!(!camera.is_animating());
+        assert_eq!(camera.zoom(), 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn limits_have_to_be_ordered() {
+        camera().set_zoom_limits(5.0, 1.0);
+    }
+
+    #[test]
+    fn pans_with_the_cursor() {
+        let mut camera = camera();
+        let world = camera.screen_to_world(Point2::new(50.0, 50.0));
+        camera.pan(Vector2::new(20.0, -10.0));
+        assert_eq!(camera.screen_to_world(Point2::new(70.0, 40.0)), world);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::engine::render::{render_object::{RenderType, UniformAttrib, UniformRole}, post_process::effect_type};
+
+pub const NAME:&str = "blur";
+
+//3x3 gaussian with its taps radius pixels apart, add the effect several times for a wider blur
+pub fn render_type() -> RenderType {
+    effect_type(
+        String::from(
+            "#version 300 es
+
+            precision highp float;
+
+            in vec2 vUv;
+
+            out vec4 fragColor;
+
+            uniform sampler2D source;
+            uniform vec2 resolution;
+            uniform float radius;
+
+            vec4 blur(vec2 uv, vec2 step) {
+                vec4 result = vec4(0.0);
+                for(int x = -1; x <= 1; x++) {
+                    for(int y = -1; y <= 1; y++) {
+                        float weight = (2.0 - abs(float(x))) * (2.0 - abs(float(y))) / 16.0;
+                        result += texture(source, uv + vec2(float(x), float(y)) * step) * weight;
+                    }
+                }
+                return result;
+            }
+
+            void main() {
+                fragColor = blur(vUv, radius / resolution);
+            }"
+        ),
+        vec![
+            UniformAttrib {
+                name:String::from("radius"),
+                role:UniformRole::Custom
+            }
+        ]
+    )
+}
@@ -0,0 +1,39 @@
+use crate::engine::render::{render_object::{RenderType, UniformAttrib, UniformRole}, post_process::effect_type};
+
+pub const NAME:&str = "color_grading";
+
+//exposure is in stops, contrast and saturation scale by 1 + their value
+pub fn render_type() -> RenderType {
+    effect_type(
+        String::from(
+            "#version 300 es
+
+            precision highp float;
+
+            in vec2 vUv;
+
+            out vec4 fragColor;
+
+            uniform sampler2D source;
+            uniform float exposure;
+            uniform float contrast;
+            uniform float saturation;
+
+            vec3 grade(vec3 color) {
+                color *= exp2(exposure);
+                color = (color - 0.5) * (1.0 + contrast) + 0.5;
+                float luma = dot(color, vec3(0.299, 0.587, 0.114));
+                return clamp(mix(vec3(luma), color, 1.0 + saturation), 0.0, 1.0);
+            }
+
+            void main() {
+                vec4 color = texture(source, vUv);
+                fragColor = vec4(grade(color.rgb), color.a);
+            }"
+        ),
+        ["exposure", "contrast", "saturation"].iter().map(|x| UniformAttrib {
+            name:String::from(*x),
+            role:UniformRole::Custom
+        }).collect()
+    )
+}
@@ -0,0 +1,57 @@
+use crate::engine::render::{render_object::RenderType, post_process::effect_type};
+
+pub const NAME:&str = "fxaa";
+
+//fast approximate anti aliasing, smooths edges along the direction the luma changes in.
+//https://github.com/mattdesl/glsl-fxaa
+pub fn render_type() -> RenderType {
+    effect_type(
+        String::from(
+            "#version 300 es
+
+            precision highp float;
+
+            in vec2 vUv;
+
+            out vec4 fragColor;
+
+            uniform sampler2D source;
+            uniform vec2 resolution;
+
+            const float REDUCE_MIN = 1.0 / 128.0;
+            const float REDUCE_MUL = 1.0 / 8.0;
+            const float SPAN_MAX = 8.0;
+            const vec3 LUMA = vec3(0.299, 0.587, 0.114);
+
+            vec4 fxaa(vec2 uv) {
+                vec2 texel = 1.0 / resolution;
+                float lumaNW = dot(texture(source, uv + vec2(-1.0, -1.0) * texel).rgb, LUMA);
+                float lumaNE = dot(texture(source, uv + vec2(1.0, -1.0) * texel).rgb, LUMA);
+                float lumaSW = dot(texture(source, uv + vec2(-1.0, 1.0) * texel).rgb, LUMA);
+                float lumaSE = dot(texture(source, uv + vec2(1.0, 1.0) * texel).rgb, LUMA);
+                vec4 center = texture(source, uv);
+                float lumaM = dot(center.rgb, LUMA);
+                float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+                float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+                vec2 dir = vec2(-((lumaNW + lumaNE) - (lumaSW + lumaSE)), (lumaNW + lumaSW) - (lumaNE + lumaSE));
+                float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+                float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+                dir = clamp(dir * rcpDirMin, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+                vec3 rgbA = 0.5 * (texture(source, uv + dir * (1.0 / 3.0 - 0.5)).rgb + texture(source, uv + dir * (2.0 / 3.0 - 0.5)).rgb);
+                vec3 rgbB = rgbA * 0.5 + 0.25 * (texture(source, uv - dir * 0.5).rgb + texture(source, uv + dir * 0.5).rgb);
+                float lumaB = dot(rgbB, LUMA);
+                if(lumaB < lumaMin || lumaB > lumaMax) {
+                    return vec4(rgbA, center.a);
+                }
+                return vec4(rgbB, center.a);
+            }
+
+            void main() {
+                fragColor = fxaa(vUv);
+            }"
+        ),
+        Vec::new()
+    )
+}
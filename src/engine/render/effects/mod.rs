@@ -0,0 +1,15 @@
+pub mod blur;
+pub mod color_grading;
+pub mod fxaa;
+pub mod vignette;
+
+use super::shader_registry::ShaderRegistry;
+
+//post processing effects every renderer starts with, added to the chain by their NAME with Renderer::add_effect.
+//Every uniform of these is neutral at zero, so an effect only changes the image once its uniforms are set
+pub fn register_builtin(shaders:&mut ShaderRegistry) {
+    shaders.register(blur::NAME, blur::render_type());
+    shaders.register(color_grading::NAME, color_grading::render_type());
+    shaders.register(fxaa::NAME, fxaa::render_type());
+    shaders.register(vignette::NAME, vignette::render_type());
+}
@@ -0,0 +1,37 @@
+use crate::engine::render::{render_object::{RenderType, UniformAttrib, UniformRole}, post_process::effect_type};
+
+pub const NAME:&str = "vignette";
+
+//darkens towards the corners, strength is how much of the color is left out at them
+pub fn render_type() -> RenderType {
+    effect_type(
+        String::from(
+            "#version 300 es
+
+            precision highp float;
+
+            in vec2 vUv;
+
+            out vec4 fragColor;
+
+            uniform sampler2D source;
+            uniform float strength;
+
+            float vignette(vec2 uv) {
+                float d = length(uv - 0.5) * 1.41421356;
+                return 1.0 - strength * d * d;
+            }
+
+            void main() {
+                vec4 color = texture(source, vUv);
+                fragColor = vec4(color.rgb * vignette(vUv), color.a);
+            }"
+        ),
+        vec![
+            UniformAttrib {
+                name:String::from("strength"),
+                role:UniformRole::Custom
+            }
+        ]
+    )
+}
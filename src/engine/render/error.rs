@@ -0,0 +1,38 @@
+use std::fmt;
+
+use super::{render_object::IndexFormat, program::ShaderError, layer::LayerId};
+
+//why the renderer couldn't draw what it was given
+#[derive(Clone, Debug)]
+pub enum RenderError {
+    //the object needs more verticies than one chunk of its render type's index format can address
+    IndexOverflow { verticies:usize, index_format:IndexFormat },
+    //nothing is registered under the name
+    UnknownRenderType(String),
+    //the render type's shaders failed, and so did its fallback if it has one
+    Shader(ShaderError),
+    //checkFramebufferStatus of an offscreen target, e.g. for a zero sized canvas
+    IncompleteFramebuffer(u32),
+    //the layer was never added to this renderer
+    UnknownLayer(LayerId)
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IndexOverflow { verticies, index_format } => write!(f, "Render object needs {} verticies, more than {:?} indicies can address", verticies, index_format),
+            Self::UnknownRenderType(name) => write!(f, "No render type registered as {}", name),
+            Self::Shader(e) => e.fmt(f),
+            Self::IncompleteFramebuffer(status) => write!(f, "Framebuffer incomplete, status {:#x}", status),
+            Self::UnknownLayer(layer) => write!(f, "Render object is in {:?}, which was never added", layer)
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<ShaderError> for RenderError {
+    fn from(e:ShaderError) -> Self {
+        Self::Shader(e)
+    }
+}
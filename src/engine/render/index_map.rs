@@ -31,6 +31,10 @@ impl<T> IndexMap<T> {
         self.last_idx
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
     pub fn iter(&self) -> Iter<'_, (usize, T)> {
         self.vec.iter()
     }
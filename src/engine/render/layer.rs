@@ -0,0 +1,54 @@
+use cgmath::{Point3, Rad};
+
+use super::camera::Camera;
+
+//index of a layer in the renderer, layers are drawn in the order they were added
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct LayerId(pub(super) usize);
+
+//layers every renderer starts with, render objects go into CONTENT unless told otherwise
+impl LayerId {
+    pub const BACKGROUND:LayerId = LayerId(0);
+    pub const CONTENT:LayerId = LayerId(1);
+    pub const ANNOTATIONS:LayerId = LayerId(2);
+    pub const OVERLAY:LayerId = LayerId(3);
+}
+
+//render objects drawn together over the layers before them. The depth buffer is cleared before each layer, so a
+//layer covers the ones under it whatever the depth of their content. Inside a layer objects are drawn by z index
+#[derive(Debug)]
+pub struct Layer {
+    name:String,
+    pub visible:bool,
+    //fades opaque types by blending them with a constant, translucent ones multiply it into their alpha through an
+    //opacity uniform, which all the built in types declare. Translucent types without one ignore it, and objects
+    //fade one by one, so they show through each other where they overlap
+    pub opacity:f32,
+    //drawn from this camera instead of the renderer's or a render pass's
+    pub camera:Option<Camera>
+}
+
+impl Layer {
+    pub fn new(name:&str) -> Self {
+        Self { name:String::from(name), visible:true, opacity:1.0, camera:None }
+    }
+
+    pub fn with_camera(mut self, camera:Camera) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+pub(super) fn builtin() -> Vec<Layer> {
+    vec![
+        Layer::new("background"),
+        Layer::new("content"),
+        Layer::new("annotations"),
+        //fixed in front of the screen like the renderer's starting camera, for ui that ignores the world camera
+        Layer::new("overlay").with_camera(Camera::new(Point3::new(0.0, 0.0, 1.0), Rad(-1.57079633), Rad(0.0)))
+    ]
+}
@@ -1,7 +1,18 @@
-mod render_object;
-mod program;
+pub mod render_object;
+pub mod program;
+pub mod error;
 mod index_map;
+pub mod backend;
 pub mod camera;
+pub mod camera_2d;
+pub mod bounds;
 pub mod texture;
 pub mod renderer;
-pub mod types;
\ No newline at end of file
+pub mod shader_registry;
+pub mod uniform_buffer;
+pub mod render_target;
+pub mod layer;
+pub mod pick;
+pub mod post_process;
+pub mod types;
+pub mod effects;
\ No newline at end of file
@@ -0,0 +1,160 @@
+use web_sys::WebGl2RenderingContext;
+
+use super::{backend::{Backend, FramebufferHandle, RenderbufferHandle, TextureHandle}, render_object::RenderType, error::RenderError};
+
+//Picking draws every render object again into an integer target, each writing an id instead of a color. The programs
+//for it link a render type's own vertex shader with a pick fragment shader, the one the type declares with
+//RenderType::with_pick_shader or else one picking everything the type's triangles cover. Declared ones discard what
+//the type's fragment shader discards or leaves fully transparent, so strokes and transparent parts of images pick
+//exactly as drawn
+
+//uniform pick fragment shaders write, 0 is left for the background
+pub const PICK_ID:&str = "pickId";
+//flat uint instanced vertex shaders set to gl_InstanceID, pick fragment shaders of instanced types add it to PICK_ID
+pub const PICK_INSTANCE:&str = "pickInstance";
+
+//default pick fragment shader of batched types
+pub const PICK_FRAGMENT:&str = "#version 300 es
+uniform highp uint pickId;
+out highp uint pickOutput;
+void main() {
+    pickOutput = pickId;
+}";
+
+//default pick fragment shader of instanced types, their vertex shaders have to write PICK_INSTANCE
+pub const PICK_INSTANCED_FRAGMENT:&str = "#version 300 es
+flat in highp uint pickInstance;
+uniform highp uint pickId;
+out highp uint pickOutput;
+void main() {
+    pickOutput = pickId + pickInstance;
+}";
+
+//what the render type's pick program links its vertex shader with
+pub(super) fn fragment_shader(render_type:&RenderType) -> String {
+    match (&render_type.pick_shader, &render_type.instanced) {
+        (Some(shader), _) => shader.clone(),
+        (None, Some(_)) => String::from(PICK_INSTANCED_FRAGMENT),
+        (None, None) => String::from(PICK_FRAGMENT)
+    }
+}
+
+//integer color attachment the ids are drawn into, with a depth buffer of its own
+#[derive(Debug)]
+pub(super) struct PickTarget {
+    gl:Backend,
+    framebuffer:FramebufferHandle,
+    texture:TextureHandle,
+    depth:RenderbufferHandle,
+    width:u32,
+    height:u32
+}
+
+impl Drop for PickTarget {
+    fn drop(&mut self) {
+        self.gl.delete_framebuffer(self.framebuffer);
+        self.gl.delete_texture(self.texture);
+        self.gl.delete_renderbuffer(self.depth);
+    }
+}
+
+impl PickTarget {
+    pub fn new(gl:&Backend, width:u32, height:u32) -> Result<Self,RenderError> {
+        //integer textures can't be filtered or batched, so it is kept out of the texture batcher
+        let texture = gl.create_texture();
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(texture));
+        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_MIN_FILTER, WebGl2RenderingContext::NEAREST as i32);
+        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_MAG_FILTER, WebGl2RenderingContext::NEAREST as i32);
+        gl.tex_image_2d(WebGl2RenderingContext::TEXTURE_2D, 0, WebGl2RenderingContext::R32UI as i32, width as i32, height as i32, WebGl2RenderingContext::RED_INTEGER, WebGl2RenderingContext::UNSIGNED_INT, None);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+
+        let depth = gl.create_renderbuffer();
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(depth));
+        gl.renderbuffer_storage(WebGl2RenderingContext::RENDERBUFFER, WebGl2RenderingContext::DEPTH_COMPONENT16, width as i32, height as i32);
+
+        let framebuffer = gl.create_framebuffer();
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(framebuffer));
+        gl.framebuffer_texture_2d(WebGl2RenderingContext::FRAMEBUFFER, WebGl2RenderingContext::COLOR_ATTACHMENT0, WebGl2RenderingContext::TEXTURE_2D, Some(texture), 0);
+        gl.framebuffer_renderbuffer(WebGl2RenderingContext::FRAMEBUFFER, WebGl2RenderingContext::DEPTH_ATTACHMENT, WebGl2RenderingContext::RENDERBUFFER, Some(depth));
+        let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+
+        //dropping the target deletes the attachments again
+        let target = Self { gl:gl.clone(), framebuffer:framebuffer, texture:texture, depth:depth, width:width, height:height };
+        if status != WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+            return Err(RenderError::IncompleteFramebuffer(status));
+        }
+        Ok(target)
+    }
+
+    pub fn size(&self) -> (u32,u32) {
+        (self.width, self.height)
+    }
+
+    //binds and clears to 0
+    pub fn bind(&self) {
+        self.gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(self.framebuffer));
+        self.gl.viewport(0, 0, self.width as i32, self.height as i32);
+        self.gl.clear_bufferuiv(WebGl2RenderingContext::COLOR, 0, &[0, 0, 0, 0]);
+        self.gl.clear(WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+    }
+
+    //value drawn at x, y from the bottom left, the target has to be bound
+    pub fn read(&self, x:u32, y:u32) -> u32 {
+        let mut dst = [0u8; 16];
+        self.gl.read_pixels(x as i32, y as i32, 1, 1, WebGl2RenderingContext::RGBA_INTEGER, WebGl2RenderingContext::UNSIGNED_INT, &mut dst);
+        u32::from_ne_bytes([dst[0], dst[1], dst[2], dst[3]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use cgmath::{Vector2, Vector4};
+    use web_sys::WebGl2RenderingContext;
+
+    use super::{PickTarget, PICK_FRAGMENT};
+    use crate::engine::render::{renderer::Renderer, error::RenderError, types::{triangle::{self, Triangle}, line::{self, Line, EndBehavior}}, backend::{Backend, recording::{RecordingBackend, GlCall}}};
+
+    #[test]
+    fn links_the_vertex_shader_with_the_pick_shader() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut renderer = Renderer::with_backend(gl.clone());
+        let _triangle = Triangle::new(&mut renderer, [Vector2::new(-0.5, -0.5), Vector2::new(0.5, -0.5), Vector2::new(0.0, 0.5)], Vector4::new(0.0, 0.0, 1.0, 1.0)).unwrap();
+        let _line = Line::new(&mut renderer, [Vector2::new(-0.5, 0.5), Vector2::new(0.5, 0.5)], Vector4::new(1.0, 0.0, 0.0, 1.0), 0.05, 0.02, EndBehavior::Rounded).unwrap();
+        renderer.pick(32, 32).unwrap();
+
+        let triangle = triangle::render_type();
+        let line = line::render_type();
+        let linked = |vertex:&str, fragment:&str| gl.state().programs.values().any(|x| x.vertex_source == vertex && x.fragment_source == fragment);
+        assert!(linked(&triangle.vertex_shader.borrow(), PICK_FRAGMENT));
+        assert!(linked(&line.vertex_shader.borrow(), line.pick_shader.as_ref().unwrap()));
+    }
+
+    #[test]
+    fn caches_pick_vertex_arrays() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut renderer = Renderer::with_backend(gl.clone());
+        let _triangle = Triangle::new(&mut renderer, [Vector2::new(-0.5, -0.5), Vector2::new(0.5, -0.5), Vector2::new(0.0, 0.5)], Vector4::new(0.0, 0.0, 1.0, 1.0)).unwrap();
+        gl.take_calls();
+        renderer.pick(32, 32).unwrap();
+        assert_eq!(gl.take_calls().iter().filter(|x| matches!(x, GlCall::CreateVertexArray(_))).count(), 1);
+
+        //neither pick nor render point vertex arrays at another program again
+        renderer.pick(32, 32).unwrap();
+        renderer.render();
+        assert!(!gl.take_calls().iter().any(|x| matches!(x, GlCall::CreateVertexArray(_) | GlCall::VertexAttribPointer { .. })));
+    }
+
+    #[test]
+    fn incomplete_targets_are_errors() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let backend:Backend = gl.clone();
+        let result = PickTarget::new(&backend, 0, 0);
+        assert!(matches!(result, Err(RenderError::IncompleteFramebuffer(WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT))));
+        //nothing is left allocated
+        assert!(gl.state().framebuffers.is_empty());
+        assert!(gl.state().renderbuffers.is_empty());
+    }
+}
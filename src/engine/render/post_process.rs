@@ -0,0 +1,176 @@
+use std::{rc::Rc, fmt::Debug};
+
+use cgmath::Vector2;
+use web_sys::WebGl2RenderingContext;
+
+use super::{render_object::{RenderType, RenderTypeId, GlBuffers, VertexAttrib, UniformAttrib, UniformRole, ShaderDataTypes, AttributeRole}, renderer::{UniformBlock, UniformData, UniformRoleMap}, render_target::RenderTarget, program::Program, texture::TextureBatcher, backend::{Backend, GraphicsBackend}};
+
+//vertex shader every effect is drawn with, a quad covering the screen. Fragment shaders get the position of the
+//pixel in the previous stage's output as vUv
+pub const EFFECT_VERTEX_SHADER:&str = "#version 300 es
+
+in vec2 position;
+
+out vec2 vUv;
+
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0);
+    vUv = position * 0.5 + 0.5;
+}";
+
+//sampler2D holding the previous stage's output, the scene for the first effect
+pub const EFFECT_SOURCE:&str = "source";
+//vec2 size of the source in pixels
+pub const EFFECT_RESOLUTION:&str = "resolution";
+
+const QUAD_VERTICIES:[f32; 8] = [-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+const QUAD_INDICIES:[u32; 6] = [0, 1, 2, 0, 2, 3];
+
+//render type of a full screen effect, registered by name in the ShaderRegistry like any other render type so it can
+//be reloaded. uniform_attribs are the effect's own uniforms, source and resolution are added to them
+pub fn effect_type(fragment_shader:String, uniform_attribs:Vec<UniformAttrib>) -> RenderType {
+    let mut uniforms = vec![
+        UniformAttrib { name:String::from(EFFECT_SOURCE), role:UniformRole::Custom },
+        UniformAttrib { name:String::from(EFFECT_RESOLUTION), role:UniformRole::Custom }
+    ];
+    uniforms.extend(uniform_attribs);
+    RenderType::new_unique(
+        String::from(EFFECT_VERTEX_SHADER),
+        fragment_shader,
+        vec![
+            VertexAttrib {
+                name:String::from("position"),
+                role:AttributeRole::Custom,
+                data_type:ShaderDataTypes::FloatVec2
+            }
+        ],
+        uniforms
+    )
+}
+
+//one stage of the chain, drawing the output of the stage before it through the effect's fragment shader
+pub struct PostEffect {
+    render_type:Rc<RenderType>,
+    program:Rc<Program>,
+    quad:GlBuffers,
+    uniforms:UniformBlock,
+    pub enabled:bool
+}
+
+impl Debug for PostEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PostEffect")
+            .field("render_type", &self.render_type)
+            .field("quad", &"..")
+            .field("uniforms", &self.uniforms)
+            .field("enabled", &self.enabled)
+            .finish()
+    }
+}
+
+impl PostEffect {
+    pub(super) fn new(gl:&Backend, render_type:Rc<RenderType>, program:Rc<Program>) -> Self {
+        let verticies:Vec<u8> = QUAD_VERTICIES.iter().flat_map(|x| x.to_ne_bytes()).collect();
+        let quad = render_type.setup_arrs(gl, &verticies, &QUAD_INDICIES, program.handle(), verticies.len(), QUAD_INDICIES.len());
+        Self { render_type:render_type, program:program, quad:quad, uniforms:UniformBlock::default(), enabled:true }
+    }
+
+    pub fn render_type(&self) -> &Rc<RenderType> {
+        &self.render_type
+    }
+
+    pub fn set_uniform(&mut self, name:&str, value:UniformData) {
+        self.uniforms.set(&self.render_type, name, value);
+    }
+
+    pub(super) fn set_program(&mut self, gl:&dyn GraphicsBackend, program:Rc<Program>) {
+        self.quad.rebind(gl, &self.render_type, program.handle());
+        self.uniforms.clear_cached_locations();
+        self.program = program;
+    }
+
+    fn draw(&mut self, gl:&dyn GraphicsBackend, texture_batcher:&TextureBatcher, global_uniforms:&UniformRoleMap, source:&RenderTarget) {
+        self.set_uniform(EFFECT_SOURCE, UniformData::Texture(Some(source.texture())));
+        self.set_uniform(EFFECT_RESOLUTION, UniformData::Vec2(Vector2::new(source.width() as f32, source.height() as f32)));
+        gl.use_program(Some(self.program.handle()));
+        self.uniforms.setup_uniforms_and_textures(gl, texture_batcher, self.program.handle(), global_uniforms);
+        self.quad.draw(gl, self.render_type.clone(), QUAD_INDICIES.len() as i32);
+    }
+}
+
+//effects run in order after the scene is drawn. While any is enabled the scene goes into an offscreen target instead
+//of the canvas, and the last enabled effect draws to the canvas
+#[derive(Debug, Default)]
+pub struct PostProcess {
+    effects:Vec<PostEffect>,
+    //the scene is drawn into the first, effects then alternate between them
+    targets:Option<[RenderTarget; 2]>
+}
+
+impl PostProcess {
+    pub fn effects(&self) -> &[PostEffect] {
+        &self.effects
+    }
+
+    //effects can be reordered, toggled or removed through this
+    pub fn effects_mut(&mut self) -> &mut Vec<PostEffect> {
+        &mut self.effects
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.effects.iter().any(|x| x.enabled)
+    }
+
+    //targets have to match the canvas, they are dropped once no effect is enabled
+    pub(super) fn needs_targets(&mut self, width:u32, height:u32) -> bool {
+        if !self.is_active() {
+            self.targets = None;
+            return false;
+        }
+        match &self.targets {
+            Some(targets) => targets[0].width() != width || targets[0].height() != height,
+            None => true
+        }
+    }
+
+    pub(super) fn set_targets(&mut self, targets:[RenderTarget; 2]) {
+        self.targets = Some(targets);
+    }
+
+    pub(super) fn scene_target(&self) -> Option<&RenderTarget> {
+        match self.is_active() {
+            true => self.targets.as_ref().map(|x| &x[0]),
+            false => None
+        }
+    }
+
+    pub(super) fn set_program(&mut self, gl:&dyn GraphicsBackend, render_type:RenderTypeId, program:&Rc<Program>) {
+        for effect in self.effects.iter_mut().filter(|x| x.render_type.id() == render_type) {
+            effect.set_program(gl, program.clone());
+        }
+    }
+
+    //runs the enabled effects over the scene target, leaving the default framebuffer bound
+    pub(super) fn apply(&mut self, gl:&dyn GraphicsBackend, texture_batcher:&TextureBatcher, global_uniforms:&UniformRoleMap, width:u32, height:u32) {
+        let targets = match &self.targets {
+            Some(targets) => targets,
+            None => return
+        };
+        let enabled:Vec<usize> = (0..self.effects.len()).filter(|i| self.effects[*i].enabled).collect();
+
+        //effects replace what is under them. Depth testing is enabled again for the renderer's own state afterwards,
+        //blending is set per render type when drawing
+        gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.disable(WebGl2RenderingContext::BLEND);
+        for (n, i) in enabled.iter().enumerate() {
+            if n + 1 == enabled.len() {
+                gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+                gl.viewport(0, 0, width as i32, height as i32);
+            } else {
+                targets[(n + 1) % 2].bind();
+            }
+            self.effects[*i].draw(gl, texture_batcher, global_uniforms, &targets[n % 2]);
+        }
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+    }
+}
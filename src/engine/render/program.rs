@@ -1,91 +1,216 @@
-use web_sys::{WebGlProgram, WebGl2RenderingContext, WebGlShader};
+use std::fmt;
 
-use crate::log_str;
+use web_sys::WebGl2RenderingContext;
 
-pub fn create_program_from_src(gl:&WebGl2RenderingContext, vertex_src:&str, frag_src:&str) -> WebGlProgram {
-    let vertex_shader = match compile_shader(
-        gl, 
+use super::{backend::{Backend, GraphicsBackend, ProgramHandle}, render_object::{VertexAttrib, UniformAttrib, ShaderDataTypes, AttributeRole, UniformRole}};
+
+//linked program that is deleted once nothing draws with it anymore
+#[derive(Debug)]
+pub struct Program {
+    gl:Backend,
+    handle:ProgramHandle
+}
+
+impl Program {
+    pub fn new(gl:&Backend, handle:ProgramHandle) -> Self {
+        Self { gl:gl.clone(), handle:handle }
+    }
+
+    pub fn handle(&self) -> ProgramHandle {
+        self.handle
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        self.gl.delete_program(self.handle);
+    }
+}
+
+//attributes and uniforms a linked program uses. Attributes are in location order, which sets their offsets
+//in a vertex, use layout(location = n) in the shader to pin it. Roles come from the names, see AttributeRole::from_name
+#[derive(Debug)]
+pub struct ShaderReflection {
+    pub vertex_attribs:Vec<VertexAttrib>,
+    pub uniform_attribs:Vec<UniformAttrib>
+}
+
+impl ShaderReflection {
+    //attributes of types ShaderDataTypes has no buffer layout for are link errors
+    pub fn from_program(gl:&dyn GraphicsBackend, program:ProgramHandle) -> Result<Self, ShaderError> {
+        let mut attribs:Vec<(i32,VertexAttrib)> = gl.get_active_attribs(program).into_iter()
+            .filter(|x| !x.name.starts_with("gl_"))
+            .map(|x| {
+                let data_type = ShaderDataTypes::from_webgl_representation(x.data_type)
+                    .ok_or_else(|| ShaderError::new(ShaderStage::Link, format!("Attribute {} has unsupported type {:#x}", x.name, x.data_type), None))?;
+                Ok((gl.get_attrib_location(program, &x.name), VertexAttrib { role:AttributeRole::from_name(&x.name), name:x.name, data_type:data_type }))
+            }).collect::<Result<_,ShaderError>>()?;
+        attribs.sort_by_key(|(location,_)| *location);
+
+        let uniform_attribs = gl.get_active_uniforms(program).into_iter().map(|x| {
+            //arrays are reported as their first element
+            let name = x.name.trim_end_matches("[0]").to_owned();
+            UniformAttrib { role:UniformRole::from_name(&name), name:name }
+        }).collect();
+
+        Ok(Self {
+            vertex_attribs:attribs.into_iter().map(|(_,x)| x).collect(),
+            uniform_attribs:uniform_attribs
+        })
+    }
+
+    //links the sources once just to query them
+    pub fn from_src(gl:&dyn GraphicsBackend, vertex_src:&str, frag_src:&str) -> Result<Self, ShaderError> {
+        let program = create_program_from_src(gl, vertex_src, frag_src)?;
+        let reflection = Self::from_program(gl, program);
+        gl.delete_program(program);
+        reflection
+    }
+
+    //moves the named attributes out, for instanced render types
+    pub fn take_attribs(&mut self, names:&[&str]) -> Result<Vec<VertexAttrib>, ShaderError> {
+        if let Some(name) = names.iter().find(|name| !self.vertex_attribs.iter().any(|x| x.name == **name)) {
+            return Err(ShaderError::new(ShaderStage::Link, format!("Shader has no active attribute {}", name), None));
+        }
+        let (taken,rest) = self.vertex_attribs.drain(..).partition(|x| names.contains(&x.name.as_str()));
+        self.vertex_attribs = rest;
+        Ok(taken)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    //linking and checking the linked program against its render type
+    Link
+}
+
+//lines are the 1 based source lines the info log points at, excerpt is the source around the first of them
+#[derive(Clone, Debug)]
+pub struct ShaderError {
+    pub stage:ShaderStage,
+    pub log:String,
+    pub lines:Vec<usize>,
+    pub excerpt:String
+}
+
+impl ShaderError {
+    pub fn new(stage:ShaderStage, log:String, source:Option<&str>) -> Self {
+        let lines = parse_error_lines(&log);
+        let excerpt = match (source, lines.first()) {
+            (Some(source), Some(line)) => source_excerpt(source, *line, EXCERPT_CONTEXT),
+            _ => String::new()
+        };
+        Self { stage:stage, log:log, lines:lines, excerpt:excerpt }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.stage {
+            ShaderStage::Vertex => write!(f, "Error when compiling vertex shader\n{}", self.log)?,
+            ShaderStage::Fragment => write!(f, "Error when compiling fragment shader\n{}", self.log)?,
+            ShaderStage::Link => write!(f, "Error linking program\n{}", self.log)?
+        };
+        if !self.excerpt.is_empty() { write!(f, "\n{}", self.excerpt)?; }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+const EXCERPT_CONTEXT:usize = 2;
+
+//gl info logs point at lines as `ERROR: <source string>:<line>: message`
+fn parse_error_lines(log:&str) -> Vec<usize> {
+    let mut lines = Vec::new();
+    for l in log.lines() {
+        let parts:Vec<&str> = l.splitn(4, ':').map(|x| x.trim()).collect();
+        if parts.len() < 4 || parts[1].parse::<u32>().is_err() { continue; }
+        if let Ok(line) = parts[2].parse::<usize>() {
+            if !lines.contains(&line) { lines.push(line); }
+        }
+    }
+    lines
+}
+
+//numbered source lines around line, which is marked with >
+fn source_excerpt(source:&str, line:usize, context:usize) -> String {
+    source.lines().enumerate()
+        .map(|(i,x)| (i + 1, x))
+        .filter(|(i,_)| *i + context >= line && *i <= line + context)
+        .map(|(i,x)| format!("{}{:>4} | {}", if i == line { ">" } else { " " }, i, x.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+//compile and link errors are returned with the info log, nothing is left allocated on failure
+pub fn create_program_from_src(gl:&dyn GraphicsBackend, vertex_src:&str, frag_src:&str) -> Result<ProgramHandle, ShaderError> {
+    let vertex_shader = gl.compile_shader(
         WebGl2RenderingContext::VERTEX_SHADER,
         &vertex_src
-    ) {
-        Ok(v) => v,
-        Err(s) => {
-            log_str("Error when compiling vertex shader");
-            log_str(&s);
-            panic!();
-        }
-    };
+    ).map_err(|s| ShaderError::new(ShaderStage::Vertex, s, Some(vertex_src)))?;
 
-    let frag_shader = match compile_shader(
-        gl, 
+    let frag_shader = match gl.compile_shader(
         WebGl2RenderingContext::FRAGMENT_SHADER,
         &frag_src
     ) {
         Ok(v) => v,
         Err(s) => {
-            log_str("Error when compiling fragment shader");
-            log_str(&s);
-            panic!();
+            gl.delete_shader(vertex_shader);
+            return Err(ShaderError::new(ShaderStage::Fragment, s, Some(frag_src)));
         }
     };
 
-    let program = match link_program(gl, &vertex_shader, &frag_shader) {
-        Ok(v) => v,
-        Err(s) => {
-            log_str("Error linking program");
-            log_str(&s);
-            panic!();
-        }
-    };
-    program
+    let program = gl.link_program(vertex_shader, frag_shader);
+
+    //the program keeps its own copy of the compiled code once linked
+    gl.delete_shader(vertex_shader);
+    gl.delete_shader(frag_shader);
+    program.map_err(|s| ShaderError::new(ShaderStage::Link, s, None))
 }
 
-fn compile_shader(
-    context: &WebGl2RenderingContext,
-    shader_type: u32,
-    source: &str,
-) -> Result<WebGlShader, String> {
-    let shader = context
-        .create_shader(shader_type)
-        .ok_or_else(|| String::from("Unable to create shader object"))?;
-    context.shader_source(&shader, source);
-    context.compile_shader(&shader);
-
-    if context
-        .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
-        .as_bool()
-        .unwrap_or(false)
-    {
-        Ok(shader)
-    } else {
-        Err(context
-            .get_shader_info_log(&shader)
-            .unwrap_or_else(|| String::from("Unknown error creating shader")))
+#[cfg(test)]
+mod tests {
+    use super::{ShaderReflection, ShaderStage};
+    use crate::engine::render::{render_object::{ShaderDataTypes, AttributeRole}, backend::recording::RecordingBackend};
+
+    const FRAGMENT:&str = "#version 300 es
+        precision highp float;
+        out vec4 FragColor;
+        void main() { FragColor = vec4(1.0); }";
+
+    #[test]
+    fn reflects_attributes_in_location_order() {
+        let gl = RecordingBackend::new(1, 1);
+        let reflection = ShaderReflection::from_src(&gl, "#version 300 es
+            in vec2 position;
+            in uvec4 flags;
+            void main() { gl_Position = vec4(position, 0.0, 1.0); }", FRAGMENT).unwrap();
+        let attribs:Vec<_> = reflection.vertex_attribs.iter().map(|x| (x.name.as_str(), x.data_type.clone(), x.role)).collect();
+        assert_eq!(attribs, vec![("position", ShaderDataTypes::FloatVec2, AttributeRole::Position), ("flags", ShaderDataTypes::UintVec4, AttributeRole::Custom)]);
     }
-}
 
-fn link_program(
-    context: &WebGl2RenderingContext,
-    vert_shader: &WebGlShader,
-    frag_shader: &WebGlShader,
-) -> Result<WebGlProgram, String> {
-    let program = context
-        .create_program()
-        .ok_or_else(|| String::from("Unable to create shader object"))?;
-
-    context.attach_shader(&program, vert_shader);
-    context.attach_shader(&program, frag_shader);
-    context.link_program(&program);
-
-    if context
-        .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
-        .as_bool()
-        .unwrap_or(false)
-    {
-        Ok(program)
-    } else {
-        Err(context
-            .get_program_info_log(&program)
-            .unwrap_or_else(|| String::from("Unknown error creating program object")))
+    #[test]
+    fn unsupported_attribute_types_are_link_errors() {
+        let gl = RecordingBackend::new(1, 1);
+        let error = ShaderReflection::from_src(&gl, "#version 300 es
+            in mat2x3 skew;
+            void main() { gl_Position = vec4(skew[0], 1.0); }", FRAGMENT).unwrap_err();
+        assert_eq!(error.stage, ShaderStage::Link);
+        assert!(error.log.contains("skew"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn taking_missing_attributes_is_a_link_error() {
+        let gl = RecordingBackend::new(1, 1);
+        let mut reflection = ShaderReflection::from_src(&gl, "#version 300 es
+            in vec2 position;
+            void main() { gl_Position = vec4(position, 0.0, 1.0); }", FRAGMENT).unwrap();
+        let error = reflection.take_attribs(&["offset"]).unwrap_err();
+        assert_eq!(error.stage, ShaderStage::Link);
+        //nothing is taken when one of the names is missing
+        assert_eq!(reflection.vertex_attribs.len(), 1);
+    }
+}
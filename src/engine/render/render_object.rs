@@ -1,18 +1,21 @@
-use std::{rc::Rc, mem, collections::HashMap, fmt::Debug};
+use std::{rc::Rc, mem, cell::{Cell, RefCell}, collections::HashMap, fmt::Debug, convert::TryFrom, sync::atomic::{AtomicUsize, Ordering}};
 
-use js_sys::{Uint16Array, ArrayBuffer, Float32Array, Uint8Array};
+use cgmath::{Point3, Vector3, EuclideanSpace};
+use js_sys::{Uint16Array, Uint32Array, Uint8Array};
 use wasm_bindgen::UnwrapThrowExt;
-use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlVertexArrayObject, WebGlProgram};
+use web_sys::WebGl2RenderingContext;
 
-use crate::{log_str, log_f32_arr, log_u16_arr, log_u8_arr, log_u8_as_f32_arr};
+use crate::{log_str, log_u16_arr, log_u32_arr, log_u8_as_f32_arr};
 
-use super::{program::create_program_from_src, renderer::{UniformBlock, UniformData, VertexData, RenderObjectAllocation, Renderer}};
+use super::{uniform_buffer::CAMERA_BLOCK, program::{create_program_from_src, Program, ShaderReflection, ShaderError, ShaderStage}, renderer::{UniformBlock, UniformData, VertexData, RenderObjectAllocation, Renderer, PickId}, layer::LayerId, bounds::Aabb, error::RenderError, backend::{Backend, GraphicsBackend, BufferHandle, VertexArrayHandle, ProgramHandle}};
 
 pub struct RenderObject {
     pub(super) type_id:Rc<RenderType>,
     pub(super) uniforms:UniformBlock,
     pub(super) verticies:Vec<u8>,
-    pub(super) indicies:Vec<u16>,
+    pub(super) indicies:Vec<u32>,
+    pub(super) layer:LayerId,
+    pub(super) z_index:i32,
     pub(super) allocation:Option<RenderObjectAllocation>
 }
 
@@ -22,6 +25,8 @@ impl Debug for RenderObject {
         .field("uniforms", &self.uniforms)
         .field("verticies", &self.verticies)
         .field("indicies", &self.indicies)
+        .field("layer", &self.layer)
+        .field("z_index", &self.z_index)
         .field("allocation", &self.allocation)
         .finish()
     }
@@ -29,7 +34,43 @@ impl Debug for RenderObject {
 
 impl RenderObject {
     pub fn new(render_type:Rc<RenderType>) -> Self {
-        Self { type_id: render_type, uniforms: UniformBlock::default(), verticies: Vec::new(), indicies: Vec::new(), allocation:None }
+        let mut uniforms = UniformBlock::default();
+        //uniforms with a role are filled in by the renderer, they only have to be in the block
+        for attrib in render_type.uniform_attribs.iter().filter(|x| x.role != UniformRole::Custom) {
+            uniforms.set(&render_type, &attrib.name, UniformData::Float(0.0));
+        }
+        Self { type_id: render_type, uniforms: uniforms, verticies: Vec::new(), indicies: Vec::new(), layer:LayerId::CONTENT, z_index:0, allocation:None }
+    }
+
+    //objects in a layer are drawn from the lowest z index up, equal ones in the order their types were first added.
+    //Like uniforms, takes effect on the next update
+    pub fn set_layer(&mut self, layer:LayerId, z_index:i32) {
+        self.layer = layer;
+        self.z_index = z_index;
+    }
+
+    //set_layer and update, keeping the old layer and z index if the renderer doesn't take the object there
+    pub fn move_to_layer(&mut self, renderer:&mut Renderer, layer:LayerId, z_index:i32) -> Result<(),RenderError> {
+        let (previous_layer, previous_z_index) = (self.layer, self.z_index);
+        self.set_layer(layer, z_index);
+        let result = self.update(renderer);
+        if result.is_err() {
+            self.set_layer(previous_layer, previous_z_index);
+        }
+        result
+    }
+
+    pub fn layer(&self) -> LayerId {
+        self.layer
+    }
+
+    pub fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    //what Renderer::pick returns for this object, None until it has been added
+    pub fn pick_id(&self) -> Option<PickId> {
+        self.allocation.as_ref().map(|x| x.pick_id())
     }
 
     pub fn set_uniform(&mut self, name:&str, value:UniformData) {
@@ -37,48 +78,212 @@ impl RenderObject {
     }
 
     #[inline]
-    pub fn add_triangle(&mut self, indicies:[u16; 3]) {
+    pub fn add_triangle(&mut self, indicies:[u32; 3]) {
         self.indicies.extend_from_slice(&indicies);
     }
     
     #[inline]
-    pub fn set_v_data(&mut self, idx:u16, name:&str, value:VertexData) {
+    pub fn set_v_data(&mut self, idx:u32, name:&str, value:VertexData) {
         let offset = self.type_id.calc_v_attrib_offset(name, idx).expect_throw(&format!("Could not find vertex attribute with the name {}",name));
-        let bytes = value.into_bytes();
-        let end = offset + bytes.len();
-        if end >= self.verticies.len() { self.verticies.resize(end, 0); }
-        self.verticies.splice(offset..end, bytes).for_each(|_| {});
+        self.write_bytes(offset, value.into_bytes());
     }
 
-    pub fn set_v_datas(&mut self, idx:u16, name:&str, values:Vec<VertexData>) {
+    pub fn set_v_datas(&mut self, idx:u32, name:&str, values:Vec<VertexData>) {
         for (x,data) in values.into_iter().enumerate() {
-            self.set_v_data(idx + x as u16, name, data)
+            self.set_v_data(idx + x as u32, name, data)
         }
     }
 
-    pub fn sub_data(&mut self, idx:u16, data:Vec<u8>) {
+    //instanced render types share the mesh from their InstancedData, objects only hold per instance data
+    #[inline]
+    pub fn set_i_data(&mut self, instance:u32, name:&str, value:VertexData) {
+        let offset = self.type_id.calc_i_attrib_offset(name, instance).expect_throw(&format!("Could not find instance attribute with the name {}",name));
+        self.write_bytes(offset, value.into_bytes());
+    }
+
+    pub fn set_i_datas(&mut self, instance:u32, name:&str, values:Vec<VertexData>) {
+        for (x,data) in values.into_iter().enumerate() {
+            self.set_i_data(instance + x as u32, name, data)
+        }
+    }
+
+    pub fn instance_count(&self) -> usize {
+        match self.type_id.instance_size {
+            0 => 0,
+            size => self.verticies.len() / size
+        }
+    }
+
+    //drops instances from count onwards
+    pub fn truncate_instances(&mut self, count:usize) {
+        self.verticies.truncate(count * self.type_id.instance_size);
+    }
+
+    fn write_bytes(&mut self, offset:usize, bytes:Vec<u8>) {
+        let end = offset + bytes.len();
+        if end >= self.verticies.len() { self.verticies.resize(end, 0); }
+        self.verticies.splice(offset..end, bytes).for_each(|_| {});
+    }
+
+    pub fn sub_data(&mut self, idx:u32, data:Vec<u8>) {
         let offset = self.type_id.vertex_size * idx as usize;
         let end = offset + data.len();
         if end >= self.verticies.len() { self.verticies.resize(end+1, 0); }
         self.verticies.splice(offset..end, data).for_each(|_| {});
     }
 
-    pub fn update(&mut self, renderer:&mut Renderer) {
-        renderer.update(self);
+    pub fn update(&mut self, renderer:&mut Renderer) -> Result<(),RenderError> {
+        renderer.update(self)
+    }
+
+    //average of the verticies' positions, or the instances' for instanced types.
+    //None if the type has no float position attribute
+    pub fn center(&self) -> Option<Point3<f32>> {
+        let (attrib, offset, stride) = self.type_id.object_position_attrib()?;
+        let positions = read_positions(attrib, offset, stride, &self.verticies)?;
+        if positions.is_empty() { return None; }
+        let count = positions.len() as f32;
+        Some(Point3::from_vec(positions.into_iter().map(|x| x.to_vec()).sum::<Vector3<f32>>() / count))
+    }
+
+    //box around the verticies' positions. Instances are boxed by their positions grown by the box around the mesh's,
+    //so instanced types need a position attribute in both. None for types without float positions
+    pub fn bounds(&self) -> Option<Aabb> {
+        let (attrib, offset, stride) = self.type_id.object_position_attrib()?;
+        let bounds = Aabb::from_points(read_positions(attrib, offset, stride, &self.verticies)?)?;
+        match &self.type_id.instanced {
+            None => Some(bounds),
+            Some(mesh) => {
+                let attrib = position_attrib(&self.type_id.vertex_attribs)?;
+                let offset = self.type_id.vertex_attrib_offsets[&attrib.name];
+                let mesh = Aabb::from_points(read_positions(attrib, offset, self.type_id.vertex_size, &mesh.verticies)?)?;
+                Some(Aabb::new(bounds.min + mesh.min.to_vec(), bounds.max + mesh.max.to_vec()))
+            }
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct InstancedData {
     pub verticies:Vec<u8>,
-    pub indicies:Vec<u16>
+    pub indicies:Vec<u32>
 }
 
-//TODO: make shader registry
+//width of the indicies in a render type's index buffers, U16 uses half the memory but a chunk can only address 65536 verticies
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IndexFormat {
+    U16,
+    U32
+}
+
+impl IndexFormat {
+    pub fn get_webgl_representation(&self) -> u32 {
+        match self {
+            Self::U16 => WebGl2RenderingContext::UNSIGNED_SHORT,
+            Self::U32 => WebGl2RenderingContext::UNSIGNED_INT
+        }
+    }
+
+    pub fn get_size(&self) -> usize {
+        match self {
+            Self::U16 => mem::size_of::<u16>(),
+            Self::U32 => mem::size_of::<u32>()
+        }
+    }
+
+    //saturates where usize is 32 bits, like on wasm32
+    pub fn max_verticies(&self) -> usize {
+        usize::try_from(self.addressable()).unwrap_or(usize::MAX)
+    }
+
+    //u32 indicies address one vertex more than a 32 bit usize holds
+    fn addressable(&self) -> u64 {
+        match self {
+            Self::U16 => u16::MAX as u64 + 1,
+            Self::U32 => u32::MAX as u64 + 1
+        }
+    }
+
+    //Err unless every index fits once offset is added to it
+    pub(super) fn check(&self, indicies:&[u32], offset:usize) -> Result<(),RenderError> {
+        let verticies = indicies.iter().max().map_or(0, |x| *x as u64 + 1) + offset as u64;
+        match verticies > self.addressable() {
+            true => Err(RenderError::IndexOverflow { verticies:usize::try_from(verticies).unwrap_or(usize::MAX), index_format:*self }),
+            false => Ok(())
+        }
+    }
+
+    //indicies are checked before they get into a chunk, so they always fit
+    fn to_bytes(&self, indicies:&[u32]) -> Vec<u8> {
+        match self {
+            Self::U16 => indicies.iter().flat_map(|x| (*x as u16).to_ne_bytes()).collect(),
+            Self::U32 => indicies.iter().flat_map(|x| x.to_ne_bytes()).collect()
+        }
+    }
+}
+
+//how a render type's fragments combine with what is already drawn. Opaque types are drawn first with depth writes,
+//the rest are drawn after them from back to front without writing depth
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlendMode {
+    //the default, types with translucent fragments have to opt into one of the others
+    Opaque,
+    //straight alpha
+    Alpha,
+    Additive,
+    Multiply,
+    //colors already multiplied by their alpha
+    Premultiplied
+}
+
+impl BlendMode {
+    pub(super) fn is_opaque(&self) -> bool {
+        *self == Self::Opaque
+    }
+
+    //opaque types have no alpha for a layer's opacity to fade, so below 1 they are blended by a constant instead.
+    //Translucent types fade through their opacity uniform
+    pub(super) fn apply_with_opacity(&self, gl:&dyn GraphicsBackend, opacity:f32) {
+        if !self.is_opaque() || opacity >= 1.0 {
+            return self.apply(gl);
+        }
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_color(0.0, 0.0, 0.0, opacity);
+        gl.blend_func(WebGl2RenderingContext::CONSTANT_ALPHA, WebGl2RenderingContext::ONE_MINUS_CONSTANT_ALPHA);
+    }
+
+    pub(super) fn apply(&self, gl:&dyn GraphicsBackend) {
+        let (src, dst) = match self {
+            Self::Opaque => {
+                gl.disable(WebGl2RenderingContext::BLEND);
+                return;
+            },
+            Self::Alpha => (WebGl2RenderingContext::SRC_ALPHA, WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA),
+            Self::Additive => (WebGl2RenderingContext::SRC_ALPHA, WebGl2RenderingContext::ONE),
+            Self::Multiply => (WebGl2RenderingContext::DST_COLOR, WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA),
+            Self::Premultiplied => (WebGl2RenderingContext::ONE, WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA)
+        };
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(src, dst);
+    }
+}
+
+//unique per constructed render type, unlike its address it is never reused after the type is dropped
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RenderTypeId(usize);
+
+static NEXT_RENDER_TYPE_ID:AtomicUsize = AtomicUsize::new(0);
+
+//usually registered by name in the renderer's ShaderRegistry.
+//hand written attribute lists are checked against the linked program, the reflected constructors derive them from it
 #[derive(Debug)]
 pub struct RenderType {
-    pub vertex_shader:String,
-    pub fragment_shader:String,
+    id:RenderTypeId,
+    //replaced by ShaderRegistry::reload
+    pub vertex_shader:RefCell<String>,
+    pub fragment_shader:RefCell<String>,
+    //fragment shader linked with vertex_shader for picking, see pick::fragment_shader
+    pub pick_shader:Option<String>,
     pub instanced:Option<InstancedData>,
     pub blank_vertex:Option<Vec<u8>>,
     pub vertex_attribs:Vec<VertexAttrib>,
@@ -86,6 +291,12 @@ pub struct RenderType {
     pub uniform_attribs:Vec<UniformAttrib>,
     pub vertex_size:usize,
     pub vertex_attrib_offsets:HashMap<String, usize>,
+    pub instance_size:usize,
+    pub instance_attrib_offsets:HashMap<String, usize>,
+    pub index_format:IndexFormat,
+    pub blend_mode:BlendMode,
+    //whether objects are skipped while their bounds are out of view
+    pub culled:bool,
     pub verticies_chunk_min_size:usize,
     pub verticies_chunk_grow_factor:f32,
     pub verticies_chunk_max_size:usize,
@@ -96,7 +307,6 @@ pub struct RenderType {
 
 //Functions used by renderer
 impl RenderType {
-    //TODO: update constructors to auto generate vertex attribs from shaders()
     pub fn new_unique(
         vertex_shader:String,
         fragment_shader:String,
@@ -151,14 +361,12 @@ impl RenderType {
         verticies_grow_factor:f32,
         indicies_grow_factor:f32
     ) -> Self {
-        let (offsets,vertex_size) = vertex_attribs.iter().fold((HashMap::new(),0), |(mut acc,last), x| {
-            acc.insert(x.name.clone(), last);
-            let new =x.data_type.get_size() as usize + last;
-            (acc,new)
-        });
+        let (offsets,vertex_size) = attrib_offsets(&vertex_attribs);
         Self {
-            vertex_shader:vertex_shader,
-            fragment_shader:fragment_shader,
+            id:RenderTypeId(NEXT_RENDER_TYPE_ID.fetch_add(1, Ordering::Relaxed)),
+            vertex_shader:RefCell::new(vertex_shader),
+            fragment_shader:RefCell::new(fragment_shader),
+            pick_shader:None,
             instanced:None,
             blank_vertex:None,
             vertex_attribs:vertex_attribs,
@@ -166,6 +374,11 @@ impl RenderType {
             uniform_attribs:uniform_attribs,
             vertex_size:vertex_size,
             vertex_attrib_offsets:offsets,
+            instance_size:0,
+            instance_attrib_offsets:HashMap::new(),
+            index_format:IndexFormat::U16,
+            blend_mode:BlendMode::Opaque,
+            culled:true,
             verticies_chunk_min_size:verticies_chunk_min_size,
             verticies_chunk_grow_factor:verticies_grow_factor,
             verticies_chunk_max_size:verticies_chunk_max_size,
@@ -175,197 +388,582 @@ impl RenderType {
         }
     }
 
-    pub(super) fn setup_program(&self, gl:&WebGl2RenderingContext) -> WebGlProgram {
-        create_program_from_src(gl, &self.vertex_shader, &self.fragment_shader)
+    //draws mesh once per instance, the verticies of objects of this type are their instance attributes and their
+    //indicies are unused. Chunk sizes are in instances.
+    pub fn new_instanced(
+        vertex_shader:String,
+        fragment_shader:String,
+        vertex_attribs:Vec<VertexAttrib>,
+        instance_attribs:Vec<VertexAttrib>,
+        uniform_attribs:Vec<UniformAttrib>,
+        mesh:InstancedData,
+        instances_chunk_min_size:usize,
+        instances_chunk_max_size:usize,
+        instances_grow_factor:f32
+    ) -> Self {
+        let (instance_offsets,instance_size) = attrib_offsets(&instance_attribs);
+        let mut render_type = Self::new_batched_growable(
+            vertex_shader,
+            fragment_shader,
+            vertex_attribs,
+            uniform_attribs,
+            Vec::new(),
+            instances_chunk_min_size,
+            instances_chunk_max_size,
+            0,
+            0,
+            instances_grow_factor,
+            1.0
+        );
+        assert!(mesh.verticies.len() % render_type.vertex_size == 0, "Instanced mesh data is not a whole number of verticies");
+        if let Some(max) = mesh.indicies.iter().max() {
+            assert!((*max as usize) < mesh.verticies.len() / render_type.vertex_size, "Instanced mesh index {} is out of range", max);
+        }
+        render_type.instanced = Some(mesh);
+        render_type.instance_attribs = instance_attribs;
+        render_type.instance_size = instance_size;
+        render_type.instance_attrib_offsets = instance_offsets;
+        render_type
+    }
+
+    //attribute and uniform lists come from linking the shaders with gl
+    pub fn new_reflected(
+        gl:&dyn GraphicsBackend,
+        vertex_shader:String,
+        fragment_shader:String,
+        blank_vertex:Vec<u8>,
+        verticies_chunk_min_size:usize,
+        verticies_chunk_max_size:usize,
+        indicies_chunk_min_size:usize,
+        indicies_chunk_max_size:usize,
+        verticies_grow_factor:f32,
+        indicies_grow_factor:f32
+    ) -> Result<Self, ShaderError> {
+        let reflection = ShaderReflection::from_src(gl, &vertex_shader, &fragment_shader)?;
+        Ok(Self::new_batched_growable(
+            vertex_shader,
+            fragment_shader,
+            reflection.vertex_attribs,
+            reflection.uniform_attribs,
+            blank_vertex,
+            verticies_chunk_min_size,
+            verticies_chunk_max_size,
+            indicies_chunk_min_size,
+            indicies_chunk_max_size,
+            verticies_grow_factor,
+            indicies_grow_factor
+        ))
+    }
+
+    //instance_attribs names the per instance attributes, the rest belong to the mesh
+    pub fn new_instanced_reflected(
+        gl:&dyn GraphicsBackend,
+        vertex_shader:String,
+        fragment_shader:String,
+        instance_attribs:&[&str],
+        mesh:InstancedData,
+        instances_chunk_min_size:usize,
+        instances_chunk_max_size:usize,
+        instances_grow_factor:f32
+    ) -> Result<Self, ShaderError> {
+        let mut reflection = ShaderReflection::from_src(gl, &vertex_shader, &fragment_shader)?;
+        let instance_attribs = reflection.take_attribs(instance_attribs)?;
+        Ok(Self::new_instanced(
+            vertex_shader,
+            fragment_shader,
+            reflection.vertex_attribs,
+            instance_attribs,
+            reflection.uniform_attribs,
+            mesh,
+            instances_chunk_min_size,
+            instances_chunk_max_size,
+            instances_grow_factor
+        ))
     }
 
-    pub(super) fn setup_arrs(&self, gl:&WebGl2RenderingContext, verticies:&Vec<u8>, indicies:&Vec<u16>, program:&WebGlProgram, verticies_size:usize, indicies_size:usize) -> GlBuffers {
-        let vao = gl.create_vertex_array().expect_throw("Error creating VAO");
-        gl.bind_vertex_array(Some(&vao));
+    pub fn with_index_format(mut self, index_format:IndexFormat) -> Self {
+        self.index_format = index_format;
+        self
+    }
 
-        let vbo = gl.create_buffer().expect_throw("Error Creating VBO");
-        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&vbo));
-        unsafe {
-            let buffer_view = match &self.instanced {
-                None => js_sys::Uint8Array::view(&verticies),
-                Some(instanced_data) => js_sys::Uint8Array::view(&instanced_data.verticies)
-            };
-            if verticies_size == verticies.len() {
-                gl.buffer_data_with_array_buffer_view(WebGl2RenderingContext::ARRAY_BUFFER, &buffer_view, WebGl2RenderingContext::DYNAMIC_DRAW);
-            } else {
-                gl.buffer_data_with_i32(WebGl2RenderingContext::ARRAY_BUFFER, verticies_size as i32, WebGl2RenderingContext::DYNAMIC_DRAW);
-                gl.buffer_sub_data_with_i32_and_array_buffer_view(WebGl2RenderingContext::ARRAY_BUFFER, 0, &buffer_view);
-            }
-        }
+    pub fn with_blend_mode(mut self, blend_mode:BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
 
-        let mut offset = 0;
-        let stride = self.vertex_attribs.iter().fold(0, |acc, a:&VertexAttrib| acc + a.data_type.get_size() );
-        for a in self.vertex_attribs.iter() {
-            let location = gl.get_attrib_location(program, &a.name);
-            if location == -1 { log_str(&format!("Could not find attribute location of {} attribute",a.name))}
-            let location = location as u32;
-            gl.enable_vertex_attrib_array(location);
-            gl.vertex_attrib_pointer_with_i32(location, a.data_type.get_count(), a.data_type.get_webgl_representation(), false, stride, offset);
-            offset += a.data_type.get_size();
-        }
+    //fragment shader picking objects of this type, it gets the vertex shader's outputs and writes the uint
+    //pick::PICK_ID uniform to its only output. Types that discard fragments or leave them fully transparent
+    //declare one discarding the same ones, so they aren't picked where nothing is drawn
+    pub fn with_pick_shader(mut self, fragment_shader:String) -> Self {
+        self.pick_shader = Some(fragment_shader);
+        self
+    }
+
+    //culling assumes verticies are drawn where their position attribute puts them, types whose vertex shaders move
+    //them elsewhere have to turn it off
+    pub fn with_culling(mut self, culled:bool) -> Self {
+        self.culled = culled;
+        self
+    }
+
+    pub fn id(&self) -> RenderTypeId {
+        self.id
+    }
+
+    pub(super) fn setup_program(&self, gl:&Backend) -> Result<Program,ShaderError> {
+        self.setup_program_from_src(gl, &self.vertex_shader.borrow(), &self.fragment_shader.borrow())
+    }
 
-        let ibo = gl.create_buffer().expect_throw("Error Creating IBO");
-        gl.bind_buffer(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, Some(&ibo));
-        unsafe {
-            let buffer_view = match &self.instanced {
-                None => js_sys::Uint16Array::view(&indicies),
-                Some(instanced_data) => js_sys::Uint16Array::view(&instanced_data.indicies)
+    //links the sources and checks them against this type's attributes without touching the type
+    pub(super) fn setup_program_from_src(&self, gl:&Backend, vertex_shader:&str, fragment_shader:&str) -> Result<Program,ShaderError> {
+        let program = Program::new(gl, create_program_from_src(gl.as_ref(), vertex_shader, fragment_shader)?);
+        self.check_program(gl.as_ref(), program.handle())?;
+        Ok(program)
+    }
+
+    //attributes the shader reads without a matching declaration would silently read garbage, so those are errors.
+    //Declared attributes and uniforms that aren't active may just have been optimized out
+    pub(super) fn check_program(&self, gl:&dyn GraphicsBackend, program:ProgramHandle) -> Result<(),ShaderError> {
+        let reflection = ShaderReflection::from_program(gl, program)?;
+        let declared:Vec<&VertexAttrib> = self.vertex_attribs.iter().chain(self.instance_attribs.iter()).collect();
+        for active in reflection.vertex_attribs.iter() {
+            let log = match declared.iter().find(|x| x.name == active.name) {
+                None => format!("Shader attribute {} is missing from the render type's attributes", active.name),
+                Some(x) if x.data_type.get_shader_type() != active.data_type => format!("Attribute {} is declared as {:?} but the shader uses {:?}", active.name, x.data_type, active.data_type),
+                Some(_) => continue
             };
-            if indicies_size == indicies.len() {
-                gl.buffer_data_with_array_buffer_view(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, &buffer_view, WebGl2RenderingContext::DYNAMIC_DRAW);
-            } else {
-                gl.buffer_data_with_i32(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, (indicies_size*mem::size_of::<u16>()) as i32, WebGl2RenderingContext::DYNAMIC_DRAW);
-                gl.buffer_sub_data_with_i32_and_array_buffer_view(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, 0, &buffer_view);
-            }
+            return Err(ShaderError::new(ShaderStage::Link, log, None));
         }
+        for x in declared.iter().filter(|x| !reflection.vertex_attribs.iter().any(|a| a.name == x.name)) {
+            log_str(&format!("Attribute {} is not used by the shader", x.name));
+        }
+        for active in reflection.uniform_attribs.iter().filter(|x| !self.uniform_attribs.iter().any(|u| u.name == x.name)) {
+            log_str(&format!("Uniform {} is missing from the render type's uniforms and can't be set", active.name));
+        }
+        Ok(())
+    }
 
-        let instance_buffer_object = match self.instanced {
-            None => None,
-            Some(_) => {
-                let instanced_buffer_object = gl.create_buffer().expect_throw("unable to create Instance Buffer Object");
-                gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&instanced_buffer_object));
-
-                unsafe {
-                    let buffer_view = js_sys::Uint8Array::view(&verticies);
-                    gl.buffer_data_with_array_buffer_view(WebGl2RenderingContext::ARRAY_BUFFER, &buffer_view, WebGl2RenderingContext::DYNAMIC_DRAW);
+    //magenta placeholder for when the type's own program fails, placed by its pos or position attribute and
+    //transformed by the type's view and projection uniforms or the camera block.
+    //The software backend's FallbackShader expects the view uniform to be declared before projection
+    pub(super) fn fallback_shaders(&self) -> (String,String) {
+        let position = position_attrib(&self.vertex_attribs)
+            .or_else(|| self.vertex_attribs.iter().find(|x| x.data_type.get_webgl_representation() == WebGl2RenderingContext::FLOAT && x.data_type.get_locations() == 1 && x.data_type.get_count() > 1));
+        let (declaration, position) = match position {
+            Some(a) => (
+                format!("in {} {};", a.data_type.get_glsl_name(), a.name),
+                match a.data_type.get_count() {
+                    2 => format!("vec4({}, 0.0, 1.0)", a.name),
+                    3 => format!("vec4({}, 1.0)", a.name),
+                    _ => a.name.clone()
                 }
+            ),
+            None => (String::new(), String::from("vec4(0.0)"))
+        };
+        let role = |role:UniformRole| self.uniform_attribs.iter().find(|x| x.role == role);
+        let (uniforms, position) = match (role(UniformRole::View), role(UniformRole::Projection)) {
+            (Some(view), Some(projection)) => (
+                format!("uniform mat4 {};\nuniform mat4 {};", view.name, projection.name),
+                format!("{} * {} * {}", projection.name, view.name, position)
+            ),
+            _ if self.uses_camera_block() => (
+                format!("layout(std140) uniform {} {{\n    mat4 projection;\n    mat4 view;\n}};", CAMERA_BLOCK),
+                format!("projection * view * {}", position)
+            ),
+            _ => (String::new(), position)
+        };
+
+        (
+            format!("#version 300 es\n{}\n{}\nvoid main() {{\n    gl_Position = {};\n}}", declaration, uniforms, position),
+            String::from("#version 300 es\nprecision mediump float;\nconst vec4 errorColor = vec4(1.0, 0.0, 1.0, 1.0);\nout vec4 fragColor;\nvoid main() {\n    fragColor = errorColor;\n}")
+        )
+    }
 
-                let stride = self.instance_attribs.iter().fold(0, |acc, a:&VertexAttrib| acc + a.data_type.get_size() );
-                let mut offset = 0;
-                for a in self.instance_attribs.iter() {
-                    let location = gl.get_attrib_location(program, &a.name) as u32;
-                    gl.enable_vertex_attrib_array(location);
-                    gl.vertex_attrib_pointer_with_i32(location, a.data_type.get_count(), a.data_type.get_webgl_representation(), false, stride, offset);
-                    gl.vertex_attrib_divisor(location, 1);
+    pub(super) fn setup_arrs(&self, gl:&Backend, verticies:&[u8], indicies:&[u32], program:ProgramHandle, verticies_size:usize, indicies_size:usize) -> GlBuffers {
+        let vao = gl.create_vertex_array();
+        gl.bind_vertex_array(Some(vao));
 
-                    offset += a.data_type.get_size();
-                }
+        let vbo = gl.create_buffer();
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(vbo));
+        match &self.instanced {
+            None => upload_sized(gl.as_ref(), WebGl2RenderingContext::ARRAY_BUFFER, verticies, verticies_size),
+            Some(instanced_data) => gl.buffer_data(WebGl2RenderingContext::ARRAY_BUFFER, &instanced_data.verticies, WebGl2RenderingContext::DYNAMIC_DRAW)
+        };
+
+        let ibo = gl.create_buffer();
+        gl.bind_buffer(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, Some(ibo));
+        match &self.instanced {
+            None => upload_sized(gl.as_ref(), WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, &self.index_format.to_bytes(indicies), indicies_size*self.index_format.get_size()),
+            Some(instanced_data) => gl.buffer_data(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, &self.index_format.to_bytes(&instanced_data.indicies), WebGl2RenderingContext::DYNAMIC_DRAW)
+        };
+
+        let instance_buffer_object = match self.instanced {
+            None => None,
+            Some(_) => {
+                let instanced_buffer_object = gl.create_buffer();
+                gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(instanced_buffer_object));
+                upload_sized(gl.as_ref(), WebGl2RenderingContext::ARRAY_BUFFER, verticies, verticies_size);
                 Some(instanced_buffer_object)
             }
         };
 
+        self.bind_attribs(gl.as_ref(), program, vbo, instance_buffer_object);
         gl.bind_vertex_array(None);
 
         GlBuffers { 
             gl:gl.clone(),
             vao:vao,
+            pick_vao:Cell::new(None),
             vbo: vbo, 
             ibo: ibo, 
-            instance: instance_buffer_object
+            instance: instance_buffer_object,
+            index_format:self.index_format
+        }
+    }
+
+    //points the bound vao's attributes at the buffers, using the program's attribute locations
+    fn bind_attribs(&self, gl:&dyn GraphicsBackend, program:ProgramHandle, vbo:BufferHandle, instance:Option<BufferHandle>) {
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(vbo));
+        bind_attrib_pointers(gl, program, &self.vertex_attribs, 0);
+
+        if let Some(instance) = instance {
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(instance));
+            bind_attrib_pointers(gl, program, &self.instance_attribs, 1);
         }
     }
 
+    pub(super) fn uses_camera_block(&self) -> bool {
+        self.vertex_shader.borrow().contains(&format!("uniform {}", CAMERA_BLOCK))
+    }
+
+    //position attribute read out of an object's verticies, the instance attributes for instanced types
+    fn object_position_attrib(&self) -> Option<(&VertexAttrib, usize, usize)> {
+        let (attribs, offsets, stride) = match self.instanced {
+            None => (&self.vertex_attribs, &self.vertex_attrib_offsets, self.vertex_size),
+            Some(_) => (&self.instance_attribs, &self.instance_attrib_offsets, self.instance_size)
+        };
+        let attrib = position_attrib(attribs)?;
+        Some((attrib, offsets[&attrib.name], stride))
+    }
+
     pub(super) fn get_blank_vertex(&self) -> Option<&Vec<u8>> {
         self.blank_vertex.as_ref()
     }
 
-    fn calc_v_attrib_offset(&self, name:&str, idx:u16) -> Option<usize> {
+    //size of one vertex, or one instance for instanced types, in a chunk's vertex buffer
+    pub(super) fn stride(&self) -> usize {
+        match self.instanced {
+            None => self.vertex_size,
+            Some(_) => self.instance_size
+        }
+    }
+
+    //instance counts aren't limited by the index format
+    pub(super) fn max_chunk_verticies(&self) -> usize {
+        match self.instanced {
+            None => self.index_format.max_verticies(),
+            Some(_) => usize::MAX
+        }
+    }
+
+    fn calc_v_attrib_offset(&self, name:&str, idx:u32) -> Option<usize> {
         Some(self.vertex_size*idx as usize + self.vertex_attrib_offsets.get(name)?)
     }
+
+    fn calc_i_attrib_offset(&self, name:&str, instance:u32) -> Option<usize> {
+        Some(self.instance_size*instance as usize + self.instance_attrib_offsets.get(name)?)
+    }
+}
+
+//attribute with the Position role, or named like one for hand written lists
+fn position_attrib(attribs:&[VertexAttrib]) -> Option<&VertexAttrib> {
+    attribs.iter().find(|x| x.role == AttributeRole::Position)
+        .or_else(|| attribs.iter().find(|x| x.name == "pos" || x.name == "position"))
+}
+
+//every vertex's value of a float position attribute, a 2 component one is at z 0 and the w of a 4 component one
+//is ignored. None for other types
+fn read_positions(attrib:&VertexAttrib, offset:usize, stride:usize, verticies:&[u8]) -> Option<Vec<Point3<f32>>> {
+    let components = match attrib.data_type {
+        ShaderDataTypes::FloatVec2 => 2,
+        ShaderDataTypes::FloatVec3 | ShaderDataTypes::FloatVec4 => 3,
+        _ => return None
+    };
+    let count = match stride {
+        0 => 0,
+        stride => verticies.len() / stride
+    };
+    Some((0..count).map(|v| {
+        let mut position = [0.0f32; 3];
+        let start = v * stride + offset;
+        for (c, x) in position.iter_mut().enumerate().take(components) {
+            let bytes = &verticies[start + c*4..start + c*4 + 4];
+            *x = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        Point3::new(position[0], position[1], position[2])
+    }).collect())
+}
+
+//gl needs every attribute offset and the stride to be a multiple of the attribute's component size,
+//padding each attribute to 4 bytes keeps that true for any mix of types
+const ATTRIB_ALIGNMENT:usize = 4;
+
+fn align_attrib(offset:usize) -> usize {
+    (offset + ATTRIB_ALIGNMENT - 1) / ATTRIB_ALIGNMENT * ATTRIB_ALIGNMENT
+}
+
+fn attrib_offsets(attribs:&[VertexAttrib]) -> (HashMap<String, usize>, usize) {
+    attribs.iter().fold((HashMap::new(),0), |(mut acc,last), x| {
+        acc.insert(x.name.clone(), last);
+        let new = align_attrib(x.data_type.get_size() as usize + last);
+        (acc,new)
+    })
 }
 
-#[derive(PartialEq)]
 pub(super) struct GlBuffers {
-    gl:WebGl2RenderingContext,
-    vao:WebGlVertexArrayObject,
-    vbo:WebGlBuffer,
-    ibo:WebGlBuffer,
-    instance:Option<WebGlBuffer>
+    gl:Backend,
+    vao:VertexArrayHandle,
+    //the same buffers at the pick program's attribute locations, created by the first pick
+    pick_vao:Cell<Option<VertexArrayHandle>>,
+    vbo:BufferHandle,
+    ibo:BufferHandle,
+    instance:Option<BufferHandle>,
+    index_format:IndexFormat
 }
 
 impl Drop for GlBuffers {
     fn drop(&mut self) {
-        self.gl.delete_buffer(Some(&self.vbo));
-        self.gl.delete_buffer(Some(&self.ibo));
-        if let Some(buffer) = &self.instance {
-            self.gl.delete_buffer(Some(&buffer))
+        self.gl.delete_buffer(self.vbo);
+        self.gl.delete_buffer(self.ibo);
+        if let Some(buffer) = self.instance {
+            self.gl.delete_buffer(buffer)
+        }
+        self.gl.delete_vertex_array(self.vao);
+        if let Some(vao) = self.pick_vao.get() {
+            self.gl.delete_vertex_array(vao);
         }
-        self.gl.delete_vertex_array(Some(&self.vao));
     }
 }
 
 impl GlBuffers {
-    pub fn buffer_sub_data(&self, gl:&WebGl2RenderingContext, verticies:&[u8],v_start:usize, indicies:&[u16], i_start:usize) {
+    //v_start is in bytes, i_start in indicies
+    pub fn buffer_sub_data(&self, gl:&dyn GraphicsBackend, verticies:&[u8],v_start:usize, indicies:&[u32], i_start:usize) {
+        //instanced index buffers only hold the render type's mesh
+        if self.update_instanced_data(gl, verticies, v_start).is_ok() { return; }
+
         if !verticies.is_empty() {
-            match &self.instance {
-                None => gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&self.vbo)),
-                Some(instance_buffer) => gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(instance_buffer))
-            };
-            
-            unsafe {
-                let buffer_view = js_sys::Uint8Array::view(verticies);
-                gl.buffer_sub_data_with_i32_and_array_buffer_view(WebGl2RenderingContext::ARRAY_BUFFER, v_start as i32, &buffer_view)
-            }
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(self.vbo));
+            gl.buffer_sub_data(WebGl2RenderingContext::ARRAY_BUFFER, v_start as i32, verticies)
         }
 
+        //the element array binding belongs to the vao, binding it without one would change the default vao instead
+        gl.bind_vertex_array(Some(self.vao));
+        gl.bind_buffer(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, Some(self.ibo));
+        gl.buffer_sub_data(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, (i_start*self.index_format.get_size()) as i32, &self.index_format.to_bytes(indicies));
+        gl.bind_vertex_array(None);
+    }
+
+    //copies the start of this chunk's vertex and index data into dst, which has to be at least as large.
+    //verticies_size is in bytes, indicies_len in indicies
+    pub fn copy_to(&self, gl:&dyn GraphicsBackend, dst:&GlBuffers, verticies_size:usize, indicies_len:usize) {
+        self.copy_verticies_to(gl, dst, 0, 0, verticies_size);
+
+        //instanced index buffers only hold the render type's mesh, which setup_arrs already uploaded
         if self.instance.is_none() {
-            gl.bind_buffer(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, Some(&self.ibo));
-            unsafe {
-                let buffer_view = js_sys::Uint16Array::view(indicies);
-                gl.buffer_sub_data_with_i32_and_array_buffer_view(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, i_start as i32 as i32, &buffer_view);
-            }
+            gl.bind_buffer(WebGl2RenderingContext::COPY_READ_BUFFER, Some(self.ibo));
+            gl.bind_buffer(WebGl2RenderingContext::COPY_WRITE_BUFFER, Some(dst.ibo));
+            gl.copy_buffer_sub_data(WebGl2RenderingContext::COPY_READ_BUFFER, WebGl2RenderingContext::COPY_WRITE_BUFFER, 0, 0, (indicies_len*self.index_format.get_size()) as i32);
+            gl.bind_buffer(WebGl2RenderingContext::COPY_READ_BUFFER, None);
+            gl.bind_buffer(WebGl2RenderingContext::COPY_WRITE_BUFFER, None);
         }
     }
 
-    // pub fn update_instanced_data(&self, gl:&WebGl2RenderingContext, instance_data:&[f32]) -> Result<(),()> {
-    //     if !self.instance.is_none() { return Err(()); }
-    //     gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&self.instance.unwrap()));
-    //     i32 size = gl.get_buffer_parameter(WebGl2RenderingContext::ARRAY_BUFFER, WebGl2RenderingContext::BUFFER_SIZE);
-    //     Ok(())
-    // }
+    //copies size bytes of vertex data between chunks, dst has to be a different set of buffers
+    pub fn copy_verticies_to(&self, gl:&dyn GraphicsBackend, dst:&GlBuffers, src_offset:usize, dst_offset:usize, size:usize) {
+        gl.bind_buffer(WebGl2RenderingContext::COPY_READ_BUFFER, Some(self.instance.unwrap_or(self.vbo)));
+        gl.bind_buffer(WebGl2RenderingContext::COPY_WRITE_BUFFER, Some(dst.instance.unwrap_or(dst.vbo)));
+        gl.copy_buffer_sub_data(WebGl2RenderingContext::COPY_READ_BUFFER, WebGl2RenderingContext::COPY_WRITE_BUFFER, src_offset as i32, dst_offset as i32, size as i32);
+        gl.bind_buffer(WebGl2RenderingContext::COPY_READ_BUFFER, None);
+        gl.bind_buffer(WebGl2RenderingContext::COPY_WRITE_BUFFER, None);
+    }
+
+    //writes instance data at start bytes into the instance buffer, Err if these buffers aren't instanced
+    pub fn update_instanced_data(&self, gl:&dyn GraphicsBackend, instance_data:&[u8], start:usize) -> Result<(),()> {
+        let instance_buffer = self.instance.ok_or(())?;
+        if !instance_data.is_empty() {
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(instance_buffer));
+            gl.buffer_sub_data(WebGl2RenderingContext::ARRAY_BUFFER, start as i32, instance_data);
+        }
+        Ok(())
+    }
+
+    pub fn draw(&self, gl:&dyn GraphicsBackend, render_type:Rc<RenderType>, count:i32) {
+        self.draw_with(gl, self.vao, &render_type, count);
+    }
+
+    //draws count indicies starting at offset, for non instanced buffers
+    pub fn draw_range(&self, gl:&dyn GraphicsBackend, offset:usize, count:i32) {
+        self.draw_range_with(gl, self.vao, offset, count);
+    }
 
-    pub fn draw(&self, gl:&WebGl2RenderingContext, render_type:Rc<RenderType>, count:i32) {
-        gl.bind_vertex_array(Some(&self.vao));
+    //draw and draw_range with the pick program, which has to be in use
+    pub fn pick(&self, gl:&dyn GraphicsBackend, render_type:&RenderType, program:ProgramHandle, count:i32) {
+        self.draw_with(gl, self.pick_vao(gl, render_type, program), render_type, count);
+    }
+
+    pub fn pick_range(&self, gl:&dyn GraphicsBackend, render_type:&RenderType, program:ProgramHandle, offset:usize, count:i32) {
+        self.draw_range_with(gl, self.pick_vao(gl, render_type, program), offset, count);
+    }
+
+    fn pick_vao(&self, gl:&dyn GraphicsBackend, render_type:&RenderType, program:ProgramHandle) -> VertexArrayHandle {
+        if let Some(vao) = self.pick_vao.get() {
+            return vao;
+        }
+        let vao = gl.create_vertex_array();
+        gl.bind_vertex_array(Some(vao));
+        gl.bind_buffer(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, Some(self.ibo));
+        render_type.bind_attribs(gl, program, self.vbo, self.instance);
+        gl.bind_vertex_array(None);
+        self.pick_vao.set(Some(vao));
+        vao
+    }
+
+    fn draw_with(&self, gl:&dyn GraphicsBackend, vao:VertexArrayHandle, render_type:&RenderType, count:i32) {
+        gl.bind_vertex_array(Some(vao));
         if self.instance.is_some() {
             let l = &render_type.instanced.as_ref().expect_throw("Expected render type data to contain instance data").indicies.len();
-            gl.draw_elements_instanced_with_i32(WebGl2RenderingContext::TRIANGLES, *l as i32, WebGl2RenderingContext::UNSIGNED_SHORT, 0, count);
+            gl.draw_elements_instanced(WebGl2RenderingContext::TRIANGLES, *l as i32, self.index_format.get_webgl_representation(), 0, count);
         } else {
-            gl.draw_elements_with_i32(WebGl2RenderingContext::TRIANGLES, count, WebGl2RenderingContext::UNSIGNED_SHORT, 0);
+            gl.draw_elements(WebGl2RenderingContext::TRIANGLES, count, self.index_format.get_webgl_representation(), 0);
         }
         gl.bind_vertex_array(None);
     }
 
+    fn draw_range_with(&self, gl:&dyn GraphicsBackend, vao:VertexArrayHandle, offset:usize, count:i32) {
+        gl.bind_vertex_array(Some(vao));
+        gl.draw_elements(WebGl2RenderingContext::TRIANGLES, count, self.index_format.get_webgl_representation(), (offset*self.index_format.get_size()) as i32);
+        gl.bind_vertex_array(None);
+    }
+
+    //points the vao at the attribute locations of a new program for the same render type.
+    //The pick program is linked again for it, so the pick vao is deleted until the next pick
+    pub fn rebind(&self, gl:&dyn GraphicsBackend, render_type:&RenderType, program:ProgramHandle) {
+        gl.bind_vertex_array(Some(self.vao));
+        render_type.bind_attribs(gl, program, self.vbo, self.instance);
+        gl.bind_vertex_array(None);
+        if let Some(vao) = self.pick_vao.take() {
+            gl.delete_vertex_array(vao);
+        }
+    }
+
     pub fn is_instanced(&self) -> bool {
         self.instance.is_some()
     }
 
     #[allow(unused)]
-    pub fn log_data(&self, gl:&WebGl2RenderingContext, v_count:u32, i_count:u32) {
-        //TODO: Check if this causes memory leak
-        let v_dst = Uint8Array::new(&ArrayBuffer::new(v_count * 4));
-        let i_dst = Uint16Array::new(&ArrayBuffer::new(i_count * 2));
+    pub fn log_data(&self, gl:&dyn GraphicsBackend, v_count:u32, i_count:u32) {
+        let mut v_dst = vec![0u8; v_count as usize * 4];
+        let mut i_dst = vec![0u8; i_count as usize * self.index_format.get_size()];
 
-        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER,Some(&self.vbo));
-        gl.bind_buffer(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,Some(&self.ibo));
-        gl.get_buffer_sub_data_with_i32_and_array_buffer_view_and_dst_offset_and_length(WebGl2RenderingContext::ARRAY_BUFFER,0, &v_dst, 0, v_count);
-        gl.get_buffer_sub_data_with_i32_and_array_buffer_view_and_dst_offset_and_length(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,0, &i_dst, 0, i_count);
-        
+        gl.bind_vertex_array(Some(self.vao));
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER,Some(self.vbo));
+        gl.get_buffer_sub_data(WebGl2RenderingContext::ARRAY_BUFFER,0, &mut v_dst);
+        gl.get_buffer_sub_data(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,0, &mut i_dst);
+        gl.bind_vertex_array(None);
 
         log_str("verticies");
-        log_u8_as_f32_arr(v_dst);
+        log_u8_as_f32_arr(Uint8Array::from(&v_dst[..]));
         log_str("indicies");
-        log_u16_arr(i_dst);
+        match self.index_format {
+            IndexFormat::U16 => {
+                let i_dst:Vec<u16> = i_dst.chunks_exact(2).map(|x| u16::from_ne_bytes([x[0],x[1]])).collect();
+                log_u16_arr(Uint16Array::from(&i_dst[..]));
+            },
+            IndexFormat::U32 => {
+                let i_dst:Vec<u32> = i_dst.chunks_exact(4).map(|x| u32::from_ne_bytes([x[0],x[1],x[2],x[3]])).collect();
+                log_u32_arr(Uint32Array::from(&i_dst[..]));
+            }
+        }
     }
 }
 
-#[derive(Clone, Copy,Debug)]
+//interleaved attributes in the buffer bound to ARRAY_BUFFER, attributes the program doesn't use are skipped
+fn bind_attrib_pointers(gl:&dyn GraphicsBackend, program:ProgramHandle, attribs:&[VertexAttrib], divisor:u32) {
+    let (offsets, stride) = attrib_offsets(attribs);
+    let stride = stride as i32;
+    for a in attribs.iter() {
+        //unused attributes were already reported once by check_program
+        let location = gl.get_attrib_location(program, &a.name);
+        if location == -1 { continue; }
+        let data_type = &a.data_type;
+        //matrices take one location per column
+        let column_size = data_type.get_component_size() * data_type.get_count();
+        for column in 0..data_type.get_locations() {
+            let location = (location + column) as u32;
+            let offset = offsets[&a.name] as i32 + column * column_size;
+            gl.enable_vertex_attrib_array(location);
+            if data_type.is_integer() {
+                gl.vertex_attrib_i_pointer(location, data_type.get_count(), data_type.get_webgl_representation(), stride, offset);
+            } else {
+                gl.vertex_attrib_pointer(location, data_type.get_count(), data_type.get_webgl_representation(), data_type.is_normalized(), stride, offset);
+            }
+            //set even when 0, rebinding to a new program can move locations between the vertex and instance buffer
+            gl.vertex_attrib_divisor(location, divisor);
+        }
+    }
+}
+
+//allocates size bytes for the buffer bound to target and fills its start with data
+fn upload_sized(gl:&dyn GraphicsBackend, target:u32, data:&[u8], size:usize) {
+    if size == data.len() {
+        gl.buffer_data(target, data, WebGl2RenderingContext::DYNAMIC_DRAW);
+    } else {
+        gl.buffer_data_with_size(target, size as i32, WebGl2RenderingContext::DYNAMIC_DRAW);
+        gl.buffer_sub_data(target, 0, data);
+    }
+}
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[allow(unused)]
 pub enum AttributeRole {
     Custom,
+    //where the vertex is, used to sort translucent objects and to place the fallback shader
+    Position,
     TextureCoordinate
 }
 
+impl AttributeRole {
+    //naming convention used by reflection
+    pub fn from_name(name:&str) -> Self {
+        match name.to_lowercase().as_str() {
+            "pos" | "position" => Self::Position,
+            "texcoord" | "texcoords" | "uv" => Self::TextureCoordinate,
+            _ => Self::Custom
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 #[allow(unused)]
 pub enum UniformRole {
     Custom,
     Projection,
-    View
+    View,
+    //float opacity of the layer being drawn
+    Opacity
+}
+
+impl UniformRole {
+    //naming convention used by reflection, these uniforms are filled in by the renderer
+    pub fn from_name(name:&str) -> Self {
+        match name {
+            "projection" => Self::Projection,
+            "view" => Self::View,
+            "opacity" => Self::Opacity,
+            _ => Self::Custom
+        }
+    }
 }
 
 #[derive(Clone,Debug)]
@@ -381,7 +979,7 @@ pub struct UniformAttrib {
     pub role:UniformRole
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 #[allow(unused)]
 pub enum ShaderDataTypes {
     FLOAT,
@@ -389,58 +987,150 @@ pub enum ShaderDataTypes {
     FloatVec3,
     FloatVec4,
     INT,
-    // INT_VEC2,
-    // INT_VEC3,
-    // INT_VEC4,
-    // BOOL,
-    // BOOL_VEC2,
-    // BOOL_VEC3,
-    // BOOL_VEC4,
-    // FLOAT_MAT2,
-    // FLOAT_MAT3,
-    // FLOAT_MAT4,
-    // SAMPLER_2D,
-    // SAMPLER_CUBE,
+    IntVec2,
+    IntVec3,
+    IntVec4,
+    UINT,
+    UintVec2,
+    UintVec3,
+    UintVec4,
+    //one column per attribute location
+    FloatMat2,
+    FloatMat3,
+    FloatMat4,
+    //integers stored in the buffer that the shader reads as floats in 0..1, or -1..1 for the signed ones.
+    //Packed colors are UnsignedByteNormalizedVec4
+    ByteNormalizedVec2,
+    ByteNormalizedVec4,
+    UnsignedByteNormalizedVec2,
+    UnsignedByteNormalizedVec4,
+    ShortNormalizedVec2,
+    ShortNormalizedVec4,
+    UnsignedShortNormalizedVec2,
+    UnsignedShortNormalizedVec4
     // FLOAT_MAT2x3,
     // FLOAT_MAT2x4,
     // FLOAT_MAT3x2,
     // FLOAT_MAT3x4,
     // FLOAT_MAT4x2,
     // FLOAT_MAT4x3,
-    // UNSIGNED_INT_VEC2,
-    // UNSIGNED_INT_VEC3,
-    // UNSIGNED_INT_VEC4,
-    // UNSIGNED_NORMALIZED,
-    // SIGNED_NORMALIZED
 }
 
 impl ShaderDataTypes {
+    //from the type enum of an active attribute, normalized types show up as the float vector they are read as
+    pub fn from_webgl_representation(data_type:u32) -> Option<Self> {
+        match data_type {
+            WebGl2RenderingContext::FLOAT => Some(Self::FLOAT),
+            WebGl2RenderingContext::FLOAT_VEC2 => Some(Self::FloatVec2),
+            WebGl2RenderingContext::FLOAT_VEC3 => Some(Self::FloatVec3),
+            WebGl2RenderingContext::FLOAT_VEC4 => Some(Self::FloatVec4),
+            WebGl2RenderingContext::INT => Some(Self::INT),
+            WebGl2RenderingContext::INT_VEC2 => Some(Self::IntVec2),
+            WebGl2RenderingContext::INT_VEC3 => Some(Self::IntVec3),
+            WebGl2RenderingContext::INT_VEC4 => Some(Self::IntVec4),
+            WebGl2RenderingContext::UNSIGNED_INT => Some(Self::UINT),
+            WebGl2RenderingContext::UNSIGNED_INT_VEC2 => Some(Self::UintVec2),
+            WebGl2RenderingContext::UNSIGNED_INT_VEC3 => Some(Self::UintVec3),
+            WebGl2RenderingContext::UNSIGNED_INT_VEC4 => Some(Self::UintVec4),
+            WebGl2RenderingContext::FLOAT_MAT2 => Some(Self::FloatMat2),
+            WebGl2RenderingContext::FLOAT_MAT3 => Some(Self::FloatMat3),
+            WebGl2RenderingContext::FLOAT_MAT4 => Some(Self::FloatMat4),
+            _ => None
+        }
+    }
+
+    //component type in the buffer
     pub fn get_webgl_representation(&self) -> u32 {
         match self {
-            Self::FLOAT => WebGl2RenderingContext::FLOAT,
-            Self::INT => WebGl2RenderingContext::INT,
-            Self::FloatVec2 => WebGl2RenderingContext::FLOAT,
-            Self::FloatVec4 => WebGl2RenderingContext::FLOAT,
-            Self::FloatVec3 => WebGl2RenderingContext::FLOAT
+            Self::FLOAT | Self::FloatVec2 | Self::FloatVec3 | Self::FloatVec4 |
+            Self::FloatMat2 | Self::FloatMat3 | Self::FloatMat4 => WebGl2RenderingContext::FLOAT,
+            Self::INT | Self::IntVec2 | Self::IntVec3 | Self::IntVec4 => WebGl2RenderingContext::INT,
+            Self::UINT | Self::UintVec2 | Self::UintVec3 | Self::UintVec4 => WebGl2RenderingContext::UNSIGNED_INT,
+            Self::ByteNormalizedVec2 | Self::ByteNormalizedVec4 => WebGl2RenderingContext::BYTE,
+            Self::UnsignedByteNormalizedVec2 | Self::UnsignedByteNormalizedVec4 => WebGl2RenderingContext::UNSIGNED_BYTE,
+            Self::ShortNormalizedVec2 | Self::ShortNormalizedVec4 => WebGl2RenderingContext::SHORT,
+            Self::UnsignedShortNormalizedVec2 | Self::UnsignedShortNormalizedVec4 => WebGl2RenderingContext::UNSIGNED_SHORT
         }
     }
 
-    pub fn get_size(&self) -> i32 {
+    //the type the shader declares the attribute as
+    pub fn get_shader_type(&self) -> Self {
         match self {
-            Self::FLOAT => 4,
-            Self::INT => 4,
-            Self::FloatVec2 => 8,
-            Self::FloatVec3 => 12,
-            Self::FloatVec4 => 16
+            Self::ByteNormalizedVec2 | Self::UnsignedByteNormalizedVec2 |
+            Self::ShortNormalizedVec2 | Self::UnsignedShortNormalizedVec2 => Self::FloatVec2,
+            Self::ByteNormalizedVec4 | Self::UnsignedByteNormalizedVec4 |
+            Self::ShortNormalizedVec4 | Self::UnsignedShortNormalizedVec4 => Self::FloatVec4,
+            x => x.clone()
+        }
+    }
+
+    pub fn get_glsl_name(&self) -> &'static str {
+        match self.get_shader_type() {
+            Self::FLOAT => "float",
+            Self::FloatVec2 => "vec2",
+            Self::FloatVec3 => "vec3",
+            Self::FloatVec4 => "vec4",
+            Self::INT => "int",
+            Self::IntVec2 => "ivec2",
+            Self::IntVec3 => "ivec3",
+            Self::IntVec4 => "ivec4",
+            Self::UINT => "uint",
+            Self::UintVec2 => "uvec2",
+            Self::UintVec3 => "uvec3",
+            Self::UintVec4 => "uvec4",
+            Self::FloatMat2 => "mat2",
+            Self::FloatMat3 => "mat3",
+            _ => "mat4"
+        }
+    }
+
+    pub fn get_component_size(&self) -> i32 {
+        match self.get_webgl_representation() {
+            WebGl2RenderingContext::BYTE | WebGl2RenderingContext::UNSIGNED_BYTE => 1,
+            WebGl2RenderingContext::SHORT | WebGl2RenderingContext::UNSIGNED_SHORT => 2,
+            _ => 4
         }
     }
 
+    //bytes in the buffer, before padding to ATTRIB_ALIGNMENT
+    pub fn get_size(&self) -> i32 {
+        self.get_component_size() * self.get_count() * self.get_locations()
+    }
+
+    //components per attribute location
     pub fn get_count(&self) -> i32 {
         match self {
-            Self::FLOAT | Self::INT => 1,
-            Self::FloatVec2 => 2,
-            Self::FloatVec3 => 3,
-            Self::FloatVec4 => 4
+            Self::FLOAT | Self::INT | Self::UINT => 1,
+            Self::FloatVec2 | Self::IntVec2 | Self::UintVec2 | Self::FloatMat2 |
+            Self::ByteNormalizedVec2 | Self::UnsignedByteNormalizedVec2 |
+            Self::ShortNormalizedVec2 | Self::UnsignedShortNormalizedVec2 => 2,
+            Self::FloatVec3 | Self::IntVec3 | Self::UintVec3 | Self::FloatMat3 => 3,
+            _ => 4
+        }
+    }
+
+    pub fn get_locations(&self) -> i32 {
+        match self {
+            Self::FloatMat2 => 2,
+            Self::FloatMat3 => 3,
+            Self::FloatMat4 => 4,
+            _ => 1
+        }
+    }
+
+    //read by the shader as integers, these have to be bound with vertexAttribIPointer
+    pub fn is_integer(&self) -> bool {
+        match self.get_webgl_representation() {
+            WebGl2RenderingContext::INT | WebGl2RenderingContext::UNSIGNED_INT => true,
+            _ => false
+        }
+    }
+
+    pub fn is_normalized(&self) -> bool {
+        match self.get_webgl_representation() {
+            WebGl2RenderingContext::BYTE | WebGl2RenderingContext::UNSIGNED_BYTE |
+            WebGl2RenderingContext::SHORT | WebGl2RenderingContext::UNSIGNED_SHORT => true,
+            _ => false
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,139 @@
+use cgmath::{Rad, Vector4};
+use web_sys::WebGl2RenderingContext;
+
+use super::{error::RenderError, backend::{Backend, GraphicsBackend, FramebufferHandle, RenderbufferHandle}, camera::{Camera, Projection}, renderer::{Renderer, MappedTexture}, texture::{BatchableTextureSource, TextureFormat, TextureFilter}};
+
+//texture source for a target's color attachment, nothing is uploaded since the gpu draws into it.
+//Mips would go stale with every draw, so mipmapped filters are used without them
+struct RenderTargetSource {
+    width:i32,
+    height:i32,
+    filter:TextureFilter
+}
+
+impl BatchableTextureSource for RenderTargetSource {
+    fn format(&self) -> TextureFormat { TextureFormat::RGBA }
+    fn min_filter(&self) -> TextureFilter { self.filter.without_mipmaps() }
+    fn mag_filter(&self) -> TextureFilter { self.filter.without_mipmaps() }
+    fn height(&self) -> i32 { self.height }
+    fn width(&self) -> i32 { self.width }
+    fn unique_texture(&self) -> bool { true }
+    fn tex_sub_image_2d(&self, _:&dyn GraphicsBackend, _:i32, _:i32) {}
+}
+
+//offscreen color and depth attachments to draw into. The color attachment is a texture of its own in the
+//texture batcher, so what was drawn is sampled like any other MappedTexture through UniformData::Texture
+#[derive(Debug)]
+pub struct RenderTarget {
+    gl:Backend,
+    framebuffer:FramebufferHandle,
+    depth:RenderbufferHandle,
+    texture:MappedTexture,
+    width:u32,
+    height:u32
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        self.gl.delete_framebuffer(self.framebuffer);
+        self.gl.delete_renderbuffer(self.depth);
+    }
+}
+
+impl RenderTarget {
+    pub fn new(renderer:&mut Renderer, width:u32, height:u32, filter:TextureFilter) -> Result<Self,RenderError> {
+        //gl reports zero sized attachments as incomplete, the texture atlas can't hold them at all
+        if width == 0 || height == 0 {
+            return Err(RenderError::IncompleteFramebuffer(WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_ATTACHMENT));
+        }
+        let gl = renderer.backend().clone();
+        let texture = renderer.upload_texture(&RenderTargetSource { width:width as i32, height:height as i32, filter:filter });
+
+        let depth = gl.create_renderbuffer();
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(depth));
+        gl.renderbuffer_storage(WebGl2RenderingContext::RENDERBUFFER, WebGl2RenderingContext::DEPTH_COMPONENT16, width as i32, height as i32);
+
+        let framebuffer = gl.create_framebuffer();
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(framebuffer));
+        gl.framebuffer_texture_2d(WebGl2RenderingContext::FRAMEBUFFER, WebGl2RenderingContext::COLOR_ATTACHMENT0, WebGl2RenderingContext::TEXTURE_2D, Some(texture.texture_handle(renderer)), 0);
+        gl.framebuffer_renderbuffer(WebGl2RenderingContext::FRAMEBUFFER, WebGl2RenderingContext::DEPTH_ATTACHMENT, WebGl2RenderingContext::RENDERBUFFER, Some(depth));
+        let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        //dropping the target deletes the attachments again
+        let target = Self { gl:gl, framebuffer:framebuffer, depth:depth, texture:texture, width:width, height:height };
+        if status != WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+            return Err(RenderError::IncompleteFramebuffer(status));
+        }
+        Ok(target)
+    }
+
+    //color attachment, texcoords 0..1 cover the whole target
+    pub fn texture(&self) -> MappedTexture {
+        self.texture.clone()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub(super) fn bind(&self) {
+        self.gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(self.framebuffer));
+        self.gl.viewport(0, 0, self.width as i32, self.height as i32);
+    }
+}
+
+//draws every render type into a target from its own camera before the frame is drawn to the canvas.
+//Chunks sampling the pass's own target are skipped, since reading and writing a texture in one draw is undefined
+#[derive(Debug)]
+pub struct RenderPass {
+    pub target:RenderTarget,
+    pub camera:Camera,
+    pub clear_color:Vector4<f32>,
+    //disabled passes keep what they last drew, for content that only needs drawing once
+    pub enabled:bool
+}
+
+impl RenderPass {
+    pub fn new(target:RenderTarget, camera:Camera, clear_color:Vector4<f32>) -> Self {
+        Self { target:target, camera:camera, clear_color:clear_color, enabled:true }
+    }
+
+    //projection with the renderer's field of view and clip planes, at the target's aspect ratio
+    pub(super) fn projection(&self, fovy:Rad<f32>, znear:f32, zfar:f32) -> Projection {
+        Projection::new(self.target.width, self.target.height, fovy, znear, zfar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use web_sys::WebGl2RenderingContext;
+
+    use super::RenderTarget;
+    use crate::engine::render::{renderer::Renderer, error::RenderError, texture::TextureFilter, backend::recording::RecordingBackend};
+
+    #[test]
+    fn creates_complete_targets() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut renderer = Renderer::with_backend(gl.clone());
+        let target = RenderTarget::new(&mut renderer, 16, 8, TextureFilter::Linear).unwrap();
+        assert_eq!((target.width(), target.height()), (16, 8));
+        assert_eq!(gl.state().framebuffers.len(), 1);
+    }
+
+    #[test]
+    fn incomplete_targets_are_errors() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut renderer = Renderer::with_backend(gl.clone());
+        let result = RenderTarget::new(&mut renderer, 0, 0, TextureFilter::Linear);
+        assert!(matches!(result, Err(RenderError::IncompleteFramebuffer(WebGl2RenderingContext::FRAMEBUFFER_INCOMPLETE_ATTACHMENT))));
+        //nothing is left allocated
+        assert!(gl.state().framebuffers.is_empty());
+        assert!(gl.state().renderbuffers.is_empty());
+    }
+}
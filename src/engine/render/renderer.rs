@@ -1,14 +1,14 @@
-use std::{collections::HashMap, rc::{Rc, Weak}, mem, cell::RefCell, fmt::Debug};
+use std::{collections::HashMap, rc::{Rc, Weak}, cell::RefCell, cmp::Ordering, fmt::Debug, ops::Range};
 
-use cgmath::{Point3, Rad, Matrix4, Vector2, Vector3, Vector4};
+use cgmath::{Point3, Rad, Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4, SquareMatrix, EuclideanSpace};
 use wasm_bindgen::{JsCast, UnwrapThrowExt, prelude::Closure};
-use web_sys::{WebGl2RenderingContext, WebGlProgram, HtmlCanvasElement, WebGlUniformLocation, HtmlImageElement, Event};
+use web_sys::{WebGl2RenderingContext, HtmlCanvasElement, HtmlImageElement, Event};
 
 use crate::log_str;
 
 use gloo_console::warn;
 
-use super::{render_object::{GlBuffers, RenderType, RenderObject, UniformAttrib, UniformRole}, texture::{TextureBatcher, BatchedTexture, BatchableTextureSource, ImageTextureSource, TempBlankTextureSource, UpdateCache, TextureFormat, TextureFilter}, index_map::IndexMap, camera::{Camera, Projection}};
+use super::{render_object::{GlBuffers, RenderType, RenderTypeId, RenderObject, UniformAttrib, UniformRole, BlendMode}, shader_registry::ShaderRegistry, uniform_buffer::{UniformBuffer, CAMERA_BLOCK, CAMERA_BLOCK_BINDING, CAMERA_BLOCK_SIZE, CAMERA_PROJECTION_OFFSET, CAMERA_VIEW_OFFSET}, types, effects, texture::{TextureBatcher, BatchedTexture, BatchableTextureSource, ImageTextureSource, TempBlankTextureSource, UpdateCache, TextureFormat, TextureFilter, PlacementListener}, index_map::IndexMap, program::Program, camera::{Camera, Projection}, camera_2d::Camera2D, bounds::{Aabb, Ray}, render_target::{RenderTarget, RenderPass}, layer::{self, Layer, LayerId}, pick::{self, PickTarget}, error::RenderError, post_process::{self, PostProcess, PostEffect}, backend::{Backend, GraphicsBackend, ProgramHandle, UniformLocation, TextureHandle, webgl::WebGl2Backend}};
 
 //TODO- maybe find a better way to approach fragmentation when large number of textures are being used in one rendertype, 
 //because if textures are inserted whereever there is space and the texture batcher instances are getting full, 
@@ -20,16 +20,35 @@ const BATCH_TEXTURE_SIZE:i32 = 8192;
 const DEFAULT_FOV_Y:Rad<f32> = Rad(1.22173);
 const DEFAULT_Z_NEAR:f32 = 0.01;
 const DEFAULT_Z_FAR:f32 = 100.0;
+//fraction of a chunk's used range that has to be holes before it is compacted while rendering
+const COMPACTION_THRESHOLD:f32 = 0.25;
+//most draw calls a partly culled chunk is split into, the smallest gaps between the parts in view are drawn too
+const MAX_CULLED_DRAW_RANGES:usize = 8;
 
 #[derive(Debug)]
 pub struct Renderer {
-    gl:WebGl2RenderingContext,
-    canvas:HtmlCanvasElement,
-    render_batchers:HashMap<*const RenderType,RenderBatcher>,
+    gl:Backend,
+    render_batchers:HashMap<BatcherKey,RenderBatcher>,
+    //batchers are drawn by layer and z index, then in the order they were first added so frames are reproducible
+    render_order:Vec<BatcherKey>,
+    layers:Vec<Layer>,
+    shaders:ShaderRegistry,
+    //projection and view for every program declaring the Camera block, uploaded once per frame
+    camera_block:UniformBuffer,
+    //projection and view written to the camera block since the frame started, layers sharing them skip the upload
+    camera_block_matrices:Option<(Matrix4<f32>,Matrix4<f32>)>,
     texture_batcher:TextureBatcher,
     loaded_images:HashMap<String, Weak<RefCell<BatchedTexture>>>,
+    //drawn in order into their targets before the canvas
+    passes:Vec<RenderPass>,
+    post_process:PostProcess,
+    //created by the first pick and again when the canvas is resized
+    pick_target:Option<PickTarget>,
+    clear_color:Vector4<f32>,
     camera:Camera,
     projection:Projection,
+    //replaces camera and projection while set
+    camera_2d:Option<Camera2D>,
     pub fovy:Rad<f32>,
     pub znear:f32,
     pub zfar:f32
@@ -37,64 +56,93 @@ pub struct Renderer {
 
 impl PartialEq for Renderer {
     fn eq(&self, other: &Self) -> bool {
-        self.canvas == other.canvas && self.gl == other.gl
+        Rc::ptr_eq(&self.gl, &other.gl)
     }
 }
 
 impl Renderer {
     pub fn new(canvas:HtmlCanvasElement) -> Self {
-        let gl = canvas.get_context("webgl2").unwrap().unwrap().dyn_into::<WebGl2RenderingContext>().unwrap();
+        Self::with_backend(Rc::new(WebGl2Backend::new(canvas)))
+    }
 
+    pub fn with_backend(gl:Backend) -> Self {
         //config
         gl.pixel_storei(WebGl2RenderingContext::UNPACK_FLIP_Y_WEBGL, 1);
+        //blending is set per render type while drawing, see BlendMode
         gl.enable(WebGl2RenderingContext::DEPTH_TEST);
-        gl.enable(WebGl2RenderingContext::BLEND);
-        gl.blend_func(WebGl2RenderingContext::SRC_ALPHA, WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA);
+        //translucent types still draw over opaque ones at the same depth, which everything 2D is at
+        gl.depth_func(WebGl2RenderingContext::LEQUAL);
+
+        let (width, height) = gl.drawable_size();
 
-        let width = canvas.width();
-        let height = canvas.height();
+        let mut shaders = ShaderRegistry::new();
+        types::register_builtin(&mut shaders);
+        effects::register_builtin(&mut shaders);
 
         Self { 
             gl: gl.clone(),
-            canvas: canvas, 
             render_batchers: HashMap::new(),
+            render_order:Vec::new(),
+            layers:layer::builtin(),
+            shaders:shaders,
+            camera_block:UniformBuffer::new(&gl, CAMERA_BLOCK, CAMERA_BLOCK_BINDING, CAMERA_BLOCK_SIZE),
+            camera_block_matrices:None,
             texture_batcher:TextureBatcher::new(gl, BATCH_TEXTURE_SIZE, BATCH_TEXTURE_SIZE),
             loaded_images:HashMap::new(),
+            passes:Vec::new(),
+            post_process:PostProcess::default(),
+            pick_target:None,
+            clear_color:Vector4::new(0.0, 0.0, 0.0, 0.0),
             camera: Camera::new(Point3::new(0.0, 0.0, 1.0), Rad(-1.57079633), Rad(0.0)),
             projection: Projection::new(width, height, DEFAULT_FOV_Y, DEFAULT_Z_NEAR, DEFAULT_Z_FAR),
+            camera_2d:None,
             fovy:DEFAULT_FOV_Y,
             znear:DEFAULT_Z_NEAR,
             zfar:DEFAULT_Z_FAR
         }
     }
 
-    pub fn add(&mut self, object:&mut RenderObject) {
-        if let Some(data) = self.render_batchers.get_mut(&Rc::as_ptr(&object.type_id)) {
-            data.map_render_object(object);
-        } else {
-            let data = RenderBatcher::map_render_object_into_new(&self.gl, object);
-            self.render_batchers.insert(Rc::as_ptr(&object.type_id), data);
+    //Err if the object can't be drawn with its render type, it is left without an allocation then
+    pub fn add(&mut self, object:&mut RenderObject) -> Result<(),RenderError> {
+        let key = BatcherKey::of(object);
+        if key.layer.0 >= self.layers.len() {
+            return Err(RenderError::UnknownLayer(key.layer));
         }
+        if let Some(data) = self.render_batchers.get_mut(&key) {
+            return data.map_render_object(object);
+        }
+        let program = self.program_or_fallback(&object.type_id)?;
+        let data = RenderBatcher::map_render_object_into_new(&self.gl, key, program, object)?;
+        self.render_batchers.insert(key, data);
+        //after every batcher at the same layer and z index
+        let i = self.render_order.iter().position(|x| (x.layer, x.z_index) > (key.layer, key.z_index)).unwrap_or(self.render_order.len());
+        self.render_order.insert(i, key);
+        Ok(())
     }
 
-    pub fn update(&mut self, object:&mut RenderObject) {
+    //a type whose shaders fail keeps drawing in magenta until it's reloaded with working ones
+    fn program_or_fallback(&mut self, render_type:&RenderType) -> Result<Rc<Program>,RenderError> {
+        let program = match self.shaders.program(&self.gl, render_type) {
+            Ok(program) => program,
+            Err(e) => {
+                log_str(&e.to_string());
+                self.shaders.fallback_program(&self.gl, render_type)?
+            }
+        };
+        self.camera_block.bind_program(program.handle());
+        Ok(program)
+    }
+
+    pub fn update(&mut self, object:&mut RenderObject) -> Result<(),RenderError> {
         
         if let Some(allocation) = &object.allocation {
-            if Rc::ptr_eq(&allocation.render_type, &object.type_id) {
-                let batcher = self.render_batchers.get_mut(&Rc::as_ptr(&object.type_id)).expect_throw("Expected batcher to exist while updating render object");
-                batcher.update(object);
-            return;
+            if Rc::ptr_eq(&allocation.render_type, &object.type_id) && allocation.key == BatcherKey::of(object) {
+                let batcher = self.render_batchers.get_mut(&allocation.key).expect_throw("Expected batcher to exist while updating render object");
+                return batcher.update(object);
             }
         }
 
-        self.add(object);
-    }
-
-    fn resize_canvas(&self) {
-        let display_width = self.canvas.client_width();
-        let display_height = self.canvas.client_height();
-        self.canvas.set_width(display_width as u32);
-        self.canvas.set_height(display_height as u32);
+        self.add(object)
     }
 
     pub fn upload_image_from_url(&mut self, url:String, min_filter:TextureFilter, mag_filter:TextureFilter) -> MappedTexture {
@@ -139,33 +187,364 @@ impl Renderer {
         }
     }
 
-    pub fn set_clear_color(&self, color:Vector4<f32>) {
-        self.gl.clear_color(color.x, color.y, color.z, color.w)
+    //moves batched textures together to undo fragmentation from removed ones, texcoords taken before are stale for
+    //moved textures, which MappedTexture::listen tells
+    pub fn repack_textures(&mut self) {
+        self.texture_batcher.repack();
+    }
+
+    //see TextureBatcher::set_memory_budget, a batch texture takes BATCH_TEXTURE_SIZE² times 4 bytes
+    pub fn set_texture_memory_budget(&mut self, bytes:Option<usize>) {
+        self.texture_batcher.set_memory_budget(bytes);
+    }
+
+    pub fn texture_memory_usage(&self) -> usize {
+        self.texture_batcher.memory_usage()
+    }
+
+    //see TextureBatcher::set_anisotropy
+    pub fn set_texture_anisotropy(&mut self, anisotropy:f32) {
+        self.texture_batcher.set_anisotropy(anisotropy);
+    }
+
+    pub fn set_clear_color(&mut self, color:Vector4<f32>) {
+        self.clear_color = color;
     }
 
     pub fn render(&mut self) {
+        self.texture_batcher.update();
+        self.release_unused_batchers();
+        self.camera_block_matrices = None;
+
+        //offscreen passes go first so the canvas can show what they drew this frame
+        for i in 0..self.passes.len() {
+            let pass = &self.passes[i];
+            if !pass.enabled { continue; }
+            pass.target.bind();
+            self.clear(pass.clear_color);
+            let global_uniforms = calc_global_uniforms(pass.camera.calc_matrix(), pass.projection(self.fovy, self.znear, self.zfar).calc_matrix());
+            let target = pass.target.texture();
+            self.draw_layers(&global_uniforms, Some(&target));
+        }
+
+        let (width, height) = self.gl.drawable_size();
+        //without targets the effects are skipped and the scene is drawn straight to the canvas
+        if self.post_process.needs_targets(width, height) {
+            match (RenderTarget::new(self, width, height, TextureFilter::Linear), RenderTarget::new(self, width, height, TextureFilter::Linear)) {
+                (Ok(a), Ok(b)) => self.post_process.set_targets([a, b]),
+                (Err(e), _) | (_, Err(e)) => log_str(&format!("Post processing skipped: {}", e))
+            }
+        }
+
+        //with post processing the scene is drawn offscreen for the effects to read
+        let scene = self.post_process.scene_target().map(|x| {
+            x.bind();
+            x.texture()
+        });
+        if scene.is_none() {
+            self.gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+            self.gl.viewport(0, 0, width as i32, height as i32);
+        }
+        self.clear(self.clear_color);
+
+        if let Some(camera) = &mut self.camera_2d {
+            camera.resize(width, height);
+        }
+        let (view, projection) = self.view_projection();
+        let global_uniforms = calc_global_uniforms(view, projection);
+        self.draw_layers(&global_uniforms, scene.as_ref());
+        self.post_process.apply(self.gl.as_ref(), &self.texture_batcher, &global_uniforms, width, height);
+    }
+
+    fn clear(&self, color:Vector4<f32>) {
+        self.gl.clear_color(color.x, color.y, color.z, color.w);
         self.gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+    }
+
+    //visible layers in order with the uniforms they are drawn with, from their own camera if they have one
+    fn visible_layers(&self, global_uniforms:&UniformRoleMap) -> Vec<(LayerId,UniformRoleMap)> {
+        self.layers.iter().enumerate().filter(|(_, x)| x.visible).map(|(i, layer)| {
+            let mut layer_uniforms = global_uniforms.clone();
+            if let Some(camera) = &layer.camera {
+                layer_uniforms.insert(UniformRole::View, UniformData::Matrix4(camera.calc_matrix()));
+            }
+            layer_uniforms.insert(UniformRole::Opacity, UniformData::Float(layer.opacity));
+            (LayerId(i), layer_uniforms)
+        }).collect()
+    }
+
+    //visible layers over each other. target is the texture being drawn into, chunks sampling it are skipped
+    fn draw_layers(&mut self, global_uniforms:&UniformRoleMap, target:Option<&MappedTexture>) {
+        for (i, (layer, layer_uniforms)) in self.visible_layers(global_uniforms).into_iter().enumerate() {
+            if i > 0 {
+                self.gl.clear(WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+            }
+            self.draw_batchers(layer, &layer_uniforms, target);
+        }
+    }
+
+    //render object drawn at x, y in pixels from the top left of the canvas, None over the background.
+    //The visible layers are drawn again into an integer target with every object writing its own id, using
+    //each type's pick program, so strokes and transparent parts of images pick exactly as drawn, see pick.
+    //Within a layer the nearest object wins, translucent ones included
+    pub fn pick(&mut self, x:u32, y:u32) -> Result<Option<PickId>,RenderError> {
+        let (width, height) = self.gl.drawable_size();
+        if x >= width || y >= height { return Ok(None); }
         self.texture_batcher.update();
+        self.release_unused_batchers();
+        self.camera_block_matrices = None;
+        if self.pick_target.as_ref().map_or(true, |x| x.size() != (width, height)) {
+            self.pick_target = None;
+            self.pick_target = Some(PickTarget::new(&self.gl, width, height)?);
+        }
+        self.pick_target.as_ref().unwrap().bind();
+        self.gl.depth_mask(true);
+        BlendMode::Opaque.apply(self.gl.as_ref());
+
+        let (view, projection) = self.view_projection();
+        let global_uniforms = calc_global_uniforms(view, projection);
+        let mut ids = Vec::new();
+        for (i, (layer, layer_uniforms)) in self.visible_layers(&global_uniforms).into_iter().enumerate() {
+            if i > 0 {
+                self.gl.clear(WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+            }
+            self.pick_batchers(layer, &layer_uniforms, &mut ids);
+        }
 
-        self.resize_canvas();
-        self.gl.viewport(0, 0, self.canvas.width() as i32, self.canvas.height() as i32);
+        let value = self.pick_target.as_ref().unwrap().read(x, height - 1 - y);
+        self.gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        self.gl.viewport(0, 0, width as i32, height as i32);
+        Ok(match value {
+            0 => None,
+            x => ids.get(x as usize - 1).copied()
+        })
+    }
 
-        let global_uniforms = self.calc_global_uniforms();
+    //draws the layer with picking programs, ids gets what every id drawn stands for. Types whose picking
+    //program fails to link are left out
+    fn pick_batchers(&mut self, layer:LayerId, global_uniforms:&UniformRoleMap, ids:&mut Vec<PickId>) {
+        if !self.render_order.iter().any(|x| x.layer == layer) { return; }
+        self.upload_camera_block(global_uniforms);
+        for key in self.render_order.iter().filter(|x| x.layer == layer) {
+            let batcher = self.render_batchers.get_mut(key).expect_throw("Expected batcher to exist while picking");
+            if batcher.pick_program.is_none() {
+                match self.shaders.pick_program(&self.gl, &batcher.render_type) {
+                    Ok(program) => {
+                        self.camera_block.bind_program(program.handle());
+                        batcher.pick_program = Some(program);
+                    },
+                    Err(e) => {
+                        log_str(&e.to_string());
+                        continue;
+                    }
+                }
+            }
+            batcher.pick(&self.texture_batcher, global_uniforms, ids);
+        }
+    }
 
-        for data in self.render_batchers.values_mut() {
-            data.render(&self.texture_batcher,&global_uniforms);
+    //opaque types are drawn first and write depth, then the translucent chunks of every type are drawn over them
+    //from back to front, without writing depth so translucent objects never hide each other.
+    //A lower z index is always drawn first, depth only orders chunks with the same one
+    fn draw_batchers(&mut self, layer:LayerId, global_uniforms:&UniformRoleMap, target:Option<&MappedTexture>) {
+        //empty layers leave the camera block alone, their camera may differ from the one being drawn
+        if !self.render_order.iter().any(|x| x.layer == layer) { return; }
+        self.upload_camera_block(global_uniforms);
+        self.gl.depth_mask(true);
+        BlendMode::Opaque.apply_with_opacity(self.gl.as_ref(), self.layers[layer.0].opacity);
+        let mut translucent = Vec::new();
+        for key in self.render_order.iter().filter(|x| x.layer == layer) {
+            let data = self.render_batchers.get_mut(key).expect_throw("Expected batcher to exist while rendering");
+            if data.render_type.blend_mode.is_opaque() {
+                data.render(&self.texture_batcher, global_uniforms, target);
+            } else {
+                translucent.extend(data.sort(global_uniforms).into_iter().map(|(chunk, depth)| (depth, *key, chunk)));
+            }
         }
+
+        //farthest first, the sort is stable so equal depths keep the order their batchers were added in
+        translucent.sort_by(|a, b| a.1.z_index.cmp(&b.1.z_index).then(b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal)));
+        self.gl.depth_mask(false);
+        let mut bound = None;
+        for (_, id, chunk) in translucent {
+            let data = self.render_batchers.get_mut(&id).expect_throw("Expected batcher to exist while rendering");
+            if bound != Some(id) {
+                data.bind();
+                bound = Some(id);
+            }
+            data.render_chunk(chunk, &self.texture_batcher, global_uniforms, target);
+        }
+        //clearing the depth buffer is masked too
+        self.gl.depth_mask(true);
+    }
+
+    //new layers are drawn over every existing one
+    pub fn add_layer(&mut self, layer:Layer) -> LayerId {
+        self.layers.push(layer);
+        LayerId(self.layers.len() - 1)
+    }
+
+    //None for layers of another renderer
+    pub fn layer(&self, id:LayerId) -> Option<&Layer> {
+        self.layers.get(id.0)
+    }
+
+    //for toggling visibility, opacity and the camera override
+    pub fn layer_mut(&mut self, id:LayerId) -> Option<&mut Layer> {
+        self.layers.get_mut(id.0)
+    }
+
+    pub fn add_pass(&mut self, pass:RenderPass) {
+        self.passes.push(pass);
+    }
+
+    //passes can be reordered, toggled or removed through this
+    pub fn passes_mut(&mut self) -> &mut Vec<RenderPass> {
+        &mut self.passes
+    }
+
+    //registers a full screen effect, fragment_shader reads the previous stage from post_process::EFFECT_SOURCE at vUv
+    pub fn register_effect(&mut self, name:&str, fragment_shader:String, uniform_attribs:Vec<UniformAttrib>) -> Rc<RenderType> {
+        self.shaders.register(name, post_process::effect_type(fragment_shader, uniform_attribs))
+    }
+
+    //appends a registered effect to the end of the post processing chain
+    pub fn add_effect(&mut self, name:&str) -> Result<&mut PostEffect,RenderError> {
+        let render_type = self.render_type(name).ok_or_else(|| RenderError::UnknownRenderType(name.to_owned()))?;
+        let program = self.program_or_fallback(&render_type)?;
+        let effect = PostEffect::new(&self.gl, render_type, program);
+        let effects = self.post_process.effects_mut();
+        effects.push(effect);
+        Ok(effects.last_mut().unwrap())
+    }
+
+    pub fn post_process(&self) -> &PostProcess {
+        &self.post_process
+    }
+
+    pub fn post_process_mut(&mut self) -> &mut PostProcess {
+        &mut self.post_process
+    }
+
+    //closes every gap removals left in render chunks
+    pub fn compact(&mut self) {
+        self.release_unused_batchers();
+        for batcher in self.render_batchers.values_mut() {
+            batcher.compact(true);
+        }
+    }
+
+    //batchers without any render objects left are dropped, which deletes their program and buffers
+    fn release_unused_batchers(&mut self) {
+        let batchers = &mut self.render_batchers;
+        self.render_order.retain(|id| {
+            let batcher = batchers.get_mut(id).expect_throw("Expected batcher to exist while releasing");
+            batcher.sweep();
+            if !batcher.is_empty() { return true; }
+            batchers.remove(id);
+            false
+        });
+    }
+
+    pub fn backend(&self) -> &Backend {
+        &self.gl
+    }
+
+    pub fn shaders(&self) -> &ShaderRegistry {
+        &self.shaders
+    }
+
+    pub fn shaders_mut(&mut self) -> &mut ShaderRegistry {
+        &mut self.shaders
+    }
+
+    pub fn register_render_type(&mut self, name:&str, render_type:RenderType) -> Rc<RenderType> {
+        self.shaders.register(name, render_type)
+    }
+
+    //recompiles a registered render type with new sources while keeping its render objects, for iterating on shaders.
+    //If the new sources fail to compile or don't match the type's attributes the old program keeps drawing
+    pub fn reload_render_type(&mut self, name:&str, vertex_shader:String, fragment_shader:String) -> Result<(),RenderError> {
+        let render_type = self.render_type(name).ok_or_else(|| RenderError::UnknownRenderType(name.to_owned()))?;
+        let program = self.shaders.reload(&self.gl, &render_type, vertex_shader, fragment_shader)?;
+        self.camera_block.bind_program(program.handle());
+        self.post_process.set_program(self.gl.as_ref(), render_type.id(), &program);
+        for batcher in self.render_batchers.values_mut().filter(|x| x.key.render_type == render_type.id()) {
+            batcher.set_program(program.clone());
+        }
+        Ok(())
+    }
+
+    pub fn render_type(&self, name:&str) -> Option<Rc<RenderType>> {
+        self.shaders.get(name)
     }
 
     pub fn camera_mut(&mut self) -> &mut Camera {
         &mut self.camera
     }
 
-    fn calc_global_uniforms(&self) -> UniformRoleMap {
-        let mut result = UniformRoleMap::new();
-        result.insert(UniformRole::Projection, UniformData::Matrix4(self.projection.calc_matrix()));
-        result.insert(UniformRole::View, UniformData::Matrix4(self.camera.calc_matrix()));
-        result
+    //draws from an orthographic 2D camera instead of the perspective one, None goes back to it
+    pub fn set_camera_2d(&mut self, camera:Option<Camera2D>) {
+        self.camera_2d = camera;
+    }
+
+    pub fn camera_2d(&self) -> Option<&Camera2D> {
+        self.camera_2d.as_ref()
+    }
+
+    pub fn camera_2d_mut(&mut self) -> Option<&mut Camera2D> {
+        self.camera_2d.as_mut()
+    }
+
+    //view and projection the canvas is drawn with, from the 2D camera while one is set
+    fn view_projection(&self) -> (Matrix4<f32>, Matrix4<f32>) {
+        match &self.camera_2d {
+            Some(camera) => (camera.calc_view(), camera.calc_projection()),
+            None => (self.camera.calc_matrix(), self.projection.calc_matrix())
+        }
+    }
+
+    //screen positions are in normalized device coordinates like InputCollector::mouse_pos, -1..1 with +y up.
+    //The ray starts on the near plane and runs through every world point drawn at that position
+    pub fn screen_ray(&self, screen:Vector2<f32>) -> Ray {
+        let (view, projection) = self.view_projection();
+        let inverse = (projection * view).invert().expect_throw("Expected view projection to be invertible");
+        let unproject = |z:f32| {
+            let p = inverse * Vector4::new(screen.x, screen.y, z, 1.0);
+            Point3::from_vec(p.truncate() / p.w)
+        };
+        let near = unproject(-1.0);
+        Ray::new(near, unproject(1.0) - near)
+    }
+
+    //world point under a screen position on the plane at height z, board content sits at 0.
+    //None if the camera looks along the plane or away from it
+    pub fn screen_to_world(&self, screen:Vector2<f32>, z:f32) -> Option<Point3<f32>> {
+        self.screen_ray(screen).intersect_z(z)
+    }
+
+    //None for points behind a perspective camera, points outside the view land outside -1..1
+    pub fn world_to_screen(&self, world:Point3<f32>) -> Option<Vector2<f32>> {
+        let (view, projection) = self.view_projection();
+        let clip = projection * view * world.to_homogeneous();
+        match clip.w > 0.0 {
+            true => Some(Vector2::new(clip.x / clip.w, clip.y / clip.w)),
+            false => None
+        }
+    }
+
+    //projection and view go in with one write, again only when a pass or layer camera changes them
+    fn upload_camera_block(&mut self, global_uniforms:&UniformRoleMap) {
+        let matrices = match (global_uniforms.get(&UniformRole::Projection), global_uniforms.get(&UniformRole::View)) {
+            (Some(UniformData::Matrix4(projection)), Some(UniformData::Matrix4(view))) => (*projection, *view),
+            _ => return
+        };
+        if self.camera_block_matrices == Some(matrices) { return; }
+        debug_assert_eq!(CAMERA_VIEW_OFFSET, CAMERA_PROJECTION_OFFSET + 64, "Expected view to follow projection in the camera block");
+        let (projection, view) = matrices;
+        let data:Vec<f32> = AsRef::<[f32; 16]>::as_ref(&projection).iter().chain(AsRef::<[f32; 16]>::as_ref(&view).iter()).copied().collect();
+        self.camera_block.write_floats(CAMERA_PROJECTION_OFFSET, &data);
+        self.camera_block_matrices = Some(matrices);
     }
 
     pub fn log_buffer_data(&self) {
@@ -176,32 +555,101 @@ impl Renderer {
     }
 }
 
+fn calc_global_uniforms(view:Matrix4<f32>, projection:Matrix4<f32>) -> UniformRoleMap {
+    let mut result = UniformRoleMap::new();
+    result.insert(UniformRole::Projection, UniformData::Matrix4(projection));
+    result.insert(UniformRole::View, UniformData::Matrix4(view));
+    result.insert(UniformRole::Opacity, UniformData::Float(1.0));
+    result
+}
+
+//what the type's vertex shader multiplies positions by, types without a view or projection draw in clip space
+fn clip_transform(render_type:&RenderType, global_uniforms:&UniformRoleMap) -> Matrix4<f32> {
+    let camera_block = render_type.uses_camera_block();
+    let matrix = |role:UniformRole| match global_uniforms.get(&role) {
+        Some(UniformData::Matrix4(m)) if camera_block || render_type.uniform_attribs.iter().any(|x| x.role == role) => *m,
+        _ => Matrix4::identity()
+    };
+    matrix(UniformRole::Projection) * matrix(UniformRole::View)
+}
+
+//larger is farther from the camera
+fn ndc_depth(transform:&Matrix4<f32>, point:Point3<f32>) -> f32 {
+    let clip = transform * point.to_homogeneous();
+    clip.z / clip.w
+}
+
+fn culling_bounds(object:&RenderObject) -> Option<Aabb> {
+    match object.type_id.culled {
+        true => object.bounds(),
+        false => None
+    }
+}
+
+//allocations with their ids grouped by the chunk they are in, so each chunk doesn't have to search all of them
+fn chunk_allocations(mapped:&IndexMap<RenderChunkIndex>) -> HashMap<usize,Vec<(usize,&RenderChunkIndex)>> {
+    let mut result:HashMap<usize,Vec<(usize,&RenderChunkIndex)>> = HashMap::new();
+    for (id, x) in mapped.iter() {
+        result.entry(x.chunk).or_default().push((*id, x));
+    }
+    result
+}
+
+fn chunk_allocations_mut(mapped:&mut IndexMap<RenderChunkIndex>) -> HashMap<usize,Vec<&mut RenderChunkIndex>> {
+    let mut result:HashMap<usize,Vec<&mut RenderChunkIndex>> = HashMap::new();
+    for x in mapped.values_mut() {
+        result.entry(x.chunk).or_default().push(x);
+    }
+    result
+}
+
+//objects are batched by type within each layer and z index
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct BatcherKey {
+    render_type:RenderTypeId,
+    layer:LayerId,
+    z_index:i32
+}
+
+impl BatcherKey {
+    fn of(object:&RenderObject) -> Self {
+        Self { render_type:object.type_id.id(), layer:object.layer, z_index:object.z_index }
+    }
+}
+
 #[derive(Debug)]
 pub struct RenderBatcher {
+    key:BatcherKey,
+    render_type:Rc<RenderType>,
     chunks:IndexMap<RenderChunk>,
     mapped:IndexMap<RenderChunkIndex>,
-    gl:WebGl2RenderingContext,
-    program:WebGlProgram,
+    gl:Backend,
+    program:Rc<Program>,
+    //linked by the first pick, see pick::fragment_shader
+    pick_program:Option<Rc<Program>>,
     remove_cache:Rc<RefCell<Vec<usize>>>
 }
 
 impl RenderBatcher {
 
     fn id_mapped_internal(&mut self, render_type:Rc<RenderType>, mapped:RenderChunkIndex) -> RenderObjectAllocation {
-        RenderObjectAllocation { render_type: render_type, id: self.mapped.push(mapped), remove_cache:Rc::clone(&self.remove_cache)}
+        RenderObjectAllocation { render_type: render_type, key:self.key, id: self.mapped.push(mapped), remove_cache:Rc::clone(&self.remove_cache)}
     }
-    fn map_render_object_into_new(gl:&WebGl2RenderingContext, object:&mut RenderObject) -> RenderBatcher {
+    fn map_render_object_into_new(gl:&Backend, key:BatcherKey, program:Rc<Program>, object:&mut RenderObject) -> Result<RenderBatcher,RenderError> {
         let mut result = Self {
+            key:key,
+            render_type:object.type_id.clone(),
             gl:gl.clone(),
             chunks:IndexMap::new(),
-            program:object.type_id.setup_program(gl),
+            program:program,
+            pick_program:None,
             mapped:IndexMap::new(),
             remove_cache:Rc::new(RefCell::new(Vec::new())),
         };
 
         let type_id = object.type_id.clone();
 
-        let (chunk, mut chunk_index) = RenderChunk::map_render_object_into_new(gl, &object, &result.program);
+        let (chunk, mut chunk_index) = RenderChunk::map_render_object_into_new(gl, &object, result.program.handle())?;
 
         let chunk_id = result.chunks.push(chunk);
         chunk_index.chunk = chunk_id;
@@ -209,41 +657,58 @@ impl RenderBatcher {
         let mapped = result.id_mapped_internal(type_id, chunk_index);
 
         object.allocation = Some(mapped);
-        result
+        Ok(result)
     }
 
-    fn map_render_object(&mut self, object:&mut RenderObject) {
+    fn map_render_object(&mut self, object:&mut RenderObject) -> Result<(),RenderError> {
         let gl = self.gl.clone();
         self.sweep();
 
         let type_id = object.type_id.clone();
 
         for (i, chunk) in self.chunks.iter_mut() {
-            if let Some(mut mapped) = chunk.map_render_object(&gl, &object) {
+            if let Some(mut mapped) = chunk.map_render_object(gl.as_ref(), &object) {
                 mapped.chunk = i.clone();
                 object.allocation = Some(self.id_mapped_internal(type_id, mapped));
-                return;
+                return Ok(());
+            }
+        };
+
+        //only grow once no chunk has space left, so chunks stay as small as possible
+        for (i, chunk) in self.chunks.iter_mut() {
+            if !chunk.grow_to_fit(&gl, self.program.handle(), &object) { continue; }
+            //the grown space can still be past what the object's indicies can address
+            if let Some(mut mapped) = chunk.map_render_object(gl.as_ref(), &object) {
+                mapped.chunk = i.clone();
+                object.allocation = Some(self.id_mapped_internal(type_id, mapped));
+                return Ok(());
             }
         };
 
-        let (chunk, mut mapped) = RenderChunk::map_render_object_into_new(&gl, object, &self.program);
+        let (chunk, mut mapped) = RenderChunk::map_render_object_into_new(&gl, object, self.program.handle())?;
         mapped.chunk = self.chunks.push(chunk);
 
         object.allocation = Some(self.id_mapped_internal(type_id, mapped));
+        Ok(())
     }
 
-    fn update(&mut self, object:&mut RenderObject) {
+    fn update(&mut self, object:&mut RenderObject) -> Result<(),RenderError> {
         //at this point it is guaranteed to be Some by the Renderer.
         let id = object.allocation.as_ref().unwrap().id;
         let chunk_index = &self.mapped[id];
         let gl = &self.gl.clone();
-        if self.chunks[chunk_index.chunk].update(gl, &object, &chunk_index).is_ok() { return; }
+        if self.chunks[chunk_index.chunk].update(gl.as_ref(), &object, &chunk_index).is_ok() {
+            self.mapped[id].center = object.center();
+            self.mapped[id].bounds = culling_bounds(object);
+            return Ok(());
+        }
 
         //it is removed before re adding so that the old space(which will be overwritten anyways) is freed.
         self.sweep();
         self.remove(id);
         //it is safe to drop the old one because it is safe to call remove on the same mapped id twice.
-        self.map_render_object(object);
+        object.allocation = None;
+        self.map_render_object(object)
     }
 
     fn remove(&mut self, id:usize) {
@@ -252,8 +717,23 @@ impl RenderBatcher {
             Some(x) => x,
             None => return
         };
-        self.chunks[chunk_index.chunk].remove(&self.gl, chunk_index);
+        let chunk_id = chunk_index.chunk;
+        let chunk = &mut self.chunks[chunk_id];
+        chunk.remove(self.gl.as_ref(), chunk_index);
+        //dropping the chunk deletes its buffers
+        if chunk.allocations == 0 { self.chunks.remove(chunk_id); }
+    }
 
+    fn is_empty(&self) -> bool {
+        self.mapped.is_empty()
+    }
+
+    fn set_program(&mut self, program:Rc<Program>) {
+        for chunk in self.chunks.values_mut() {
+            chunk.rebind(self.gl.as_ref(), program.handle());
+        }
+        self.program = program;
+        self.pick_program = None;
     }
 
     fn sweep(&mut self) {
@@ -266,18 +746,96 @@ impl RenderBatcher {
         borrow.clear();
     }
 
-    fn render(&mut self, texture_batcher:&TextureBatcher, global_uniforms:&UniformRoleMap) {
+    //moves live allocations to close the gaps in chunks.
+    //unless forced only chunks fragmented past COMPACTION_THRESHOLD are moved.
+    fn compact(&mut self, force:bool) {
         self.sweep();
-        let gl = &self.gl;
-        gl.use_program(Some(&self.program));
-        for chunk in self.chunks.values_mut() {
-            chunk.render(&self.gl, texture_batcher,&self.program, global_uniforms);
+        let gl = self.gl.clone();
+        if !force && !self.chunks.values().any(|x| x.fragmented()) { return; }
+        let mut allocations = chunk_allocations_mut(&mut self.mapped);
+        for (id, chunk) in self.chunks.iter_mut() {
+            if !force && !chunk.fragmented() { continue; }
+            chunk.compact(&gl, self.program.handle(), &mut allocations.remove(id).unwrap_or_default());
+        }
+    }
+
+    //chunks with nothing in view are skipped and ones partly in view only draw the objects that are
+    fn render(&mut self, texture_batcher:&TextureBatcher, global_uniforms:&UniformRoleMap, target:Option<&MappedTexture>) {
+        self.compact(false);
+        self.gl.use_program(Some(self.program.handle()));
+        let transform = clip_transform(&self.render_type, global_uniforms);
+        let mut allocations = chunk_allocations(&self.mapped);
+        for (id, chunk) in self.chunks.iter_mut() {
+            if target.map_or(false, |x| chunk.uniforms.samples(x)) { continue; }
+            let ranges = chunk.visible_ranges(&transform, allocations.remove(id).unwrap_or_default().into_iter().map(|x| x.1).collect());
+            if ranges.as_ref().map_or(false, |x| x.is_empty()) { continue; }
+            chunk.render(self.gl.as_ref(), texture_batcher, self.program.handle(), global_uniforms, ranges.as_deref());
         }
     }
 
+    fn pick(&mut self, texture_batcher:&TextureBatcher, global_uniforms:&UniformRoleMap, ids:&mut Vec<PickId>) {
+        self.compact(false);
+        let program = self.pick_program.clone().expect_throw("Expected pick program to be linked before picking");
+        self.gl.use_program(Some(program.handle()));
+        let location = self.gl.get_uniform_location(program.handle(), pick::PICK_ID);
+        let gl = self.gl.clone();
+        let transform = clip_transform(&self.render_type, global_uniforms);
+        let mut chunk_allocations = chunk_allocations(&self.mapped);
+        for (id, chunk) in self.chunks.iter_mut() {
+            let mut allocations = chunk_allocations.remove(id).unwrap_or_default();
+            if allocations.iter().all(|(_, x)| !x.visible(&transform)) { continue; }
+            //instances are told apart by their place in the buffer, so they are all drawn
+            if !chunk.gl_buffers.is_instanced() {
+                allocations.retain(|(_, x)| x.visible(&transform));
+            }
+            allocations.sort_by_key(|(_, x)| x.v_slice.start);
+            chunk.pick(gl.as_ref(), texture_batcher, program.handle(), global_uniforms, location, &allocations, self.key, ids);
+        }
+    }
+
+    fn bind(&self) {
+        self.gl.use_program(Some(self.program.handle()));
+        self.render_type.blend_mode.apply(self.gl.as_ref());
+    }
+
+    fn render_chunk(&mut self, chunk:usize, texture_batcher:&TextureBatcher, global_uniforms:&UniformRoleMap, target:Option<&MappedTexture>) {
+        let chunk = &mut self.chunks[chunk];
+        if target.map_or(false, |x| chunk.uniforms.samples(x)) { return; }
+        chunk.render(self.gl.as_ref(), texture_batcher, self.program.handle(), global_uniforms, None);
+    }
+
+    //orders the objects in view in each chunk from back to front by their centers and returns the chunks with any
+    //with the average depth of their objects. Objects without a position are drawn at their chunk's depth
+    fn sort(&mut self, global_uniforms:&UniformRoleMap) -> Vec<(usize,f32)> {
+        self.compact(false);
+        let transform = clip_transform(&self.render_type, global_uniforms);
+        let gl = self.gl.clone();
+        let mut result = Vec::new();
+        let mut chunk_allocations = chunk_allocations(&self.mapped);
+        for (id, chunk) in self.chunks.iter_mut() {
+            let instanced = chunk.gl_buffers.is_instanced();
+            let allocations:Vec<(Option<f32>,&RenderChunkIndex)> = chunk_allocations.remove(id).unwrap_or_default().into_iter()
+                .map(|x| x.1)
+                .filter(|x| instanced || x.visible(&transform))
+                .map(|x| (x.center.map(|c| ndc_depth(&transform, c)), x))
+                .collect();
+            if allocations.is_empty() || (instanced && allocations.iter().all(|(_, x)| !x.visible(&transform))) { continue; }
+            let known:Vec<f32> = allocations.iter().filter_map(|x| x.0).collect();
+            let depth = match known.len() {
+                0 => 0.0,
+                n => known.iter().sum::<f32>() / n as f32
+            };
+            let mut order:Vec<(f32,&RenderChunkIndex)> = allocations.into_iter().map(|(d, x)| (d.unwrap_or(depth), x)).collect();
+            order.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+            chunk.sort(gl.as_ref(), transform, order.into_iter().map(|x| x.1));
+            result.push((*id, depth));
+        }
+        result
+    }
+
     fn log_buffers(&self) {
-        for (idx,chunk) in self.chunks.iter() {
-            chunk.gl_buffers.log_data(&self.gl, chunk.verticies_len as u32, chunk.indicies_len as u32);
+        for chunk in self.chunks.values() {
+            chunk.gl_buffers.log_data(self.gl.as_ref(), chunk.verticies_len as u32, chunk.indicies_len as u32);
         }
     }
 }
@@ -288,10 +846,17 @@ pub struct RenderChunk {
     uniforms:UniformBlock,
     verticies_free_areas:Vec<SlicePointer>,
     indicies_free_areas:Vec<SlicePointer>,
+    //copy of the index buffer, indicies have to be rewritten when their verticies move during compaction
+    indicies:Vec<u32>,
+    //number of render objects mapped into the chunk, it is dropped once this reaches 0
+    allocations:usize,
     verticies_len:usize,
     indicies_len:usize,
     indicies_count:usize,
-    verticies_count:usize
+    verticies_count:usize,
+    //set while the index buffer holds a back to front order instead of the allocations' layout,
+    //with the transform it was sorted for and how many indicies it has. Any change to the chunk unsorts it first
+    draw_order:Option<(Matrix4<f32>,usize)>
 }
 
 impl Debug for RenderChunk {
@@ -302,6 +867,7 @@ impl Debug for RenderChunk {
             .field("uniforms", &self.uniforms)
             .field("verticies_free_areas", &self.verticies_free_areas)
             .field("indicies_free_areas", &self.indicies_free_areas)
+            .field("allocations", &self.allocations)
             .field("verticies_len", &self.verticies_len)
             .field("indicies_len", &self.indicies_len)
             .field("vertics_count", &self.verticies_count)
@@ -312,18 +878,27 @@ impl Debug for RenderChunk {
 
 
 impl RenderChunk {
-    fn map_render_object_into_new(gl:&WebGl2RenderingContext, object:&RenderObject, program:&WebGlProgram) -> (Self,RenderChunkIndex) {
+    fn map_render_object_into_new(gl:&Backend, object:&RenderObject, program:ProgramHandle) -> Result<(Self,RenderChunkIndex),RenderError> {
         let verticies = &object.verticies;
         let verticies_len = verticies.len();
-        let verticies_chunk_size = usize::max(verticies_len,object.type_id.verticies_chunk_min_size*object.type_id.vertex_size);
+        let stride = object.type_id.stride();
+        //chunks never hold more verticies than their index format can address
+        let max_verticies_size = object.type_id.max_chunk_verticies().saturating_mul(stride);
+        if verticies_len > max_verticies_size {
+            return Err(RenderError::IndexOverflow { verticies:verticies_len/stride, index_format:object.type_id.index_format });
+        }
+        object.type_id.index_format.check(&object.indicies, 0)?;
+        let verticies_chunk_size = usize::min(usize::max(verticies_len,object.type_id.verticies_chunk_min_size*stride), max_verticies_size);
 
         let indicies = &object.indicies;
         let indicies_len = indicies.len();
         let indicied_chunk_size = usize::max(indicies_len,object.type_id.indicies_chunk_min_size);
 
         let gl_buffers = object.type_id.setup_arrs(gl, verticies, indicies, program, verticies_chunk_size, indicied_chunk_size);
+        let mut indicies = indicies.clone();
+        indicies.resize(indicied_chunk_size, 0);
 
-        return (
+        Ok((
             RenderChunk {
                 render_type: object.type_id.clone(),
                 gl_buffers: gl_buffers,
@@ -336,21 +911,26 @@ impl RenderChunk {
                     start:indicies_len,
                     size:indicied_chunk_size-indicies_len
                 }],
+                indicies:indicies,
+                allocations:1,
                 indicies_count:indicies_len,
-                verticies_count:verticies_len/object.type_id.vertex_size,
+                verticies_count:verticies_len/stride,
                 indicies_len:indicied_chunk_size,
-                verticies_len:verticies_chunk_size
+                verticies_len:verticies_chunk_size,
+                draw_order:None
             },
 
             RenderChunkIndex {
                 chunk:0,
                 v_slice:SlicePointer { start: 0, size: verticies_len },
-                i_slice:SlicePointer { start: 0, size: indicies_len }
+                i_slice:SlicePointer { start: 0, size: indicies_len },
+                center:object.center(),
+                bounds:culling_bounds(object)
             }
-        );
+        ))
     }
 
-    fn map_render_object(&mut self, gl:&WebGl2RenderingContext, object:&RenderObject) -> Option<RenderChunkIndex> {
+    fn map_render_object(&mut self, gl:&dyn GraphicsBackend, object:&RenderObject) -> Option<RenderChunkIndex> {
         if !self.uniforms.batchable_with(&object.uniforms) { return None; } 
         let verticies = &object.verticies;
         let verticies_len = verticies.len();
@@ -358,41 +938,150 @@ impl RenderChunk {
         let indicies = &object.indicies;
         let indicies_len = indicies.len();
 
-        if let (Some(v_slice),Some(i_slice)) = 
-            (
-                self.verticies_free_areas.iter_mut().find(|s| s.size >= verticies_len),
-                self.indicies_free_areas.iter_mut().find(|s| s.size >= indicies_len)
-            )
-        {
+        let v = self.verticies_free_areas.iter().position(|s| s.size >= verticies_len)?;
+        let i = self.indicies_free_areas.iter().position(|s| s.size >= indicies_len)?;
+
+        let result = RenderChunkIndex { 
+            chunk:0,
+            v_slice: SlicePointer { 
+                start: self.verticies_free_areas[v].start, 
+                size: verticies_len 
+            }, 
+            i_slice: SlicePointer { 
+                start: self.indicies_free_areas[i].start, 
+                size: indicies_len 
+            },
+            center:object.center(),
+            bounds:culling_bounds(object)
+        };
 
-            let result = RenderChunkIndex { 
-                chunk:0,
-                v_slice: SlicePointer { 
-                    start: v_slice.start, 
-                    size: verticies_len 
-                }, 
-                i_slice: SlicePointer { 
-                    start: i_slice.start, 
-                    size: indicies_len 
-                }
-            };
+        //objects whose indicies can't address that far into the chunk are left for another one
+        self.upload_at_slice(gl, object, &result).ok()?;
 
-            v_slice.start += verticies_len;
-            v_slice.size -= verticies_len;
-            i_slice.start += indicies_len;
-            i_slice.size -= indicies_len;
-            
-            self.verticies_count += verticies_len/object.type_id.vertex_size;
-            self.indicies_count += indicies_len;
+        let v_slice = &mut self.verticies_free_areas[v];
+        v_slice.start += verticies_len;
+        v_slice.size -= verticies_len;
+        let i_slice = &mut self.indicies_free_areas[i];
+        i_slice.start += indicies_len;
+        i_slice.size -= indicies_len;
+        
+        self.verticies_count += verticies_len/object.type_id.stride();
+        self.indicies_count += indicies_len;
+        self.allocations += 1;
 
-            self.upload_at_slice(gl, object, &result);
+        Some(result)
+    }
 
-            return Some(result);
+    //reallocates the buffers with room for the object, up to the render type's max sizes, keeping existing data
+    fn grow_to_fit(&mut self, gl:&Backend, program:ProgramHandle, object:&RenderObject) -> bool {
+        if !self.uniforms.batchable_with(&object.uniforms) { return false; }
+        let render_type = self.render_type.clone();
+
+        let verticies_len = match grown_size(
+            &self.verticies_free_areas,
+            self.verticies_len,
+            object.verticies.len(),
+            render_type.verticies_chunk_grow_factor,
+            usize::min(render_type.verticies_chunk_max_size, render_type.max_chunk_verticies())*render_type.stride(),
+            render_type.stride()
+        ) {
+            Some(x) => x,
+            None => return false
+        };
+        let indicies_len = match grown_size(
+            &self.indicies_free_areas,
+            self.indicies_len,
+            object.indicies.len(),
+            render_type.indicies_chunk_grow_factor,
+            render_type.indicies_chunk_max_size,
+            1
+        ) {
+            Some(x) => x,
+            None => return false
+        };
+
+        let gl_buffers = render_type.setup_arrs(gl, &[], &[], program, verticies_len, indicies_len);
+        self.unsort(gl.as_ref());
+        self.gl_buffers.copy_to(gl.as_ref(), &gl_buffers, self.verticies_len, self.indicies_len);
+        self.gl_buffers = gl_buffers;
+        self.indicies.resize(indicies_len, 0);
+
+        extend_free_areas(&mut self.verticies_free_areas, self.verticies_len, verticies_len);
+        extend_free_areas(&mut self.indicies_free_areas, self.indicies_len, indicies_len);
+        self.verticies_len = verticies_len;
+        self.indicies_len = indicies_len;
+        true
+    }
+
+    //uniform locations are cached per program, so they are looked up again on the next render
+    fn rebind(&mut self, gl:&dyn GraphicsBackend, program:ProgramHandle) {
+        self.gl_buffers.rebind(gl, &self.render_type, program);
+        self.uniforms.clear_cached_locations();
+    }
+
+    //holes are free areas before the end of the used range, which are still drawn.
+    //Instances in holes keep their old data instead of being blanked, so instanced chunks compact on any hole
+    fn fragmented(&self) -> bool {
+        let threshold = match self.gl_buffers.is_instanced() {
+            false => COMPACTION_THRESHOLD,
+            true => 0.0
+        };
+        let holes = |areas:&[SlicePointer], len:usize| {
+            let tail = areas.iter().find(|x| x.start + x.size == len).map_or(0, |x| x.size);
+            let holes:usize = areas.iter().map(|x| x.size).sum::<usize>() - tail;
+            holes > 0 && holes as f32 >= (len - tail) as f32 * threshold
+        };
+        holes(&self.verticies_free_areas, self.verticies_len) || holes(&self.indicies_free_areas, self.indicies_len)
+    }
+
+    //packs the allocations at the start of new buffers in vertex order. Verticies are copied on the gpu,
+    //indicies are rewritten from the cpu copy since they point at absolute verticies.
+    fn compact(&mut self, gl:&Backend, program:ProgramHandle, allocations:&mut Vec<&mut RenderChunkIndex>) {
+        allocations.sort_by_key(|x| x.v_slice.start);
+        let vertex_size = self.render_type.stride();
+        let gl_buffers = self.render_type.setup_arrs(gl, &[], &[], program, self.verticies_len, self.indicies_len);
+        let mut indicies = vec![0u32; self.indicies_len];
+
+        let mut v_start = 0;
+        let mut i_start = 0;
+        //adjacent allocations are copied together
+        let mut run:Option<(usize,usize,usize)> = None;
+        for mapped in allocations.iter_mut() {
+            let shift = (mapped.v_slice.start - v_start) / vertex_size;
+            for (dst, src) in indicies[i_start..i_start+mapped.i_slice.size].iter_mut().zip(&self.indicies[mapped.i_slice.start..mapped.i_slice.start+mapped.i_slice.size]) {
+                *dst = src - shift as u32;
+            }
+
+            run = match run {
+                Some((src, dst, size)) if src + size == mapped.v_slice.start => Some((src, dst, size + mapped.v_slice.size)),
+                Some((src, dst, size)) => {
+                    self.gl_buffers.copy_verticies_to(gl.as_ref(), &gl_buffers, src, dst, size);
+                    Some((mapped.v_slice.start, v_start, mapped.v_slice.size))
+                },
+                None => Some((mapped.v_slice.start, v_start, mapped.v_slice.size))
+            };
+
+            mapped.v_slice.start = v_start;
+            mapped.i_slice.start = i_start;
+            v_start += mapped.v_slice.size;
+            i_start += mapped.i_slice.size;
+        }
+        if let Some((src, dst, size)) = run {
+            self.gl_buffers.copy_verticies_to(gl.as_ref(), &gl_buffers, src, dst, size);
         }
-        return None;
+        gl_buffers.buffer_sub_data(gl.as_ref(), &[], 0, &indicies[..i_start], 0);
+
+        self.gl_buffers = gl_buffers;
+        self.indicies = indicies;
+        self.draw_order = None;
+        self.verticies_free_areas = vec![SlicePointer { start:v_start, size:self.verticies_len - v_start }];
+        self.indicies_free_areas = vec![SlicePointer { start:i_start, size:self.indicies_len - i_start }];
     }
 
-    fn remove(&mut self, gl:&WebGl2RenderingContext, mapped:RenderChunkIndex) {
+    fn remove(&mut self, gl:&dyn GraphicsBackend, mapped:RenderChunkIndex) {
+        self.allocations -= 1;
+        //the slice is blanked in allocation order
+        self.unsort(gl);
 
         let mut lower: Option<usize> = None;
 
@@ -413,23 +1102,25 @@ impl RenderChunk {
             self.verticies_free_areas.push(mapped.v_slice.clone());
         }
 
-        let mut lower: Option<usize> = None;
+        //instanced objects have no indicies, an empty slice would match the same free area on both sides
+        if mapped.i_slice.size > 0 {
+            let mut lower: Option<usize> = None;
 
-        if let Some((idx,slice)) = self.indicies_free_areas.iter_mut().enumerate().find(|(_a,x)| x.start + x.size == mapped.i_slice.start) {
-            slice.size += mapped.i_slice.size;
-            lower = Some(idx);
-            
-        } 
-        
-        if let Some((idx,slice)) = self.indicies_free_areas.iter_mut().enumerate().find(|(_a,x)| x.start == mapped.i_slice.start + mapped.i_slice.size) {
-            if let Some(lower) = lower {
-                self.indicies_free_areas[lower].size += slice.size;
-                self.indicies_free_areas.remove(idx);
-            } else {
-                slice.start -= mapped.i_slice.size;
+            if let Some((idx,slice)) = self.indicies_free_areas.iter_mut().enumerate().find(|(_a,x)| x.start + x.size == mapped.i_slice.start) {
+                slice.size += mapped.i_slice.size;
+                lower = Some(idx);
+            }
+
+            if let Some((idx,slice)) = self.indicies_free_areas.iter_mut().enumerate().find(|(_a,x)| x.start == mapped.i_slice.start + mapped.i_slice.size) {
+                if let Some(lower) = lower {
+                    self.indicies_free_areas[lower].size += slice.size;
+                    self.indicies_free_areas.remove(idx);
+                } else {
+                    slice.start -= mapped.i_slice.size;
+                }
+            } else if lower.is_none() {
+                self.indicies_free_areas.push(mapped.i_slice.clone());
             }
-        } else if lower.is_none() {
-            self.indicies_free_areas.push(mapped.i_slice.clone());
         }
 
         // //this very heavily relies on the free areas array being in order and valid, if there is any corruption in it this will just make everything worse.
@@ -502,11 +1193,12 @@ impl RenderChunk {
         }
 
         let mut indicies = Vec::new();
-        indicies.resize(mapped.i_slice.size, 0u16);
-        self.gl_buffers.buffer_sub_data(gl, &vec, mapped.v_slice.start, &indicies, mapped.i_slice.start * mem::size_of::<u16>());   
+        indicies.resize(mapped.i_slice.size, 0u32);
+        self.indicies[mapped.i_slice.start..mapped.i_slice.start+mapped.i_slice.size].copy_from_slice(&indicies);
+        self.gl_buffers.buffer_sub_data(gl, &vec, mapped.v_slice.start, &indicies, mapped.i_slice.start);   
     }
 
-    fn update(&mut self, gl:&WebGl2RenderingContext, object:&RenderObject, mapped_chunk_index:&RenderChunkIndex) -> Result<(),()> {
+    fn update(&mut self, gl:&dyn GraphicsBackend, object:&RenderObject, mapped_chunk_index:&RenderChunkIndex) -> Result<(),()> {
         let verticies_len = object.verticies.len();
         let indicies_len = object.indicies.len();
         if !self.uniforms.batchable_with(&object.uniforms) ||
@@ -516,35 +1208,135 @@ impl RenderChunk {
             return Err(()); 
         } 
 
-        self.upload_at_slice(gl, object, mapped_chunk_index);
-        Ok(())
+        self.upload_at_slice(gl, object, mapped_chunk_index).map_err(|_| ())
     }
 
-    fn upload_at_slice(&mut self, gl:&WebGl2RenderingContext, object:&RenderObject, mapped_chunk_index:&RenderChunkIndex) {
+    //Err without uploading anything if the object's indicies don't fit once offset to its verticies
+    fn upload_at_slice(&mut self, gl:&dyn GraphicsBackend, object:&RenderObject, mapped_chunk_index:&RenderChunkIndex) -> Result<(),RenderError> {
         let verticies_len = object.verticies.len();
         let indicies_len = object.indicies.len();
         let v_slice = &mapped_chunk_index.v_slice;
         let i_slice = &mapped_chunk_index.i_slice;
         let verticies = &object.verticies;
-        let mut indicies = object.indicies.clone();
         if verticies_len == mapped_chunk_index.v_slice.size && indicies_len == mapped_chunk_index.i_slice.size {
-            let offset = (v_slice.start/object.type_id.vertex_size) as u16;
-            for x in &mut indicies {
-                *x += offset;
+            let offset = v_slice.start/object.type_id.stride();
+            object.type_id.index_format.check(&object.indicies, offset)?;
+            let indicies:Vec<u32> = object.indicies.iter().map(|x| x + offset as u32).collect();
+
+            //slices are always taken from the chunk's free areas
+            debug_assert!(verticies_len + v_slice.start <= self.verticies_len && indicies_len + i_slice.start <= self.indicies_len, "Render chunk slice is out of bounds: {:#?}", self);
+
+            //the slice is written in allocation order
+            self.unsort(gl);
+            self.indicies[i_slice.start..i_slice.start+indicies_len].copy_from_slice(&indicies);
+
+            self.gl_buffers.buffer_sub_data(gl, verticies, v_slice.start, &indicies, i_slice.start);
+        }
+        Ok(())
+    }
+
+    //writes the allocations' indicies to the start of the index buffer in the given order, leaving the cpu copy
+    //in allocation order. Instanced chunks draw their instances in buffer order
+    fn sort<'a>(&mut self, gl:&dyn GraphicsBackend, transform:Matrix4<f32>, order:impl Iterator<Item = &'a RenderChunkIndex>) {
+        if self.gl_buffers.is_instanced() || self.draw_order.map_or(false, |x| x.0 == transform) { return; }
+        let mut indicies = Vec::with_capacity(self.indicies_count);
+        for mapped in order {
+            indicies.extend_from_slice(&self.indicies[mapped.i_slice.start..mapped.i_slice.start+mapped.i_slice.size]);
+        }
+        self.gl_buffers.buffer_sub_data(gl, &[], 0, &indicies, 0);
+        self.draw_order = Some((transform, indicies.len()));
+    }
+
+    //index ranges covering the chunk's allocations in view, None when all of them are. Runs of allocations in view
+    //are drawn separately, or together with what lies between them once there are more than MAX_CULLED_DRAW_RANGES.
+    //Instanced chunks can only draw instances from the start of the buffer, so they are drawn whole while any is in view
+    fn visible_ranges(&self, transform:&Matrix4<f32>, mut allocations:Vec<&RenderChunkIndex>) -> Option<Vec<Range<usize>>> {
+        let visible = allocations.iter().filter(|x| x.visible(transform)).count();
+        if visible == allocations.len() { return None; }
+        if self.gl_buffers.is_instanced() {
+            return match visible {
+                0 => Some(Vec::new()),
+                _ => None
+            };
+        }
+
+        allocations.sort_by_key(|x| x.i_slice.start);
+        let mut ranges:Vec<Range<usize>> = Vec::new();
+        let mut culled = true;
+        for mapped in allocations {
+            let range = mapped.i_slice.start..mapped.i_slice.start + mapped.i_slice.size;
+            if !mapped.visible(transform) {
+                culled = true;
+                continue;
+            }
+            //free space between allocations holds degenerate triangles, so only culled objects end a run
+            match ranges.last_mut() {
+                Some(last) if !culled => last.end = range.end,
+                _ => ranges.push(range)
             }
+            culled = false;
+        }
 
-            if verticies_len + v_slice.start > self.verticies_len || indicies_len + i_slice.start > self.indicies_len {
-                log_str(&format!("BUFFER OVERFLOW: {:#?}",self));
+        if ranges.len() > MAX_CULLED_DRAW_RANGES {
+            let mut gaps:Vec<(usize,usize)> = ranges.windows(2).enumerate().map(|(i, x)| (x[1].start - x[0].end, i)).collect();
+            gaps.sort_by(|a, b| b.0.cmp(&a.0));
+            let mut splits:Vec<usize> = gaps.into_iter().take(MAX_CULLED_DRAW_RANGES - 1).map(|x| x.1).collect();
+            splits.sort();
+            let mut merged = Vec::with_capacity(MAX_CULLED_DRAW_RANGES);
+            let mut start = ranges[0].start;
+            for i in splits {
+                merged.push(start..ranges[i].end);
+                start = ranges[i + 1].start;
             }
+            merged.push(start..ranges[ranges.len() - 1].end);
+            ranges = merged;
+        }
+        Some(ranges)
+    }
 
-            assert!(!(verticies_len + v_slice.start > self.verticies_len || indicies_len + i_slice.start > self.indicies_len));
+    //puts the allocations' indicies back where they were before sort
+    fn unsort(&mut self, gl:&dyn GraphicsBackend) {
+        if let Some((_, count)) = self.draw_order.take() {
+            self.gl_buffers.buffer_sub_data(gl, &[], 0, &self.indicies[..count], 0);
+        }
+    }
 
-            self.gl_buffers.buffer_sub_data(gl, verticies, v_slice.start, &indicies, i_slice.start * mem::size_of::<u16>());
+    //draws every allocation with the next id, allocations are in buffer order
+    fn pick(&mut self, gl:&dyn GraphicsBackend, texture_batcher:&TextureBatcher, program:ProgramHandle, global_uniforms:&UniformRoleMap, location:Option<UniformLocation>, allocations:&[(usize,&RenderChunkIndex)], key:BatcherKey, ids:&mut Vec<PickId>) {
+        let location = match location {
+            Some(x) => x,
+            None => return
+        };
+        self.unsort(gl);
+        self.uniforms.setup_pick_uniforms_and_textures(gl, texture_batcher, program, global_uniforms);
+        //instances get consecutive ids through pick::PICK_INSTANCE, compaction leaves no holes between them
+        if self.gl_buffers.is_instanced() {
+            gl.uniform1ui(location, ids.len() as u32 + 1);
+            ids.extend(allocations.iter().map(|(id, _)| PickId { key:key, allocation:*id }));
+            self.gl_buffers.pick(gl, &self.render_type, program, allocations.len() as i32);
+            return;
+        }
+        for (id, mapped) in allocations {
+            ids.push(PickId { key:key, allocation:*id });
+            gl.uniform1ui(location, ids.len() as u32);
+            self.gl_buffers.pick_range(gl, &self.render_type, program, mapped.i_slice.start, mapped.i_slice.size as i32);
         }
     }
 
-    fn render(&mut self, gl:&WebGl2RenderingContext, texture_batcher:&TextureBatcher, program:&WebGlProgram, global_uniforms:&UniformRoleMap) {
+    //ranges of indicies to draw instead of the whole used range, see visible_ranges
+    fn render(&mut self, gl:&dyn GraphicsBackend, texture_batcher:&TextureBatcher, program:ProgramHandle, global_uniforms:&UniformRoleMap, ranges:Option<&[Range<usize>]>) {
         self.uniforms.setup_uniforms_and_textures(gl, texture_batcher, program, &global_uniforms);
+        if let Some(ranges) = ranges {
+            self.unsort(gl);
+            for range in ranges {
+                self.gl_buffers.draw_range(gl, range.start, range.len() as i32);
+            }
+            return;
+        }
+        if let Some((_, count)) = self.draw_order {
+            self.gl_buffers.draw(gl, self.render_type.clone(), count as i32);
+            return;
+        }
         let (l,iter) = match self.gl_buffers.is_instanced() {
             false => (self.indicies_len,self.indicies_free_areas.iter()),
             true => (self.verticies_len,self.verticies_free_areas.iter())
@@ -561,13 +1353,39 @@ impl RenderChunk {
                 false => l
             }
         };
+        //instanced chunks draw the used range of the instance buffer, which is in bytes
+        let count = match self.gl_buffers.is_instanced() {
+            false => count,
+            true => count / self.render_type.stride()
+        };
 
         self.gl_buffers.draw(gl, self.render_type.clone(), count as i32);
     }
 }
 
+//length a chunk buffer has to grow to for a free area of needed to exist, rounded to whole units.
+//None if that would pass max_size, so a max below the min size means the chunk can never grow.
+fn grown_size(free_areas:&[SlicePointer], len:usize, needed:usize, grow_factor:f32, max_size:usize, unit:usize) -> Option<usize> {
+    if free_areas.iter().any(|x| x.size >= needed) { return Some(len); }
+    let tail = free_areas.iter().find(|x| x.start + x.size == len).map_or(0, |x| x.size);
+    let required = len + needed - tail;
+    let grown = usize::max(required, (len as f32 * grow_factor).ceil() as usize);
+    let grown = usize::min((grown + unit - 1) / unit * unit, max_size);
+    if grown < required { return None; }
+    Some(grown)
+}
+
+fn extend_free_areas(free_areas:&mut Vec<SlicePointer>, old_len:usize, new_len:usize) {
+    if new_len == old_len { return; }
+    match free_areas.iter_mut().find(|x| x.start + x.size == old_len) {
+        Some(tail) => tail.size += new_len - old_len,
+        None => free_areas.push(SlicePointer { start:old_len, size:new_len - old_len })
+    }
+}
+
 pub struct RenderObjectAllocation { 
     render_type:Rc<RenderType>, 
+    key:BatcherKey,
     id:usize,
     remove_cache:Rc<RefCell<Vec<usize>>>
 }
@@ -579,6 +1397,20 @@ impl Debug for RenderObjectAllocation {
     }
 }
 
+impl RenderObjectAllocation {
+    pub(super) fn pick_id(&self) -> PickId {
+        PickId { key:self.key, allocation:self.id }
+    }
+}
+
+//render object found by Renderer::pick, compare it with RenderObject::pick_id. An object gets a new one whenever
+//it is reallocated, by changing its type, layer or z index or outgrowing its space
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PickId {
+    key:BatcherKey,
+    allocation:usize
+}
+
 impl Drop for RenderObjectAllocation {
     fn drop(&mut self) {
         self.remove_cache.borrow_mut().push(self.id)
@@ -603,6 +1435,11 @@ impl MappedTexture {
         self.batched_texture.borrow().loaded()
     }
 
+    //told whenever texcoords from get_texcoord change
+    pub fn listen(&self) -> PlacementListener {
+        self.batched_texture.borrow().listen()
+    }
+
     fn cached_update(&self, update_cache:&mut UpdateCache,  src:Box<dyn BatchableTextureSource>) {
         update_cache.cache_update(self.batched_texture.clone(), src);
     }
@@ -610,6 +1447,10 @@ impl MappedTexture {
     fn bind(&self, texture_batcher:&TextureBatcher) {
         self.batched_texture.borrow().bind(texture_batcher);
     }
+
+    pub(super) fn texture_handle(&self, renderer:&Renderer) -> TextureHandle {
+        renderer.texture_batcher.texture_handle(&self.batched_texture.borrow())
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -617,6 +1458,16 @@ struct RenderChunkIndex {
     chunk:usize,
     v_slice:SlicePointer,
     i_slice:SlicePointer,
+    //where the object is for sorting translucent chunks
+    center:Option<Point3<f32>>,
+    //objects without are never culled
+    bounds:Option<Aabb>
+}
+
+impl RenderChunkIndex {
+    fn visible(&self, transform:&Matrix4<f32>) -> bool {
+        self.bounds.map_or(true, |x| !x.outside_clip(transform))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -630,7 +1481,21 @@ struct SlicePointer {
 pub enum UniformData {
     Texture(Option<MappedTexture>),
     Float(f32),
+    Vec2(Vector2<f32>),
+    Vec3(Vector3<f32>),
+    Vec4(Vector4<f32>),
+    Int(i32),
+    UInt(u32),
+    Bool(bool),
+    Matrix3(Matrix3<f32>),
     Matrix4(Matrix4<f32>),
+    //array uniforms, set from element 0
+    FloatArray(Vec<f32>),
+    Vec2Array(Vec<Vector2<f32>>),
+    Vec3Array(Vec<Vector3<f32>>),
+    Vec4Array(Vec<Vector4<f32>>),
+    IntArray(Vec<i32>),
+    Matrix4Array(Vec<Matrix4<f32>>),
     Global
 }
 
@@ -643,17 +1508,19 @@ impl UniformData {
     }
 }
 
-type UniformRoleMap = HashMap<UniformRole,UniformData>;
+pub(super) type UniformRoleMap = HashMap<UniformRole,UniformData>;
 
 #[derive(Clone, Debug)]
  pub struct UniformBlock {
     uniforms:HashMap<UniformAttrib, UniformData>, 
-    cached_uniform_locations:HashMap<UniformAttrib, Option<WebGlUniformLocation>>
+    cached_uniform_locations:HashMap<UniformAttrib, Option<UniformLocation>>,
+    //the pick program's, so picking doesn't throw away the drawing program's
+    cached_pick_locations:HashMap<UniformAttrib, Option<UniformLocation>>
 }
 
 impl Default for UniformBlock {
     fn default() -> Self {
-        Self { uniforms: HashMap::new(), cached_uniform_locations: HashMap::new() }
+        Self { uniforms: HashMap::new(), cached_uniform_locations: HashMap::new(), cached_pick_locations: HashMap::new() }
     }
 }
 
@@ -675,6 +1542,20 @@ impl UniformBlock {
         };
         self.uniforms.insert(attrib.clone(), value);
         self.cached_uniform_locations.insert(attrib.clone(), None);
+        self.cached_pick_locations.insert(attrib.clone(), None);
+    }
+
+    fn samples(&self, texture:&MappedTexture) -> bool {
+        self.uniforms.values().any(|x| match x {
+            UniformData::Texture(Some(x)) => x.batched_texture.borrow().same_instance(&texture.batched_texture.borrow()),
+            _ => false
+        })
+    }
+
+    pub(super) fn clear_cached_locations(&mut self) {
+        for location in self.cached_uniform_locations.values_mut().chain(self.cached_pick_locations.values_mut()) {
+            *location = None;
+        }
     }
 
     fn batchable_with(&self, other:&Self) -> bool {
@@ -689,47 +1570,105 @@ impl UniformBlock {
         return true;
     }
 
-    fn setup_uniforms_and_textures (
-        &mut self, 
-        gl:&WebGl2RenderingContext, 
-        texture_batcher:&TextureBatcher,
-        program:&WebGlProgram,
-        role_map:&UniformRoleMap
-    ) {
+    pub(super) fn setup_uniforms_and_textures(&mut self, gl:&dyn GraphicsBackend, texture_batcher:&TextureBatcher, program:ProgramHandle, role_map:&UniformRoleMap) {
+        setup_uniforms(&self.uniforms, &mut self.cached_uniform_locations, gl, texture_batcher, program, role_map);
+    }
 
-        let mut texture_count = 0;
-        for (attrib,data) in self.uniforms.iter() {
-            let data = match &attrib.role {
-                UniformRole::Custom => {
-                    &data
-                },
-                x => {
-                    role_map.get(x).unwrap()
-                }
-            };
-            let location = self.cached_uniform_locations.get_mut(&attrib).unwrap().get_or_insert_with(|| {
-                gl.get_uniform_location(program, &attrib.name).unwrap()
-            });
+    pub(super) fn setup_pick_uniforms_and_textures(&mut self, gl:&dyn GraphicsBackend, texture_batcher:&TextureBatcher, program:ProgramHandle, role_map:&UniformRoleMap) {
+        setup_uniforms(&self.uniforms, &mut self.cached_pick_locations, gl, texture_batcher, program, role_map);
+    }
+}
 
-            apply_uniform_data(&data, gl, texture_batcher, location, &mut texture_count);
+fn setup_uniforms(
+    uniforms:&HashMap<UniformAttrib, UniformData>,
+    cached_locations:&mut HashMap<UniformAttrib, Option<UniformLocation>>,
+    gl:&dyn GraphicsBackend, 
+    texture_batcher:&TextureBatcher,
+    program:ProgramHandle,
+    role_map:&UniformRoleMap
+) {
+    let mut texture_count = 0;
+    for (attrib,data) in uniforms.iter() {
+        let data = match &attrib.role {
+            UniformRole::Custom => {
+                &data
+            },
+            x => {
+                role_map.get(x).unwrap()
+            }
+        };
+        //uniforms the program doesn't use, like the type's own ones under the fallback program, are skipped
+        let cached = cached_locations.get_mut(&attrib).unwrap();
+        if cached.is_none() {
+            *cached = gl.get_uniform_location(program, &attrib.name);
         }
+        let location = match cached {
+            Some(x) => *x,
+            None => continue
+        };
+
+        apply_uniform_data(&data, gl, texture_batcher, location, &mut texture_count);
     }
 }
 
+//one variant per ShaderDataTypes, normalized variants hold the integers as they are stored in the buffer
 pub enum VertexData {
     Float(f32),
     FloatVec2(Vector2<f32>),
     FloatVec3(Vector3<f32>),
     FloatVec4(Vector4<f32>),
+    Int(i32),
+    IntVec2(Vector2<i32>),
+    IntVec3(Vector3<i32>),
+    IntVec4(Vector4<i32>),
+    Uint(u32),
+    UintVec2(Vector2<u32>),
+    UintVec3(Vector3<u32>),
+    UintVec4(Vector4<u32>),
+    FloatMat2(Matrix2<f32>),
+    FloatMat3(Matrix3<f32>),
+    FloatMat4(Matrix4<f32>),
+    ByteNormalizedVec2(Vector2<i8>),
+    ByteNormalizedVec4(Vector4<i8>),
+    UnsignedByteNormalizedVec2(Vector2<u8>),
+    UnsignedByteNormalizedVec4(Vector4<u8>),
+    ShortNormalizedVec2(Vector2<i16>),
+    ShortNormalizedVec4(Vector4<i16>),
+    UnsignedShortNormalizedVec2(Vector2<u16>),
+    UnsignedShortNormalizedVec4(Vector4<u16>),
 }
 
 impl VertexData {
+    //rgba in 0..1 packed into 4 bytes, for UnsignedByteNormalizedVec4 attributes
+    pub fn packed_color(color:Vector4<f32>) -> Self {
+        Self::UnsignedByteNormalizedVec4(color.map(|x| (x.clamp(0.0, 1.0) * 255.0).round() as u8))
+    }
+
     pub fn into_bytes(self) -> Vec<u8> {
         match self {  
             Self::Float(x) => x.to_ne_bytes().to_vec(),
             Self::FloatVec2(v) => slice_to_vec(AsRef::<[f32; 2]>::as_ref(&v)),
             Self::FloatVec3(v) => slice_to_vec(AsRef::<[f32; 3]>::as_ref(&v)),
             Self::FloatVec4(v) => slice_to_vec(AsRef::<[f32; 4]>::as_ref(&v)),
+            Self::Int(x) => x.to_ne_bytes().to_vec(),
+            Self::IntVec2(v) => AsRef::<[i32; 2]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
+            Self::IntVec3(v) => AsRef::<[i32; 3]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
+            Self::IntVec4(v) => AsRef::<[i32; 4]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
+            Self::Uint(x) => x.to_ne_bytes().to_vec(),
+            Self::UintVec2(v) => AsRef::<[u32; 2]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
+            Self::UintVec3(v) => AsRef::<[u32; 3]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
+            Self::UintVec4(v) => AsRef::<[u32; 4]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
+            Self::FloatMat2(m) => slice_to_vec(AsRef::<[f32; 4]>::as_ref(&m)),
+            Self::FloatMat3(m) => slice_to_vec(AsRef::<[f32; 9]>::as_ref(&m)),
+            Self::FloatMat4(m) => slice_to_vec(AsRef::<[f32; 16]>::as_ref(&m)),
+            Self::ByteNormalizedVec2(v) => AsRef::<[i8; 2]>::as_ref(&v).iter().map(|x| *x as u8).collect(),
+            Self::ByteNormalizedVec4(v) => AsRef::<[i8; 4]>::as_ref(&v).iter().map(|x| *x as u8).collect(),
+            Self::UnsignedByteNormalizedVec2(v) => AsRef::<[u8; 2]>::as_ref(&v).to_vec(),
+            Self::UnsignedByteNormalizedVec4(v) => AsRef::<[u8; 4]>::as_ref(&v).to_vec(),
+            Self::ShortNormalizedVec2(v) => AsRef::<[i16; 2]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
+            Self::ShortNormalizedVec4(v) => AsRef::<[i16; 4]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
+            Self::UnsignedShortNormalizedVec2(v) => AsRef::<[u16; 2]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
+            Self::UnsignedShortNormalizedVec4(v) => AsRef::<[u16; 4]>::as_ref(&v).iter().flat_map(|x| x.to_ne_bytes()).collect(),
         }
     }
 }
@@ -740,25 +1679,756 @@ fn slice_to_vec(slice:&[f32]) -> Vec<u8> {
 
 fn apply_uniform_data(
     data:&UniformData,
-    gl:&WebGl2RenderingContext,
+    gl:&dyn GraphicsBackend,
     texture_batcher:&TextureBatcher,
-    location:&WebGlUniformLocation,
+    location:UniformLocation,
     texture_count:&mut i32,
 ) {
     match data {
-        UniformData::Float(x) => { gl.uniform1f(Some(&location), *x); },
+        UniformData::Float(x) => { gl.uniform1f(location, *x); },
+        UniformData::Vec2(v) => { gl.uniform2f(location, v.x, v.y); },
+        UniformData::Vec3(v) => { gl.uniform3f(location, v.x, v.y, v.z); },
+        UniformData::Vec4(v) => { gl.uniform4f(location, v.x, v.y, v.z, v.w); },
+        UniformData::Int(x) => { gl.uniform1i(location, *x); },
+        UniformData::UInt(x) => { gl.uniform1ui(location, *x); },
+        UniformData::Bool(x) => { gl.uniform1i(location, *x as i32); },
+        UniformData::Matrix3(mat) => {
+            let data:&[f32; 9] = mat.as_ref();
+            gl.uniform_matrix3fv(location, false, data);
+        },
+        UniformData::FloatArray(x) => { gl.uniform1fv(location, x); },
+        UniformData::Vec2Array(x) => { gl.uniform2fv(location, &x.iter().flat_map(|v| AsRef::<[f32; 2]>::as_ref(v).to_vec()).collect::<Vec<_>>()); },
+        UniformData::Vec3Array(x) => { gl.uniform3fv(location, &x.iter().flat_map(|v| AsRef::<[f32; 3]>::as_ref(v).to_vec()).collect::<Vec<_>>()); },
+        UniformData::Vec4Array(x) => { gl.uniform4fv(location, &x.iter().flat_map(|v| AsRef::<[f32; 4]>::as_ref(v).to_vec()).collect::<Vec<_>>()); },
+        UniformData::IntArray(x) => { gl.uniform1iv(location, x); },
+        UniformData::Matrix4Array(x) => { gl.uniform_matrix4fv(location, false, &x.iter().flat_map(|m| AsRef::<[f32; 16]>::as_ref(m).to_vec()).collect::<Vec<_>>()); },
         UniformData::Texture(Some(mapped)) => {
             let active = WebGl2RenderingContext::TEXTURE0 + *texture_count as u32;
             gl.active_texture(active);
             mapped.bind(texture_batcher);
-            gl.uniform1i(Some(&location), *texture_count);
+            gl.uniform1i(location, *texture_count);
             *texture_count += 1;
         },
         UniformData::Texture(None) => {},
         UniformData::Matrix4(mat) => {
             let data:&[f32; 16] = mat.as_ref();
-            gl.uniform_matrix4fv_with_f32_array(Some(&location), false, data);
+            gl.uniform_matrix4fv(location, false, data);
         },
         UniformData::Global => { panic!("Tried to apply a UniformData which was labeled Global, this should be converted to the correct UniformData before being applied.") }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use cgmath::{Vector2, Vector3, Vector4, Matrix3, Point2, Point3, Rad, InnerSpace};
+    use web_sys::WebGl2RenderingContext;
+
+    use super::{Renderer, VertexData, UniformData};
+    use crate::engine::render::camera_2d::Camera2D;
+    use crate::engine::render::layer::Layer;
+    use crate::engine::render::{layer::LayerId, error::RenderError, render_object::{RenderType, RenderObject, IndexFormat, VertexAttrib, AttributeRole, ShaderDataTypes, InstancedData, UniformAttrib, UniformRole}, uniform_buffer::{CAMERA_BLOCK_BINDING, CAMERA_PROJECTION_OFFSET}, camera::Camera, program::ShaderStage, types::{triangle::{self, Triangle}, line::{self, Line, EndBehavior}}, effects::vignette, backend::{ProgramHandle, BufferHandle, recording::{RecordingBackend, GlCall, UniformValue}}};
+
+    fn renderer() -> (Rc<RecordingBackend>, Renderer) {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let renderer = Renderer::with_backend(gl.clone());
+        (gl, renderer)
+    }
+
+    fn triangle(renderer:&mut Renderer, x:f32, color:Vector4<f32>) -> Triangle {
+        Triangle::new(renderer, [Vector2::new(x, 0.0), Vector2::new(x + 0.5, 0.0), Vector2::new(x, 0.5)], color).unwrap()
+    }
+
+    //the triangle shader, with chunks big enough to go past what u16 indicies can address
+    fn big_type(index_format:IndexFormat) -> Rc<RenderType> {
+        let small = triangle::render_type();
+        Rc::new(RenderType::new_batched_growable(small.vertex_shader.into_inner(), small.fragment_shader.into_inner(), small.vertex_attribs, small.uniform_attribs, Vec::new(), 100, 200000, 100, 400000, 2.0, 2.0).with_index_format(index_format))
+    }
+
+    //one triangle between the first and last of n verticies
+    fn big_object(render_type:&Rc<RenderType>, n:u32) -> RenderObject {
+        let mut object = RenderObject::new(render_type.clone());
+        object.set_v_datas(0, "pos", (0..n).map(|i| VertexData::FloatVec2(Vector2::new(i as f32/n as f32, 0.0))).collect());
+        object.set_v_datas(0, "vColor", (0..n).map(|_| VertexData::FloatVec4(Vector4::new(1.0, 0.0, 0.0, 1.0))).collect());
+        object.add_triangle([0, 1, n-1]);
+        object
+    }
+
+    fn floats(bytes:&[u8]) -> Vec<f32> {
+        bytes.chunks_exact(4).map(|x| f32::from_ne_bytes([x[0], x[1], x[2], x[3]])).collect()
+    }
+
+    //vertex data uploaded to ARRAY_BUFFER, in call order
+    fn vertex_uploads(gl:&RecordingBackend) -> Vec<Vec<f32>> {
+        gl.calls().into_iter().filter_map(|x| match x {
+            GlCall::BufferSubData { target:WebGl2RenderingContext::ARRAY_BUFFER, data, .. } => Some(floats(&data)),
+            _ => None
+        }).collect()
+    }
+
+    fn program_with_source(gl:&RecordingBackend, source:&str) -> Option<ProgramHandle> {
+        gl.state().programs.iter().find(|(_, x)| x.vertex_source.contains(source) || x.fragment_source.contains(source)).map(|x| *x.0)
+    }
+
+    fn program_using(gl:&RecordingBackend, attrib:&str) -> ProgramHandle {
+        *gl.state().programs.iter().find(|(_, x)| x.attrib_locations.contains_key(attrib)).expect("No program with the attribute").0
+    }
+
+    #[test]
+    fn uploads_interleaved_verticies() {
+        let (gl, mut renderer) = renderer();
+        let _triangle = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(vertex_uploads(&gl), vec![vec![
+            0.0, 0.0, 1.0, 0.0, 0.0, 1.0,
+            0.5, 0.0, 1.0, 0.0, 0.0, 1.0,
+            0.0, 0.5, 1.0, 0.0, 0.0, 1.0
+        ]]);
+    }
+
+    #[test]
+    fn batches_objects_into_one_draw() {
+        let (gl, mut renderer) = renderer();
+        let _first = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        let second = triangle(&mut renderer, -0.5, Vector4::new(0.0, 1.0, 0.0, 1.0));
+        renderer.render();
+        assert_eq!(gl.draw_calls(), vec![GlCall::DrawElements { mode:WebGl2RenderingContext::TRIANGLES, count:6, index_type:WebGl2RenderingContext::UNSIGNED_SHORT, offset:0 }]);
+
+        //the second object's indicies point past the first one's verticies
+        let indicies = gl.calls().into_iter().filter_map(|x| match x {
+            GlCall::BufferSubData { target:WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, offset:6, data } => Some(data),
+            _ => None
+        }).last().expect("Expected the second object's indicies to be uploaded");
+        assert_eq!(indicies, [3u16, 4, 5].iter().flat_map(|x| x.to_ne_bytes()).collect::<Vec<u8>>());
+
+        drop(second);
+        gl.take_calls();
+        renderer.render();
+        assert_eq!(gl.draw_calls(), vec![GlCall::DrawElements { mode:WebGl2RenderingContext::TRIANGLES, count:3, index_type:WebGl2RenderingContext::UNSIGNED_SHORT, offset:0 }]);
+    }
+
+    #[test]
+    fn compacts_removed_objects() {
+        let (gl, mut renderer) = renderer();
+        let mut triangles:Vec<Option<Triangle>> = (0..4).map(|i| Some(triangle(&mut renderer, i as f32 * 0.1, Vector4::new(1.0, 0.0, 0.0, 1.0)))).collect();
+        triangles[1] = None;
+        triangles[2] = None;
+        gl.take_calls();
+        renderer.compact();
+        renderer.render();
+
+        //the last triangle's verticies move down next to the first one's, 72 bytes each
+        let calls = gl.calls();
+        assert!(calls.contains(&GlCall::CopyBufferSubData { read_target:WebGl2RenderingContext::COPY_READ_BUFFER, write_target:WebGl2RenderingContext::COPY_WRITE_BUFFER, read_offset:216, write_offset:72, size:72 }));
+        assert!(calls.contains(&GlCall::BufferSubData { target:WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, offset:0, data:[0u16, 1, 2, 3, 4, 5].iter().flat_map(|x| x.to_ne_bytes()).collect() }));
+        assert_eq!(gl.draw_calls(), vec![GlCall::DrawElements { mode:WebGl2RenderingContext::TRIANGLES, count:6, index_type:WebGl2RenderingContext::UNSIGNED_SHORT, offset:0 }]);
+    }
+
+    #[test]
+    fn releases_emptied_batchers() {
+        let (gl, mut renderer) = renderer();
+        //only the camera block is there before anything is drawn
+        let buffers:Vec<BufferHandle> = gl.state().buffers.keys().copied().collect();
+        assert_eq!(buffers.len(), 1);
+        assert!(gl.state().programs.is_empty());
+        let triangle = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        renderer.render();
+        renderer.pick(0, 0).unwrap();
+        assert_eq!(gl.state().programs.len(), 2);
+
+        //the chunk's buffers and vaos go with the batcher, and with it the last users of its programs
+        drop(triangle);
+        renderer.render();
+        assert!(renderer.render_batchers.is_empty());
+        assert!(renderer.render_order.is_empty());
+        let state = gl.state();
+        assert_eq!(state.buffers.keys().copied().collect::<Vec<_>>(), buffers);
+        assert!(state.vertex_arrays.is_empty());
+        assert!(state.programs.is_empty());
+    }
+
+    //buffers copied into with the bytes copied, in call order
+    fn copies(gl:&RecordingBackend) -> Vec<(BufferHandle, i32)> {
+        let mut write_buffer = None;
+        gl.calls().into_iter().filter_map(|x| match x {
+            GlCall::BindBuffer(WebGl2RenderingContext::COPY_WRITE_BUFFER, Some(buffer)) => {
+                write_buffer = Some(buffer);
+                None
+            },
+            GlCall::CopyBufferSubData { size, .. } => Some((write_buffer.expect("Expected a buffer to copy into"), size)),
+            _ => None
+        }).collect()
+    }
+
+    #[test]
+    fn grows_full_chunks_in_place() {
+        let (gl, mut renderer) = renderer();
+        //triangle chunks start with room for 20 verticies and indicies, 6 triangles fit
+        let mut triangles:Vec<Triangle> = (0..6).map(|i| triangle(&mut renderer, i as f32 * 0.01, Vector4::new(1.0, 0.0, 0.0, 1.0))).collect();
+        assert!(copies(&gl).is_empty());
+        gl.take_calls();
+        triangles.push(triangle(&mut renderer, 0.06, Vector4::new(1.0, 0.0, 0.0, 1.0)));
+
+        //the old buffers of 24 byte verticies and u16 indicies are copied once into ones twice the size
+        let grown:Vec<(usize, i32)> = copies(&gl).into_iter().map(|(buffer, size)| (gl.buffer_contents(buffer).unwrap().len(), size)).collect();
+        assert_eq!(grown, vec![(40*24, 20*24), (40*2, 20*2)]);
+        renderer.render();
+        assert_eq!(gl.draw_calls(), vec![GlCall::DrawElements { mode:WebGl2RenderingContext::TRIANGLES, count:21, index_type:WebGl2RenderingContext::UNSIGNED_SHORT, offset:0 }]);
+    }
+
+    #[test]
+    fn starts_a_new_chunk_past_the_max_size() {
+        let (gl, mut renderer) = renderer();
+        //13 triangles fill a chunk grown to its max of 40 verticies, the next one can't fit
+        let _triangles:Vec<Triangle> = (0..14).map(|i| triangle(&mut renderer, i as f32 * 0.01, Vector4::new(1.0, 0.0, 0.0, 1.0))).collect();
+        assert_eq!(copies(&gl).len(), 2);
+        renderer.render();
+        assert_eq!(gl.draw_calls(), vec![
+            GlCall::DrawElements { mode:WebGl2RenderingContext::TRIANGLES, count:39, index_type:WebGl2RenderingContext::UNSIGNED_SHORT, offset:0 },
+            GlCall::DrawElements { mode:WebGl2RenderingContext::TRIANGLES, count:3, index_type:WebGl2RenderingContext::UNSIGNED_SHORT, offset:0 }
+        ]);
+    }
+
+    //a triangle mesh moved by an offset and colored per instance
+    fn instanced_type() -> Rc<RenderType> {
+        let mesh:Vec<u8> = [0.0f32, 0.0, 0.1, 0.0, 0.0, 0.1].iter().flat_map(|x| x.to_ne_bytes()).collect();
+        Rc::new(RenderType::new_instanced(
+            String::from("#version 300 es
+            in vec2 pos;
+            in vec2 offset;
+            in vec4 vColor;
+            out vec4 color;
+            void main() {
+                gl_Position = vec4(pos + offset, 0.0, 1.0);
+                color = vColor;
+            }"),
+            triangle::render_type().fragment_shader.into_inner(),
+            vec![VertexAttrib { name:String::from("pos"), role:AttributeRole::Position, data_type:ShaderDataTypes::FloatVec2 }],
+            vec![
+                VertexAttrib { name:String::from("offset"), role:AttributeRole::Position, data_type:ShaderDataTypes::FloatVec2 },
+                VertexAttrib { name:String::from("vColor"), role:AttributeRole::Custom, data_type:ShaderDataTypes::FloatVec4 }
+            ],
+            triangle::render_type().uniform_attribs,
+            InstancedData { verticies:mesh, indicies:vec![0, 1, 2] },
+            4,
+            64,
+            2.0
+        ))
+    }
+
+    fn instanced_object(renderer:&mut Renderer, render_type:&Rc<RenderType>, offsets:&[f32]) -> RenderObject {
+        let mut object = RenderObject::new(render_type.clone());
+        for (i, x) in offsets.iter().enumerate() {
+            object.set_i_data(i as u32, "offset", VertexData::FloatVec2(Vector2::new(*x, 0.0)));
+            object.set_i_data(i as u32, "vColor", VertexData::FloatVec4(Vector4::new(1.0, 0.0, 0.0, 1.0)));
+        }
+        object.update(renderer).unwrap();
+        object
+    }
+
+    fn instanced_draw(instance_count:i32) -> GlCall {
+        GlCall::DrawElementsInstanced { mode:WebGl2RenderingContext::TRIANGLES, count:3, index_type:WebGl2RenderingContext::UNSIGNED_SHORT, offset:0, instance_count:instance_count }
+    }
+
+    #[test]
+    fn draws_every_instance_at_once() {
+        let (gl, mut renderer) = renderer();
+        let render_type = instanced_type();
+        let _two = instanced_object(&mut renderer, &render_type, &[0.0, 0.2]);
+        let _one = instanced_object(&mut renderer, &render_type, &[0.4]);
+        renderer.render();
+        assert_eq!(gl.draw_calls(), vec![instanced_draw(3)]);
+    }
+
+    #[test]
+    fn advances_instance_attributes_per_instance() {
+        let (gl, mut renderer) = renderer();
+        let _object = instanced_object(&mut renderer, &instanced_type(), &[0.0]);
+        let program = program_using(&gl, "offset");
+        let state = gl.state();
+        let location = |name:&str| state.programs[&program].attrib_locations[name] as u32;
+        let vao = state.vertex_arrays.values().find(|x| x.attribs.contains_key(&location("offset"))).expect("Expected a vertex array with the instance attributes");
+        assert_eq!(vao.attribs[&location("pos")].divisor, 0);
+        assert_eq!(vao.attribs[&location("offset")].divisor, 1);
+        assert_eq!(vao.attribs[&location("vColor")].divisor, 1);
+        assert!(gl.calls().contains(&GlCall::VertexAttribDivisor(location("offset"), 1)));
+    }
+
+    #[test]
+    fn uploads_instance_data() {
+        let (gl, mut renderer) = renderer();
+        let render_type = instanced_type();
+        let _first = instanced_object(&mut renderer, &render_type, &[0.25, 0.5]);
+        let _second = instanced_object(&mut renderer, &render_type, &[0.75]);
+        assert_eq!(vertex_uploads(&gl), vec![
+            vec![0.25, 0.0, 1.0, 0.0, 0.0, 1.0, 0.5, 0.0, 1.0, 0.0, 0.0, 1.0],
+            vec![0.75, 0.0, 1.0, 0.0, 0.0, 1.0]
+        ]);
+    }
+
+    #[test]
+    fn compacts_removed_instances() {
+        let (gl, mut renderer) = renderer();
+        let render_type = instanced_type();
+        let _first = instanced_object(&mut renderer, &render_type, &[0.0]);
+        let second = instanced_object(&mut renderer, &render_type, &[0.2]);
+        let _third = instanced_object(&mut renderer, &render_type, &[0.4]);
+        drop(second);
+        gl.take_calls();
+        renderer.render();
+
+        //instances are drawn from the start of the buffer, so the third moves into the hole, 24 bytes each
+        assert!(gl.calls().contains(&GlCall::CopyBufferSubData { read_target:WebGl2RenderingContext::COPY_READ_BUFFER, write_target:WebGl2RenderingContext::COPY_WRITE_BUFFER, read_offset:48, write_offset:24, size:24 }));
+        assert_eq!(gl.draw_calls(), vec![instanced_draw(2)]);
+        //picking gives instances consecutive ids in buffer order, which has no holes left
+        let compacted = vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.4, 0.0, 1.0, 0.0, 0.0, 1.0];
+        assert!(gl.state().buffers.values().any(|x| x.len() >= 48 && floats(&x[..48]) == compacted));
+    }
+
+    //a triangle from x to x + 0.1 in a chunk big enough for all of them, off screen past 1
+    fn culled_triangle(renderer:&mut Renderer, render_type:&Rc<RenderType>, x:f32) -> RenderObject {
+        let mut object = RenderObject::new(render_type.clone());
+        object.set_v_datas(0, "pos", vec![VertexData::FloatVec2(Vector2::new(x, 0.0)), VertexData::FloatVec2(Vector2::new(x + 0.1, 0.0)), VertexData::FloatVec2(Vector2::new(x, 0.1))]);
+        object.set_v_datas(0, "vColor", (0..3).map(|_| VertexData::FloatVec4(Vector4::new(1.0, 0.0, 0.0, 1.0))).collect());
+        object.add_triangle([0, 1, 2]);
+        object.update(renderer).unwrap();
+        object
+    }
+
+    //draw_range of count indicies from the index offset
+    fn range_draw(offset:i32, count:i32) -> GlCall {
+        GlCall::DrawElements { mode:WebGl2RenderingContext::TRIANGLES, count:count, index_type:WebGl2RenderingContext::UNSIGNED_SHORT, offset:offset * 2 }
+    }
+
+    #[test]
+    fn skips_chunks_out_of_view() {
+        let (gl, mut renderer) = renderer();
+        let render_type = big_type(IndexFormat::U16);
+        let _right = culled_triangle(&mut renderer, &render_type, 5.0);
+        let _left = culled_triangle(&mut renderer, &render_type, -6.0);
+        renderer.render();
+        assert!(gl.draw_calls().is_empty());
+    }
+
+    #[test]
+    fn draws_runs_in_view_separately() {
+        let (gl, mut renderer) = renderer();
+        let render_type = big_type(IndexFormat::U16);
+        let _objects:Vec<RenderObject> = [0.0, 5.0, -0.5, 0.2, 5.0].iter().map(|x| culled_triangle(&mut renderer, &render_type, *x)).collect();
+        renderer.render();
+        assert_eq!(gl.draw_calls(), vec![range_draw(0, 3), range_draw(6, 6)]);
+    }
+
+    #[test]
+    fn merges_runs_across_the_smallest_gaps() {
+        let (gl, mut renderer) = renderer();
+        let render_type = big_type(IndexFormat::U16);
+        //one more run in view than can be drawn separately, the runs around the one triangle gap are drawn together
+        let gaps = [2, 2, 2, 1, 2, 2, 2, 2];
+        assert_eq!(gaps.len(), super::MAX_CULLED_DRAW_RANGES);
+        let mut objects = vec![culled_triangle(&mut renderer, &render_type, 0.0)];
+        for gap in gaps.iter() {
+            for _ in 0..*gap {
+                objects.push(culled_triangle(&mut renderer, &render_type, 5.0));
+            }
+            objects.push(culled_triangle(&mut renderer, &render_type, 0.0));
+        }
+        renderer.render();
+
+        //triangles in view start at 0, 9, 18, 27, 33, 42, 51, 60 and 69
+        let expected = vec![range_draw(0, 3), range_draw(9, 3), range_draw(18, 3), range_draw(27, 9), range_draw(42, 3), range_draw(51, 3), range_draw(60, 3), range_draw(69, 3)];
+        assert_eq!(gl.draw_calls(), expected);
+    }
+
+    #[test]
+    fn draws_instanced_chunks_whole_or_not_at_all() {
+        let (gl, mut renderer) = renderer();
+        let render_type = instanced_type();
+        let _off = instanced_object(&mut renderer, &render_type, &[5.0, 6.0]);
+        renderer.render();
+        assert!(gl.draw_calls().is_empty());
+
+        let _on = instanced_object(&mut renderer, &render_type, &[0.0]);
+        gl.take_calls();
+        renderer.render();
+        assert_eq!(gl.draw_calls(), vec![instanced_draw(3)]);
+    }
+
+    #[test]
+    fn sets_layer_opacity_uniform() {
+        let (gl, mut renderer) = renderer();
+        let _triangle = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        renderer.layer_mut(LayerId::CONTENT).unwrap().opacity = 0.25;
+        renderer.render();
+        assert_eq!(gl.uniform_value(program_using(&gl, "vColor"), "opacity"), Some(UniformValue::Float(0.25)));
+    }
+
+    #[test]
+    fn hidden_layers_are_not_drawn() {
+        let (gl, mut renderer) = renderer();
+        let _triangle = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        renderer.layer_mut(LayerId::CONTENT).unwrap().visible = false;
+        renderer.render();
+        assert!(gl.draw_calls().is_empty());
+    }
+
+    #[test]
+    fn rejects_objects_past_their_index_format() {
+        let (gl, mut renderer) = renderer();
+        let mut object = big_object(&big_type(IndexFormat::U16), 70000);
+        assert!(matches!(object.update(&mut renderer), Err(RenderError::IndexOverflow { verticies:70000, index_format:IndexFormat::U16 })));
+        renderer.render();
+        assert!(gl.draw_calls().is_empty());
+    }
+
+    #[test]
+    fn checks_indicies_up_to_u32_max() {
+        assert!(IndexFormat::U32.check(&[0, u32::MAX], 0).is_ok());
+        assert!(IndexFormat::U32.check(&[u32::MAX - 1], 1).is_ok());
+        assert!(matches!(IndexFormat::U32.check(&[u32::MAX], 1), Err(RenderError::IndexOverflow { index_format:IndexFormat::U32, .. })));
+        assert!(matches!(IndexFormat::U16.check(&[u16::MAX as u32], 1), Err(RenderError::IndexOverflow { verticies:65537, index_format:IndexFormat::U16 })));
+    }
+
+    #[test]
+    fn draws_u32_indicies() {
+        let (gl, mut renderer) = renderer();
+        let mut object = big_object(&big_type(IndexFormat::U32), 70000);
+        object.update(&mut renderer).unwrap();
+        renderer.render();
+        assert_eq!(gl.draw_calls(), vec![GlCall::DrawElements { mode:WebGl2RenderingContext::TRIANGLES, count:3, index_type:WebGl2RenderingContext::UNSIGNED_INT, offset:0 }]);
+    }
+
+    #[test]
+    fn starts_a_new_chunk_past_u16_indicies() {
+        let (gl, mut renderer) = renderer();
+        let render_type = big_type(IndexFormat::U16);
+        let mut first = big_object(&render_type, 40000);
+        let mut second = big_object(&render_type, 40000);
+        first.update(&mut renderer).unwrap();
+        second.update(&mut renderer).unwrap();
+        renderer.render();
+        assert_eq!(gl.draw_calls().len(), 2);
+    }
+
+    #[test]
+    fn reloads_line_shaders() {
+        let (gl, mut renderer) = renderer();
+        let _line = Line::new(&mut renderer, [Vector2::new(0.0, 0.0), Vector2::new(0.5, 0.0)], Vector4::new(1.0, 0.0, 0.0, 1.0), 0.01, 0.01, EndBehavior::Clipped).unwrap();
+        let sources = line::render_type();
+        let fragment_shader = sources.fragment_shader.into_inner().replace("s * opacity", "s * opacity * 0.5");
+        renderer.reload_render_type(line::NAME, sources.vertex_shader.into_inner(), fragment_shader).unwrap();
+        gl.take_calls();
+        renderer.render();
+
+        let reloaded = program_with_source(&gl, "s * opacity * 0.5").expect("Expected the reloaded sources to be linked");
+        assert!(gl.calls().contains(&GlCall::UseProgram(Some(reloaded))));
+        assert_eq!(gl.draw_calls().len(), 1);
+    }
+
+    #[test]
+    fn keeps_line_shaders_when_reload_fails() {
+        let (gl, mut renderer) = renderer();
+        let _line = Line::new(&mut renderer, [Vector2::new(0.0, 0.0), Vector2::new(0.5, 0.0)], Vector4::new(1.0, 0.0, 0.0, 1.0), 0.01, 0.01, EndBehavior::Clipped).unwrap();
+        let sources = line::render_type();
+        let original = sources.vertex_shader.borrow().clone();
+        //an attribute the render type doesn't declare
+        let vertex_shader = original.replace("in vec2 pos;", "in vec2 pos;\n            in float vExtra;");
+        let error = match renderer.reload_render_type(line::NAME, vertex_shader, sources.fragment_shader.into_inner()) {
+            Err(RenderError::Shader(e)) => e,
+            x => panic!("Expected a shader error, got {:?}", x)
+        };
+        assert_eq!(error.stage, ShaderStage::Link);
+        assert!(error.log.contains("vExtra"));
+        assert_eq!(*renderer.render_type(line::NAME).unwrap().vertex_shader.borrow(), original);
+        //the program linked to check the new sources is deleted again
+        assert_eq!(program_with_source(&gl, "vExtra"), None);
+
+        gl.take_calls();
+        renderer.render();
+        let previous = program_with_source(&gl, "s * opacity").expect("Expected the line program to still be linked");
+        assert!(gl.calls().contains(&GlCall::UseProgram(Some(previous))));
+        assert_eq!(gl.draw_calls().len(), 1);
+    }
+
+    //a triangle built by hand, since Triangle::new expects its type to draw
+    fn triangle_object(renderer:&Renderer) -> RenderObject {
+        let mut object = RenderObject::new(renderer.render_type(triangle::NAME).unwrap());
+        object.set_v_datas(0, "pos", vec![VertexData::FloatVec2(Vector2::new(0.0, 0.0)), VertexData::FloatVec2(Vector2::new(0.5, 0.0)), VertexData::FloatVec2(Vector2::new(0.0, 0.5))]);
+        object.set_v_datas(0, "vColor", (0..3).map(|_| VertexData::FloatVec4(Vector4::new(1.0, 0.0, 0.0, 1.0))).collect());
+        object.add_triangle([0, 1, 2]);
+        object
+    }
+
+    #[test]
+    fn draws_failed_types_with_the_fallback() {
+        let (gl, mut renderer) = renderer();
+        gl.fail_compiles_containing("color.a * opacity");
+        let _triangle = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        assert!(renderer.shaders().error(&renderer.render_type(triangle::NAME).unwrap()).is_some());
+        gl.take_calls();
+        renderer.render();
+
+        let fallback = program_with_source(&gl, "errorColor").expect("Expected the fallback program to be linked");
+        assert!(gl.calls().contains(&GlCall::UseProgram(Some(fallback))));
+        assert_eq!(gl.draw_calls().len(), 1);
+    }
+
+    #[test]
+    fn skips_types_whose_fallback_fails() {
+        let (gl, mut renderer) = renderer();
+        gl.fail_compiles_containing("color.a * opacity");
+        gl.fail_compiles_containing("errorColor");
+        let mut object = triangle_object(&renderer);
+        assert!(matches!(object.update(&mut renderer), Err(RenderError::Shader(_))));
+        renderer.render();
+        assert!(gl.draw_calls().is_empty());
+    }
+
+    #[test]
+    fn unknown_render_types_are_errors() {
+        let (_, mut renderer) = renderer();
+        assert!(renderer.render_type("missing").is_none());
+        assert!(matches!(renderer.reload_render_type("missing", String::new(), String::new()), Err(RenderError::UnknownRenderType(_))));
+        assert!(matches!(renderer.add_effect("missing"), Err(RenderError::UnknownRenderType(_))));
+    }
+
+    #[test]
+    fn points_attributes_at_the_vertex_layout() {
+        let (gl, mut renderer) = renderer();
+        let attribs = vec![
+            ("pos", ShaderDataTypes::FloatVec2),
+            ("flags", ShaderDataTypes::UintVec2),
+            ("id", ShaderDataTypes::INT),
+            ("transform", ShaderDataTypes::FloatMat3),
+            ("tint", ShaderDataTypes::UnsignedByteNormalizedVec4),
+            ("normal", ShaderDataTypes::ShortNormalizedVec2)
+        ];
+        let declarations:String = attribs.iter().map(|(name, data_type)| format!("in {} {};\n", data_type.get_glsl_name(), name)).collect();
+        let render_type = RenderType::new_batched_growable(
+            format!("#version 300 es\n{}void main() {{ gl_Position = vec4(pos, 0.0, 1.0); }}", declarations),
+            String::from("#version 300 es\nprecision highp float;\nout vec4 FragColor;\nvoid main() { FragColor = vec4(1.0); }"),
+            attribs.into_iter().map(|(name, data_type)| VertexAttrib { name:String::from(name), role:AttributeRole::Custom, data_type:data_type }).collect(),
+            Vec::new(), Vec::new(), 20, 40, 20, 40, 2.0, 2.0
+        );
+        let mut object = RenderObject::new(Rc::new(render_type));
+        object.set_v_datas(0, "pos", vec![VertexData::FloatVec2(Vector2::new(0.0, 0.0)), VertexData::FloatVec2(Vector2::new(0.5, 0.0)), VertexData::FloatVec2(Vector2::new(0.0, 0.5))]);
+        object.set_v_data(0, "flags", VertexData::UintVec2(Vector2::new(7, 9)));
+        object.set_v_data(0, "tint", VertexData::packed_color(Vector4::new(1.0, 0.0, 0.5, 1.0)));
+        object.set_v_data(0, "normal", VertexData::ShortNormalizedVec2(Vector2::new(-32767, 32767)));
+        object.add_triangle([0, 1, 2]);
+        object.update(&mut renderer).unwrap();
+
+        //integer and normalized values are stored as they are, not as floats
+        let verticies = gl.calls().into_iter().find_map(|x| match x {
+            GlCall::BufferSubData { target:WebGl2RenderingContext::ARRAY_BUFFER, data, .. } => Some(data),
+            _ => None
+        }).expect("Expected the verticies to be uploaded");
+        assert_eq!(verticies[8..16], [7u32, 9].iter().flat_map(|x| x.to_ne_bytes()).collect::<Vec<u8>>()[..]);
+        assert_eq!(verticies[56..60], [255, 0, 128, 255]);
+        assert_eq!(verticies[60..64], [-32767i16, 32767].iter().flat_map(|x| x.to_ne_bytes()).collect::<Vec<u8>>()[..]);
+
+        let pointers:Vec<GlCall> = gl.calls().into_iter().filter(|x| matches!(x, GlCall::VertexAttribPointer { .. } | GlCall::VertexAttribIPointer { .. })).collect();
+        let stride = 64;
+        assert_eq!(pointers, vec![
+            GlCall::VertexAttribPointer { location:0, size:2, data_type:WebGl2RenderingContext::FLOAT, normalized:false, stride:stride, offset:0 },
+            //integer attributes keep their type instead of being converted to floats
+            GlCall::VertexAttribIPointer { location:1, size:2, data_type:WebGl2RenderingContext::UNSIGNED_INT, stride:stride, offset:8 },
+            GlCall::VertexAttribIPointer { location:2, size:1, data_type:WebGl2RenderingContext::INT, stride:stride, offset:16 },
+            //one location per matrix column
+            GlCall::VertexAttribPointer { location:3, size:3, data_type:WebGl2RenderingContext::FLOAT, normalized:false, stride:stride, offset:20 },
+            GlCall::VertexAttribPointer { location:4, size:3, data_type:WebGl2RenderingContext::FLOAT, normalized:false, stride:stride, offset:32 },
+            GlCall::VertexAttribPointer { location:5, size:3, data_type:WebGl2RenderingContext::FLOAT, normalized:false, stride:stride, offset:44 },
+            GlCall::VertexAttribPointer { location:6, size:4, data_type:WebGl2RenderingContext::UNSIGNED_BYTE, normalized:true, stride:stride, offset:56 },
+            GlCall::VertexAttribPointer { location:7, size:2, data_type:WebGl2RenderingContext::SHORT, normalized:true, stride:stride, offset:60 }
+        ]);
+    }
+
+    fn assert_near(a:Point3<f32>, b:Point3<f32>) {
+        assert!((a - b).magnitude() < 1e-3, "Expected {:?}, got {:?}", b, a);
+    }
+
+    #[test]
+    fn screen_to_world_inverts_world_to_screen() {
+        let (_gl, mut renderer) = renderer();
+        let world = Point3::new(0.3, -0.2, 0.0);
+        let screen = renderer.world_to_screen(world).unwrap();
+        assert_near(renderer.screen_to_world(screen, 0.0).unwrap(), world);
+
+        renderer.set_camera_2d(Some(Camera2D::new(Point2::new(5.0, 5.0), 10.0, 64, 64)));
+        let world = Point3::new(6.0, 3.0, 0.0);
+        let screen = renderer.world_to_screen(world).unwrap();
+        assert!((screen - Vector2::new(10.0 / 32.0, -20.0 / 32.0)).magnitude() < 1e-5);
+        assert_near(renderer.screen_to_world(screen, 0.0).unwrap(), world);
+    }
+
+    #[test]
+    fn skips_post_processing_without_targets() {
+        let gl = Rc::new(RecordingBackend::new(0, 0));
+        let mut renderer = Renderer::with_backend(gl.clone());
+        renderer.add_effect(vignette::NAME).unwrap();
+        let _triangle = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        renderer.render();
+        //only the scene itself, straight to the canvas
+        assert_eq!(gl.draw_calls().len(), 1);
+        assert!(gl.state().framebuffers.is_empty());
+    }
+
+    #[test]
+    fn draws_opaque_types_before_translucent_ones() {
+        let (gl, mut renderer) = renderer();
+        let _line = Line::new(&mut renderer, [Vector2::new(0.0, 0.0), Vector2::new(0.5, 0.0)], Vector4::new(1.0, 0.0, 0.0, 1.0), 0.01, 0.01, EndBehavior::Clipped).unwrap();
+        let _triangle = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        gl.take_calls();
+        renderer.render();
+
+        let calls = gl.calls();
+        let draws:Vec<usize> = calls.iter().enumerate().filter(|(_, x)| matches!(x, GlCall::DrawElements { .. })).map(|x| x.0).collect();
+        assert_eq!(draws.len(), 2);
+        let triangle_program = program_with_source(&gl, "color.a * opacity").unwrap();
+        let line_program = program_with_source(&gl, "s * opacity").unwrap();
+        let used_before = |i:usize| calls[..i].iter().rev().find_map(|x| match x { GlCall::UseProgram(p) => *p, _ => None });
+        assert_eq!(used_before(draws[0]), Some(triangle_program));
+        assert_eq!(used_before(draws[1]), Some(line_program));
+
+        let blending = &calls[draws[0]..draws[1]];
+        assert!(calls[..draws[0]].contains(&GlCall::Disable(WebGl2RenderingContext::BLEND)));
+        assert!(blending.contains(&GlCall::Enable(WebGl2RenderingContext::BLEND)));
+        assert!(blending.contains(&GlCall::BlendFunc(WebGl2RenderingContext::SRC_ALPHA, WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA)));
+        assert!(blending.contains(&GlCall::DepthMask(false)));
+    }
+
+    #[test]
+    fn fades_opaque_types_by_a_constant() {
+        let (gl, mut renderer) = renderer();
+        let _triangle = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        renderer.layer_mut(LayerId::CONTENT).unwrap().opacity = 0.5;
+        renderer.render();
+        let calls = gl.calls();
+        assert!(calls.contains(&GlCall::BlendColor([0.0, 0.0, 0.0, 0.5])));
+        assert!(calls.contains(&GlCall::BlendFunc(WebGl2RenderingContext::CONSTANT_ALPHA, WebGl2RenderingContext::ONE_MINUS_CONSTANT_ALPHA)));
+    }
+
+    #[test]
+    fn unknown_layers_are_errors() {
+        let (_, mut other) = renderer();
+        let layer = other.add_layer(Layer::new("extra"));
+        let (gl, mut renderer) = renderer();
+        assert!(renderer.layer(layer).is_none());
+
+        let mut triangle = triangle(&mut renderer, 0.0, Vector4::new(1.0, 0.0, 0.0, 1.0));
+        assert!(matches!(triangle.set_layer(&mut renderer, layer, 0), Err(RenderError::UnknownLayer(x)) if x == layer));
+        //it stays where it was
+        triangle.set_layer(&mut renderer, LayerId::CONTENT, 0).unwrap();
+        renderer.render();
+        assert_eq!(gl.draw_calls().len(), 1);
+    }
+
+    //the triangle shader placed by the camera block, with extra uniform declarations set per object
+    fn camera_type(uniforms:&str, names:&[&str]) -> Rc<RenderType> {
+        let small = triangle::render_type();
+        let vertex_shader = format!("#version 300 es
+            layout(std140) uniform Camera {{
+                mat4 projection;
+                mat4 view;
+            }};
+            {}
+            in vec2 pos;
+            in vec4 vColor;
+            out vec4 color;
+            void main() {{
+                gl_Position = projection * view * vec4(pos, 0.0, 1.0);
+                color = vColor;
+            }}", uniforms);
+        let mut uniform_attribs = small.uniform_attribs;
+        uniform_attribs.extend(names.iter().map(|x| UniformAttrib { name:x.to_string(), role:UniformRole::Custom }));
+        Rc::new(RenderType::new_batched_growable(vertex_shader, small.fragment_shader.into_inner(), small.vertex_attribs, uniform_attribs, Vec::new(), 20, 40, 20, 40, 2.0, 2.0))
+    }
+
+    fn camera_object(renderer:&mut Renderer, render_type:&Rc<RenderType>, uniforms:Vec<(&str, UniformData)>) -> RenderObject {
+        let mut object = RenderObject::new(render_type.clone());
+        object.set_v_datas(0, "pos", vec![VertexData::FloatVec2(Vector2::new(0.0, 0.0)), VertexData::FloatVec2(Vector2::new(0.5, 0.0)), VertexData::FloatVec2(Vector2::new(0.0, 0.5))]);
+        object.set_v_datas(0, "vColor", (0..3).map(|_| VertexData::FloatVec4(Vector4::new(1.0, 0.0, 0.0, 1.0))).collect());
+        object.add_triangle([0, 1, 2]);
+        for (name, value) in uniforms {
+            object.set_uniform(name, value);
+        }
+        object.update(renderer).unwrap();
+        object
+    }
+
+    fn camera_uploads(gl:&RecordingBackend) -> Vec<(i32, Vec<f32>)> {
+        gl.calls().into_iter().filter_map(|x| match x {
+            GlCall::BufferSubData { target:WebGl2RenderingContext::UNIFORM_BUFFER, offset, data } => Some((offset, floats(&data))),
+            _ => None
+        }).collect()
+    }
+
+    #[test]
+    fn uploads_the_camera_once_per_frame() {
+        let (gl, mut renderer) = renderer();
+        let first_type = camera_type("", &[]);
+        let second_type = camera_type("uniform float unused;", &[]);
+        let _first = camera_object(&mut renderer, &first_type, Vec::new());
+        let _second = camera_object(&mut renderer, &second_type, Vec::new());
+
+        //every program declaring the block reads it from the same binding
+        let programs:Vec<ProgramHandle> = gl.state().programs.iter().filter(|(_, x)| x.vertex_source.contains("uniform Camera")).map(|x| *x.0).collect();
+        assert_eq!(programs.len(), 2);
+        for program in programs {
+            assert!(gl.calls().contains(&GlCall::UniformBlockBinding { program:program, block_index:0, binding:CAMERA_BLOCK_BINDING }));
+        }
+
+        for x in [0.0, 1.0].iter() {
+            *renderer.camera_mut() = Camera::new(Point3::new(*x, 0.0, 1.0), Rad(-std::f32::consts::FRAC_PI_2), Rad(0.0));
+            gl.take_calls();
+            renderer.render();
+            assert_eq!(gl.draw_calls().len(), 2);
+            let (view, projection) = renderer.view_projection();
+            let expected:Vec<f32> = AsRef::<[f32; 16]>::as_ref(&projection).iter().chain(AsRef::<[f32; 16]>::as_ref(&view).iter()).copied().collect();
+            assert_eq!(camera_uploads(&gl), vec![(CAMERA_PROJECTION_OFFSET as i32, expected)]);
+        }
+    }
+
+    #[test]
+    fn applies_every_kind_of_uniform() {
+        let (gl, mut renderer) = renderer();
+        let render_type = camera_type("
+            uniform vec2 v2;
+            uniform vec3 v3;
+            uniform vec4 v4;
+            uniform int i;
+            uniform uint u;
+            uniform bool b;
+            uniform mat3 m3;
+            uniform float floats[3];
+            uniform vec2 points[2];
+            uniform int ints[2];",
+            &["v2", "v3", "v4", "i", "u", "b", "m3", "floats", "points", "ints"]
+        );
+        let _object = camera_object(&mut renderer, &render_type, vec![
+            ("v2", UniformData::Vec2(Vector2::new(1.0, 2.0))),
+            ("v3", UniformData::Vec3(Vector3::new(1.0, 2.0, 3.0))),
+            ("v4", UniformData::Vec4(Vector4::new(1.0, 2.0, 3.0, 4.0))),
+            ("i", UniformData::Int(-3)),
+            ("u", UniformData::UInt(7)),
+            ("b", UniformData::Bool(true)),
+            ("m3", UniformData::Matrix3(Matrix3::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0))),
+            ("floats", UniformData::FloatArray(vec![0.5, 1.5, 2.5])),
+            ("points", UniformData::Vec2Array(vec![Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0)])),
+            ("ints", UniformData::IntArray(vec![4, 5]))
+        ]);
+        renderer.render();
+
+        let program = program_with_source(&gl, "uniform mat3 m3").expect("Expected the type's program");
+        let value = |name:&str| gl.uniform_value(program, name);
+        assert_eq!(value("v2"), Some(UniformValue::Vec2([1.0, 2.0])));
+        assert_eq!(value("v3"), Some(UniformValue::Vec3([1.0, 2.0, 3.0])));
+        assert_eq!(value("v4"), Some(UniformValue::Vec4([1.0, 2.0, 3.0, 4.0])));
+        assert_eq!(value("i"), Some(UniformValue::Int(-3)));
+        assert_eq!(value("u"), Some(UniformValue::UInt(7)));
+        assert_eq!(value("b"), Some(UniformValue::Int(1)));
+        assert_eq!(value("m3"), Some(UniformValue::Matrix3([1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0])));
+        assert_eq!(value("floats"), Some(UniformValue::FloatArray(vec![0.5, 1.5, 2.5])));
+        assert_eq!(value("points"), Some(UniformValue::FloatArray(vec![1.0, 2.0, 3.0, 4.0])));
+        assert_eq!(value("ints"), Some(UniformValue::IntArray(vec![4, 5])));
+    }
+}
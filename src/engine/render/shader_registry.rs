@@ -0,0 +1,120 @@
+use std::{collections::HashMap, rc::{Rc, Weak}};
+
+use super::{render_object::{RenderType, RenderTypeId}, program::{Program, ShaderError, create_program_from_src}, pick, backend::Backend};
+
+//render types by name, plus the programs linked for them. Render types with the same shader sources share one program,
+//which is deleted once no batcher draws with it anymore.
+#[derive(Debug)]
+pub struct ShaderRegistry {
+    types:HashMap<String,Rc<RenderType>>,
+    programs:HashMap<(String,String),Weak<Program>>,
+    errors:HashMap<RenderTypeId,ShaderError>
+}
+
+impl ShaderRegistry {
+    pub fn new() -> Self {
+        Self {
+            types:HashMap::new(),
+            programs:HashMap::new(),
+            errors:HashMap::new()
+        }
+    }
+
+    //registering a name again replaces its type, objects created with the old type keep drawing with it
+    pub fn register(&mut self, name:&str, render_type:RenderType) -> Rc<RenderType> {
+        let render_type = Rc::new(render_type);
+        self.types.insert(name.to_owned(), render_type.clone());
+        render_type
+    }
+
+    pub fn get(&self, name:&str) -> Option<Rc<RenderType>> {
+        self.types.get(name).cloned()
+    }
+
+    pub fn contains(&self, name:&str) -> bool {
+        self.types.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.types.keys()
+    }
+
+    pub fn remove(&mut self, name:&str) -> Option<Rc<RenderType>> {
+        self.types.remove(name)
+    }
+
+    //the last error from linking the render type's sources, cleared once a reload succeeds
+    pub fn error(&self, render_type:&RenderType) -> Option<&ShaderError> {
+        self.errors.get(&render_type.id())
+    }
+
+    //links the render type's program, or reuses the one linked for the same sources
+    pub(super) fn program(&mut self, gl:&Backend, render_type:&RenderType) -> Result<Rc<Program>,ShaderError> {
+        let key = (render_type.vertex_shader.borrow().clone(), render_type.fragment_shader.borrow().clone());
+        let result = match self.programs.get(&key).and_then(|x| x.upgrade()) {
+            Some(program) => render_type.check_program(gl.as_ref(), program.handle()).map(|_| program),
+            None => render_type.setup_program(gl).map(Rc::new)
+        };
+
+        match result {
+            Ok(program) => {
+                self.insert_program(key, &program);
+                Ok(program)
+            },
+            Err(e) => {
+                self.errors.insert(render_type.id(), e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    //program drawing the render type in magenta after its own failed to link
+    pub(super) fn fallback_program(&mut self, gl:&Backend, render_type:&RenderType) -> Result<Rc<Program>,ShaderError> {
+        let key = render_type.fallback_shaders();
+        if let Some(program) = self.programs.get(&key).and_then(|x| x.upgrade()) {
+            return Ok(program);
+        }
+
+        let handle = create_program_from_src(gl.as_ref(), &key.0, &key.1)?;
+        let program = Rc::new(Program::new(gl, handle));
+        self.insert_program(key, &program);
+        Ok(program)
+    }
+
+    //program writing pick ids, the render type's current vertex shader linked with its pick fragment shader
+    pub(super) fn pick_program(&mut self, gl:&Backend, render_type:&RenderType) -> Result<Rc<Program>,ShaderError> {
+        let key = (render_type.vertex_shader.borrow().clone(), pick::fragment_shader(render_type));
+        if let Some(program) = self.programs.get(&key).and_then(|x| x.upgrade()) {
+            return Ok(program);
+        }
+
+        let handle = create_program_from_src(gl.as_ref(), &key.0, &key.1)?;
+        let program = Rc::new(Program::new(gl, handle));
+        self.insert_program(key, &program);
+        Ok(program)
+    }
+
+    //swaps the render type's sources for new ones. On a compile, link or attribute error the type keeps its old
+    //sources and program. The render type's batcher has to be pointed at the returned program, see Renderer::reload_render_type
+    pub(super) fn reload(&mut self, gl:&Backend, render_type:&RenderType, vertex_shader:String, fragment_shader:String) -> Result<Rc<Program>,ShaderError> {
+        let key = (vertex_shader, fragment_shader);
+        let program = match self.programs.get(&key).and_then(|x| x.upgrade()) {
+            Some(program) => {
+                render_type.check_program(gl.as_ref(), program.handle())?;
+                program
+            },
+            None => Rc::new(render_type.setup_program_from_src(gl, &key.0, &key.1)?)
+        };
+
+        self.errors.remove(&render_type.id());
+        *render_type.vertex_shader.borrow_mut() = key.0.clone();
+        *render_type.fragment_shader.borrow_mut() = key.1.clone();
+        self.insert_program(key, &program);
+        Ok(program)
+    }
+
+    fn insert_program(&mut self, key:(String,String), program:&Rc<Program>) {
+        self.programs.retain(|_,x| x.strong_count() > 0);
+        self.programs.insert(key, Rc::downgrade(program));
+    }
+}
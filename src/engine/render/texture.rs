@@ -1,10 +1,18 @@
-use std::{collections::HashMap, cell::RefCell, rc::Rc, fmt::Debug};
+use std::{collections::HashMap, cell::{Cell, RefCell}, rc::{Rc, Weak}, fmt::Debug};
 
-use guillotiere::{AtlasAllocator, size2, Allocation};
+use guillotiere::{AtlasAllocator, AllocatorOptions, size2, Allocation};
 use wasm_bindgen::UnwrapThrowExt;
-use web_sys::{WebGlTexture, WebGl2RenderingContext, HtmlImageElement};
+use web_sys::{WebGl2RenderingContext, HtmlImageElement};
 
-//TODO- make it so that the number of instances cannot grow larger than the max provided, by merging them.
+use crate::log_str;
+
+use super::backend::{Backend, GraphicsBackend, TextureHandle};
+
+//mipmapped textures sharing an instance are padded by a gutter repeating their edges and aligned to it. It is one texel
+//at the last mip level they get, below which neighbouring allocations would be averaged together
+const MAX_MIP_LEVEL:i32 = 4;
+const GUTTER:i32 = 1 << MAX_MIP_LEVEL;
+const TEXTURE_MAX_ANISOTROPY_EXT:u32 = 0x84FE;
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 #[allow(unused)]
@@ -41,17 +49,40 @@ pub enum TextureFormat {
     // RGBA8UI
 } 
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+//the mipmapped filters sample the nearest mip level or blend the two nearest, each with the filter they start with.
+//They only apply when minifying, as a mag filter they act like that filter
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[allow(unused)]
 pub enum TextureFilter {
     Linear,
-    Nearest
+    Nearest,
+    LinearMipmapLinear,
+    LinearMipmapNearest,
+    NearestMipmapLinear,
+    NearestMipmapNearest
 }
 
 impl TextureFilter {
     fn to_wgl(&self) -> i32  {
         match self {
             Self::Linear => WebGl2RenderingContext::LINEAR as i32,
-            Self::Nearest => WebGl2RenderingContext::NEAREST as i32
+            Self::Nearest => WebGl2RenderingContext::NEAREST as i32,
+            Self::LinearMipmapLinear => WebGl2RenderingContext::LINEAR_MIPMAP_LINEAR as i32,
+            Self::LinearMipmapNearest => WebGl2RenderingContext::LINEAR_MIPMAP_NEAREST as i32,
+            Self::NearestMipmapLinear => WebGl2RenderingContext::NEAREST_MIPMAP_LINEAR as i32,
+            Self::NearestMipmapNearest => WebGl2RenderingContext::NEAREST_MIPMAP_NEAREST as i32
+        }
+    }
+
+    pub fn mipmapped(&self) -> bool {
+        !matches!(self, Self::Linear | Self::Nearest)
+    }
+
+    //the filter used within a mip level
+    pub fn without_mipmaps(&self) -> Self {
+        match self {
+            Self::Linear | Self::LinearMipmapLinear | Self::LinearMipmapNearest => Self::Linear,
+            Self::Nearest | Self::NearestMipmapLinear | Self::NearestMipmapNearest => Self::Nearest
         }
     }
 }
@@ -77,10 +108,17 @@ impl TextureFormat {
             Self::RGB | Self::RGBA => WebGl2RenderingContext::UNSIGNED_BYTE
         }
     }   
+
+    fn bytes_per_pixel(&self) -> usize {
+        match self {
+            Self::RGB => 3,
+            Self::RGBA => 4
+        }
+    }
 }
 
 pub trait BatchableTextureSource {
-    fn tex_sub_image_2d(&self, gl:&WebGl2RenderingContext, x:i32, y:i32);
+    fn tex_sub_image_2d(&self, gl:&dyn GraphicsBackend, x:i32, y:i32);
     fn width(&self) -> i32;
     fn height(&self) -> i32;
     fn format(&self) -> TextureFormat;
@@ -108,21 +146,18 @@ impl<'a> BatchableTextureSource for RawTextureSource<'a> {
     fn min_filter(&self) -> TextureFilter { self.min_filter }
     fn mag_filter(&self) -> TextureFilter { self.mag_filter }
     fn unique_texture(&self) -> bool { self.unique }
-    fn tex_sub_image_2d(&self, gl:&WebGl2RenderingContext, x:i32, y:i32) {
-        unsafe {
-            let buffer_view = js_sys::Uint8Array::view(&self.data);
-            gl.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
-                WebGl2RenderingContext::TEXTURE_2D, 
-                0, 
-                x, 
-                y, 
-                self.width, 
-                self.height, 
-                self.format.get_format(), 
-                self.format.get_type(), 
-                Some(&buffer_view)
-            ).expect_throw("Texture batcher upload failed.");
-        }
+    fn tex_sub_image_2d(&self, gl:&dyn GraphicsBackend, x:i32, y:i32) {
+        gl.tex_sub_image_2d(
+            WebGl2RenderingContext::TEXTURE_2D, 
+            0, 
+            x, 
+            y, 
+            self.width, 
+            self.height, 
+            self.format.get_format(), 
+            self.format.get_type(), 
+            self.data
+        );
     }
 }
 
@@ -156,7 +191,7 @@ impl BatchableTextureSource for TempBlankTextureSource {
     fn width(&self) -> i32 {
         self.width
     }
-    fn tex_sub_image_2d(&self, _:&WebGl2RenderingContext, _:i32, _:i32) {}
+    fn tex_sub_image_2d(&self, _:&dyn GraphicsBackend, _:i32, _:i32) {}
     fn valid(&self) -> bool {
         false
     }
@@ -185,18 +220,16 @@ impl BatchableTextureSource for ImageTextureSource {
     }
     fn min_filter(&self) -> TextureFilter { self.min_filter }
     fn mag_filter(&self) -> TextureFilter { self.mag_filter }
-    fn tex_sub_image_2d(&self, gl:&WebGl2RenderingContext, x:i32, y:i32) {
-        gl.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_html_image_element(
+    fn tex_sub_image_2d(&self, gl:&dyn GraphicsBackend, x:i32, y:i32) {
+        gl.tex_sub_image_2d_with_image(
             WebGl2RenderingContext::TEXTURE_2D, 
             0, 
             x, 
             y, 
-            self.image.width() as i32, 
-            self.image.height() as i32, 
             WebGl2RenderingContext::RGBA, 
             WebGl2RenderingContext::UNSIGNED_BYTE, 
             &self.image
-        ).expect_throw("Error uploading HtmlImageElement to texture");
+        );
     }
     fn unique_texture(&self) -> bool {
         self.unique
@@ -205,8 +238,11 @@ impl BatchableTextureSource for ImageTextureSource {
 
 pub struct BatchedTexture {
     remove_cache:Rc<RefCell<RemoveCache>>,
-    texture_id:u32,
-    allocation:Allocation,
+    placement_listeners:Rc<RefCell<PlacementListeners>>,
+    //shared with the instance holding it, which sets it to the one it is merged into
+    texture_id:Rc<Cell<u32>>,
+    //key of the allocation in its instance, which can move it when repacking
+    region:u32,
     loaded:bool,
     updating:bool
 }
@@ -215,8 +251,8 @@ impl Debug for BatchedTexture {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BatchedTexture")
         .field("remove_cache", &"..")
-        .field("texture_id", &self.texture_id)
-        .field("allocation", &self.allocation)
+        .field("texture_id", &self.texture_id.get())
+        .field("region", &self.region)
         .field("loaded", &self.loaded)
         .field("updating", &self.updating)
         .finish()
@@ -226,19 +262,33 @@ impl Debug for BatchedTexture {
 #[derive(Debug)]
 struct FutureRemovedBatchedTexture {
     texture_id:u32,
-    allocation:Allocation
+    region:u32
+}
+
+//told when a batched texture moves, which leaves texcoords from get_texcoord stale. Repacking, merging and updates
+//to another size or format move textures
+#[derive(Debug)]
+pub struct PlacementListener {
+    moved:Rc<Cell<bool>>
+}
+
+impl PlacementListener {
+    //whether the texture moved since the last call
+    pub fn moved(&self) -> bool {
+        self.moved.replace(false)
+    }
 }
 
 impl PartialEq for BatchedTexture {
     fn eq(&self, other: &Self) -> bool {
-        self.texture_id == other.texture_id && self.allocation == other.allocation && Rc::ptr_eq(&other.remove_cache, &self.remove_cache)
+        self.texture_id.get() == other.texture_id.get() && self.region == other.region && Rc::ptr_eq(&other.remove_cache, &self.remove_cache)
     }
 }
 
 impl Drop for BatchedTexture {
     fn drop(&mut self) {
         if self.updating { return; }
-        self.remove_cache.borrow_mut().remove(FutureRemovedBatchedTexture { texture_id: self.texture_id, allocation: self.allocation });
+        self.remove_cache.borrow_mut().remove(FutureRemovedBatchedTexture { texture_id: self.texture_id.get(), region: self.region });
     }
 }
 
@@ -255,8 +305,12 @@ impl BatchedTexture {
         return batcher.get_texcoord(self, x, y);
     }
 
+    pub fn listen(&self) -> PlacementListener {
+        self.placement_listeners.borrow_mut().listen(self.region)
+    }
+
     pub fn same_instance(&self, other:&Self) -> bool {
-        self.texture_id == other.texture_id
+        self.texture_id.get() == other.texture_id.get()
     }
 
     pub fn bind(&self, batcher:&TextureBatcher) {
@@ -315,33 +369,83 @@ impl RemoveCache {
 
     fn process(&mut self, batcher:&mut TextureBatcher) {
         for texture in self.inner.iter() {
-            batcher.remove(texture.texture_id,texture.allocation);
+            batcher.remove(texture.texture_id,texture.region);
+            batcher.placement_listeners.borrow_mut().remove(texture.region);
         }
         self.inner.clear();
     }
 }
 
+//listeners of each region, shared with the batched textures like the remove cache so they can be listened to
+//without the batcher
+#[derive(Debug)]
+struct PlacementListeners {
+    inner:HashMap<u32,Vec<Weak<Cell<bool>>>>
+}
+
+impl PlacementListeners {
+    fn new() -> Self {
+        Self { inner:HashMap::new() }
+    }
+
+    fn listen(&mut self, region:u32) -> PlacementListener {
+        let moved = Rc::new(Cell::new(false));
+        self.inner.entry(region).or_default().push(Rc::downgrade(&moved));
+        PlacementListener { moved:moved }
+    }
+
+    //drops the listeners that are gone
+    fn notify(&mut self, region:u32) {
+        if let Some(listeners) = self.inner.get_mut(&region) {
+            listeners.retain(|x| x.upgrade().map(|x| x.set(true)).is_some());
+        }
+    }
+
+    //the listeners of a texture added again under a new region
+    fn moved(&mut self, from:u32, to:u32) {
+        if let Some(listeners) = self.inner.remove(&from) {
+            self.inner.insert(to, listeners);
+            self.notify(to);
+        }
+    }
+
+    fn remove(&mut self, region:u32) {
+        self.inner.remove(&region);
+    }
+}
+
 #[derive(Debug)]
 pub struct TextureBatcher {
     instances:HashMap<u32,TextureBatcherInstance>,
     texture_remove_cache:Rc<RefCell<RemoveCache>>, //needs to be owned by every batched texture to allow adding to queue on drop
+    placement_listeners:Rc<RefCell<PlacementListeners>>,
     update_cache:Rc<RefCell<UpdateCache>>, //for asynchronous update operations, such as image loading
-    gl:WebGl2RenderingContext,
+    gl:Backend,
     last_instance_id:u32,
+    //region keys are unique across instances so merged regions keep theirs
+    last_region:u32,
     min_width:i32,
     min_height:i32,
+    //bytes the instances' textures may take, see set_memory_budget
+    memory_budget:Option<usize>,
+    //of mipmapped instances, None leaves it to the default
+    anisotropy:Option<f32>
 }
 
 impl TextureBatcher {    
-    pub fn new(gl:WebGl2RenderingContext, w:i32, h:i32 ) -> Self {
+    pub fn new(gl:Backend, w:i32, h:i32 ) -> Self {
         Self { 
             instances: HashMap::new(), 
             texture_remove_cache:Rc::new(RefCell::new(RemoveCache::new())),
+            placement_listeners:Rc::new(RefCell::new(PlacementListeners::new())),
             update_cache:Rc::new(RefCell::new(UpdateCache::new())),
             gl:gl,
             last_instance_id: 0, 
+            last_region: 0,
             min_width: w,
             min_height: h,
+            memory_budget: None,
+            anisotropy: None
         }
     }
 
@@ -356,90 +460,240 @@ impl TextureBatcher {
     pub fn cleanup(&mut self) {
         let cache = Rc::clone(&self.texture_remove_cache);
         cache.borrow_mut().process(self);
+        //removed textures can leave room to merge instances that are over the budget
+        self.merge(0);
     }
 
     pub fn update(&mut self) {
         let cache = Rc::clone(&self.update_cache);
         cache.borrow_mut().process(self);
+        //mips are regenerated once for everything changed since the last update, mipmapped textures are incomplete
+        //without them
+        let gl = self.gl.clone();
+        for instance in self.instances.values_mut() {
+            instance.generate_mipmap(gl.as_ref());
+        }
+    }
+
+    //anisotropic filtering of mipmapped textures, from 1 for none up to what the backend allows. Does nothing
+    //without EXT_texture_filter_anisotropic
+    pub fn set_anisotropy(&mut self, anisotropy:f32) {
+        let max = match self.gl.max_anisotropy() {
+            Some(max) => max,
+            None => return
+        };
+        let anisotropy = Some(anisotropy.max(1.0).min(max));
+        self.anisotropy = anisotropy;
+        let gl = self.gl.clone();
+        for instance in self.instances.values_mut() {
+            instance.anisotropy = anisotropy;
+            instance.bind(gl.as_ref(), WebGl2RenderingContext::TEXTURE_2D);
+            instance.configure_texture(gl.as_ref());
+        }
+    }
+
+    //bytes taken by the textures of every instance
+    pub fn memory_usage(&self) -> usize {
+        self.instances.values().map(|x| x.bytes()).sum()
+    }
+
+    //once a new instance would go over the budget, sparse instances are merged into others to make room and then the
+    //new one is made smaller, as long as the texture still fits. Textures too large for that still get an instance,
+    //which is logged. None leaves it unlimited
+    pub fn set_memory_budget(&mut self, budget:Option<usize>) {
+        self.memory_budget = budget;
+        self.cleanup();
     }
 
     fn add(&mut self, src:&dyn BatchableTextureSource) -> BatchedTexture {
         self.cleanup();
         let gl = self.gl.clone();
         let unique = src.unique_texture();
+        self.last_region += 1;
 
         //try to add into existing instance
         if !unique {
-            for (instance_id,instance) in self.instances.iter_mut() {
-                if let Some(batched_texture) = instance.add(self.texture_remove_cache.clone(), &gl, src, *instance_id) {
+            if let Some(batched_texture) = self.add_to_existing(src) {
+                return batched_texture;
+            }
+        }
+
+        let (padded_width, padded_height) = padded_size(src);
+        let (mut width, mut height) = match unique {
+            true => (src.width(), src.height()),
+            false => (i32::max(self.min_width, padded_width), i32::max(self.min_height, padded_height))
+        };
+        let bytes = |width:i32, height:i32| texture_bytes(width, height, src.format(), src.min_filter());
+
+        //merging can also leave room for the texture in the instance merged into
+        if self.over_budget(bytes(width, height)) {
+            self.merge(bytes(width, height));
+            if !unique {
+                if let Some(batched_texture) = self.add_to_existing(src) {
                     return batched_texture;
                 }
             }
         }
+        while !unique && self.over_budget(bytes(width, height)) && width / 2 >= padded_width && height / 2 >= padded_height {
+            width /= 2;
+            height /= 2;
+        }
+        if self.over_budget(bytes(width, height)) {
+            log_str(&format!("Texture memory budget exceeded by a new {}x{} batch texture", width, height));
+        }
 
         //create new instance
         self.last_instance_id += 1;
 
         let mut new_instance = TextureBatcherInstance::new(
-            &gl, 
+            gl.as_ref(), 
+            self.last_instance_id,
             src.format(), 
             src.min_filter(),
             src.mag_filter(),
-            if unique { src.width() } else { i32::max(self.min_width, src.width()) },
-            if unique { src.height() } else { i32::max(self.min_height, src.height()) }
+            width,
+            height,
+            unique,
+            self.anisotropy
         );
 
-        let result = new_instance.add(self.texture_remove_cache.clone(), &gl, src, self.last_instance_id).expect("Expected new texture batcher instance to succesfully allocate");
+        let result = new_instance.add(self.texture_remove_cache.clone(), self.placement_listeners.clone(), gl.as_ref(), src, self.last_region).expect("Expected new texture batcher instance to succesfully allocate");
 
         self.instances.insert(self.last_instance_id, new_instance);
         
         result
     }
 
+    fn add_to_existing(&mut self, src:&dyn BatchableTextureSource) -> Option<BatchedTexture> {
+        let gl = self.gl.clone();
+        let (remove_cache, listeners, region) = (&self.texture_remove_cache, &self.placement_listeners, self.last_region);
+        self.instances.values_mut().filter(|x| !x.unique).find_map(|x| x.add(remove_cache.clone(), listeners.clone(), gl.as_ref(), src, region))
+    }
+
+    fn over_budget(&self, bytes:usize) -> bool {
+        self.memory_budget.map_or(false, |budget| self.memory_usage() + bytes > budget)
+    }
+
+    //merges instances until bytes more fit in the budget or nothing else can be merged
+    fn merge(&mut self, bytes:usize) {
+        while self.over_budget(bytes) && self.merge_sparsest() {}
+    }
+
+    //moves the instance with the least in it into the fullest one with room for all of it
+    fn merge_sparsest(&mut self) -> bool {
+        let gl = self.gl.clone();
+        let mut ids:Vec<u32> = self.instances.iter().filter(|(_, x)| !x.unique).map(|(id, _)| *id).collect();
+        ids.sort_by_key(|id| self.instances[id].used_area());
+        for src in ids.iter() {
+            let mut source = self.instances.remove(src).expect_throw("Expected texture ID to be valid while merging");
+            let regions:Vec<u32> = source.regions.keys().copied().collect();
+            for dst in ids.iter().rev().filter(|x| *x != src) {
+                if self.instances.get_mut(dst).expect_throw("Expected texture ID to be valid while merging").merge(gl.as_ref(), &mut source) {
+                    for region in regions {
+                        self.placement_listeners.borrow_mut().notify(region);
+                    }
+                    return true;
+                }
+            }
+            self.instances.insert(*src, source);
+        }
+        false
+    }
+
+    //repacks every instance holding more than one texture, see TextureBatcherInstance::repack. Unique textures, like the
+    //color attachments of render targets, have an instance of their own and are never moved by this or merging.
+    //Render objects using moved textures have to update their texcoords, which MappedTexture::listen tells
+    pub fn repack(&mut self) {
+        self.cleanup();
+        let gl = self.gl.clone();
+        for (id, instance) in self.instances.iter_mut().filter(|(_, x)| x.regions.len() > 1 && !x.unique) {
+            if !instance.repack(gl.as_ref()) {
+                log_str(&format!("Texture batcher instance {} could not be repacked", id));
+                continue;
+            }
+            for region in instance.regions.keys() {
+                self.placement_listeners.borrow_mut().notify(*region);
+            }
+        }
+    }
+
     fn bind(&self, batched_texture:&BatchedTexture) {
-        self.instances.get(&batched_texture.texture_id).expect_throw("Expected texture ID to be valid while binding").bind(&self.gl.clone(),WebGl2RenderingContext::TEXTURE_2D)
+        self.instances.get(&batched_texture.texture_id.get()).expect_throw("Expected texture ID to be valid while binding").bind(self.gl.as_ref(),WebGl2RenderingContext::TEXTURE_2D)
+    }
+
+    pub(super) fn texture_handle(&self, batched_texture:&BatchedTexture) -> TextureHandle {
+        self.instances.get(&batched_texture.texture_id.get()).expect_throw("Expected texture ID to be valid while getting its handle").texture
     }
 
     fn get_texcoord(&self, batched_texture:&BatchedTexture, x:f32, y:f32) -> (f32,f32) {
-        self.instances.get(&batched_texture.texture_id).expect_throw("Expected texture ID to be valid while adjusting texcoord")
-        .adjust_texture_coord(&batched_texture.allocation, x, y)
+        let instance = self.instances.get(&batched_texture.texture_id.get()).expect_throw("Expected texture ID to be valid while adjusting texcoord");
+        instance.adjust_texture_coord(instance.region(batched_texture.region), x, y)
     }
 
     fn update_batched(&mut self, batched_texture:&mut BatchedTexture, src:&dyn BatchableTextureSource) {
         batched_texture.updating = true;
 
-        let id = batched_texture.texture_id;
+        let id = batched_texture.texture_id.get();
         let gl = self.gl.clone();
         let instance = self.instances.get(&id).expect_throw("Expected texture ID to be valid while updating");
+        let region = *instance.region(batched_texture.region);
         
-        if src.width() != batched_texture.allocation.rectangle.width() ||
-            src.height() != batched_texture.allocation.rectangle.height() ||
-            src.format() != instance.format {
-                //called to save space for differently sized texture
+        if src.width() != region.width ||
+            src.height() != region.height ||
+            src.format() != instance.format ||
+            src.min_filter() != instance.min_filter ||
+            src.mag_filter() != instance.mag_filter {
+                //called to save space for differently sized texture, can merge the instance away
                 self.cleanup();
                 //do it this way to remove old texture before adding new one, cannot assign by dereference because that would cause a drop,
                 //which would try to remove the allocation twice, which might panic(untested)
-                self.remove(batched_texture.texture_id, batched_texture.allocation);
+                let region = batched_texture.region;
+                self.remove(batched_texture.texture_id.get(), region);
                 *batched_texture = self.add(src);
+                self.placement_listeners.borrow_mut().moved(region, batched_texture.region);
         } else {
-            instance.update_batched(&gl, &batched_texture.allocation, src);
+            self.instances.get_mut(&id).expect_throw("Expected texture ID to be valid while updating").upload(gl.as_ref(), &region, src);
             batched_texture.loaded = src.valid();
         }
 
         batched_texture.updating = false;
     }
 
-    fn remove(&mut self, id:u32, allocation:Allocation) {
-        self.instances.get_mut(&id).expect_throw("Expected texture ID to be valid while removing").remove(allocation);
+    fn remove(&mut self, id:u32, region:u32) {
+        let instance = self.instances.get_mut(&id).expect_throw("Expected texture ID to be valid while removing");
+        instance.remove(region);
+        if instance.empty() {
+            self.gl.delete_texture(instance.texture);
+            self.instances.remove(&id);
+        }
     }
 }
 
+//an allocation and the texture in it, which is inset by the gutter in mipmapped instances
+#[derive(Clone, Copy, Debug)]
+struct Region {
+    allocation:Allocation,
+    width:i32,
+    height:i32
+}
+
 struct TextureBatcherInstance {
     atlas:AtlasAllocator,
-    texture:WebGlTexture,
+    texture:TextureHandle,
     width:i32,
     height:i32,
     format:TextureFormat,
+    min_filter:TextureFilter,
+    mag_filter:TextureFilter,
+    //holds a single texture that is never moved, like a render target's color attachment
+    unique:bool,
+    //its own id first, then those of the instances merged into it, shared with their batched textures
+    ids:Vec<Rc<Cell<u32>>>,
+    //batched textures by region
+    regions:HashMap<u32,Region>,
+    anisotropy:Option<f32>,
+    //set by every change to the texture, mips are only regenerated by update
+    mips_dirty:bool
 }
 
 impl Debug for TextureBatcherInstance {
@@ -450,52 +704,66 @@ impl Debug for TextureBatcherInstance {
         .field("width", &self.width)
         .field("height", &self.height)
         .field("format", &self.format)
+        .field("unique", &self.unique)
+        .field("regions", &self.regions.len())
         .finish()
     }
 }
 
 impl TextureBatcherInstance { 
-    fn new(gl:&WebGl2RenderingContext,format:TextureFormat, min_filter:TextureFilter, mag_filter:TextureFilter, width:i32,height:i32) -> Self {
-        let texture = gl.create_texture().expect_throw("Render Error: Unable to create instance of texture batcher");
-        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
-        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-            WebGl2RenderingContext::TEXTURE_2D, 
-            0, 
-            format.get_internal_format(), 
-            width, 
-            height, 
-            0, 
-            format.get_format(),
-            format.get_type(), 
-            None
-        ).expect_throw("Error uploading initial data to TextureBatcher GlTexture");
-        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_MIN_FILTER, min_filter.to_wgl());
-        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_MAG_FILTER, mag_filter.to_wgl());
-        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_WRAP_S, WebGl2RenderingContext::CLAMP_TO_EDGE as i32);
-        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_WRAP_T, WebGl2RenderingContext::CLAMP_TO_EDGE as i32);
-
-        Self { 
-            atlas: AtlasAllocator::new(size2(width, height)),
-            texture: texture, 
+    fn new(gl:&dyn GraphicsBackend, id:u32, format:TextureFormat, min_filter:TextureFilter, mag_filter:TextureFilter, width:i32, height:i32, unique:bool, anisotropy:Option<f32>) -> Self {
+        let instance = Self { 
+            atlas: create_atlas(width, height, min_filter.mipmapped() && !unique),
+            texture: create_texture(gl, format, min_filter, mag_filter, width, height), 
             width: width,
             height: height,
             format: format,
+            min_filter:min_filter,
+            mag_filter:mag_filter,
+            unique:unique,
+            ids:vec![Rc::new(Cell::new(id))],
+            regions:HashMap::new(),
+            anisotropy:anisotropy,
+            mips_dirty:min_filter.mipmapped()
+        };
+        instance.configure_texture(gl);
+        instance
+    }
+
+    fn region(&self, region:u32) -> &Region {
+        self.regions.get(&region).expect_throw("Expected texture region to be valid")
+    }
+
+    fn gutter(&self) -> i32 {
+        if self.min_filter.mipmapped() && !self.unique { GUTTER } else { 0 }
+    }
+
+    //mip and anisotropy parameters of the bound texture
+    fn configure_texture(&self, gl:&dyn GraphicsBackend) {
+        if self.gutter() > 0 {
+            gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_MAX_LEVEL, MAX_MIP_LEVEL);
+        }
+        if let (true, Some(anisotropy)) = (self.min_filter.mipmapped(), self.anisotropy) {
+            gl.tex_parameterf(WebGl2RenderingContext::TEXTURE_2D, TEXTURE_MAX_ANISOTROPY_EXT, anisotropy);
         }
     }
 
-    fn add(&mut self, remove_cache:Rc<RefCell<RemoveCache>>, gl:&WebGl2RenderingContext, src:&dyn BatchableTextureSource, instance_id:u32) -> Option<BatchedTexture> {
-        let (height,width,format) = (src.height(),src.width(),src.format());
-        if !(format == self.format && width <= self.width && height <= self.height) {return None;}
+    fn add(&mut self, remove_cache:Rc<RefCell<RemoveCache>>, placement_listeners:Rc<RefCell<PlacementListeners>>, gl:&dyn GraphicsBackend, src:&dyn BatchableTextureSource, region:u32) -> Option<BatchedTexture> {
+        let gutter = self.gutter();
+        let (height,width,format) = (src.height() + 2 * gutter,src.width() + 2 * gutter,src.format());
+        if !(format == self.format && src.min_filter() == self.min_filter && src.mag_filter() == self.mag_filter && width <= self.width && height <= self.height) {return None;}
 
         if let Some(allocation) = self.atlas.allocate(size2(width, height)) {
-            gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
-            let p = allocation.rectangle.min;
-            src.tex_sub_image_2d(gl, p.x, p.y);
+            let added = Region { allocation:allocation, width:src.width(), height:src.height() };
+            self.upload(gl, &added, src);
+
+            self.regions.insert(region, added);
 
             let result = BatchedTexture { 
                 remove_cache: remove_cache,
-                texture_id: instance_id,
-                allocation: allocation,
+                placement_listeners: placement_listeners,
+                texture_id: self.ids[0].clone(),
+                region: region,
                 loaded:src.valid(),
                 updating:false
             };
@@ -506,30 +774,357 @@ impl TextureBatcherInstance {
         }
     }
 
-    fn update_batched(&self, gl:&WebGl2RenderingContext, allocation:&Allocation, src:&dyn BatchableTextureSource) {
-        let p = allocation.rectangle.min;
-        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
-        src.tex_sub_image_2d(gl, p.x, p.y);
+    fn upload(&mut self, gl:&dyn GraphicsBackend, region:&Region, src:&dyn BatchableTextureSource) {
+        let (x, y) = self.content_min(region);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(self.texture));
+        src.tex_sub_image_2d(gl, x, y);
+        if self.gutter() > 0 {
+            self.fill_gutter(gl, region);
+        }
+        self.mips_dirty = self.min_filter.mipmapped();
+    }
+
+    //repeats the edges of the texture in region out to the edges of its allocation. The atlas can't be copied
+    //within, so its rows are put together with their left and right gutters in another texture first
+    fn fill_gutter(&self, gl:&dyn GraphicsBackend, region:&Region) {
+        let (gutter, (x, y), (width, height)) = (self.gutter(), self.content_min(region), (region.width, region.height));
+        let rows = create_texture(gl, self.format, TextureFilter::Nearest, TextureFilter::Nearest, width + 2 * gutter, height);
+        let framebuffer = gl.create_framebuffer();
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(framebuffer));
+        gl.framebuffer_texture_2d(WebGl2RenderingContext::FRAMEBUFFER, WebGl2RenderingContext::COLOR_ATTACHMENT0, WebGl2RenderingContext::TEXTURE_2D, Some(self.texture), 0);
+        gl.copy_tex_sub_image_2d(WebGl2RenderingContext::TEXTURE_2D, 0, gutter, 0, x, y, width, height);
+        for i in 0..gutter {
+            gl.copy_tex_sub_image_2d(WebGl2RenderingContext::TEXTURE_2D, 0, i, 0, x, y, 1, height);
+            gl.copy_tex_sub_image_2d(WebGl2RenderingContext::TEXTURE_2D, 0, gutter + width + i, 0, x + width - 1, y, 1, height);
+        }
+
+        gl.framebuffer_texture_2d(WebGl2RenderingContext::FRAMEBUFFER, WebGl2RenderingContext::COLOR_ATTACHMENT0, WebGl2RenderingContext::TEXTURE_2D, Some(rows), 0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(self.texture));
+        gl.copy_tex_sub_image_2d(WebGl2RenderingContext::TEXTURE_2D, 0, x - gutter, y, 0, 0, width + 2 * gutter, height);
+        for i in 0..gutter {
+            gl.copy_tex_sub_image_2d(WebGl2RenderingContext::TEXTURE_2D, 0, x - gutter, y - gutter + i, 0, 0, width + 2 * gutter, 1);
+            gl.copy_tex_sub_image_2d(WebGl2RenderingContext::TEXTURE_2D, 0, x - gutter, y + height + i, 0, height - 1, width + 2 * gutter, 1);
+        }
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        gl.delete_framebuffer(framebuffer);
+        gl.delete_texture(rows);
+    }
+
+    fn generate_mipmap(&mut self, gl:&dyn GraphicsBackend) {
+        if !self.mips_dirty { return; }
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(self.texture));
+        gl.generate_mipmap(WebGl2RenderingContext::TEXTURE_2D);
+        self.mips_dirty = false;
     }
 
-    fn bind(&self, gl:&WebGl2RenderingContext, target:u32) {
-        gl.bind_texture(target, Some(&self.texture));
+    fn content_min(&self, region:&Region) -> (i32,i32) {
+        let min = region.allocation.rectangle.min;
+        (min.x + self.gutter(), min.y + self.gutter())
     }
 
-    fn adjust_texture_coord(&self, allocation:&Allocation, x:f32, y:f32) -> (f32,f32)  {
-        let min = allocation.rectangle.min;
+    fn bind(&self, gl:&dyn GraphicsBackend, target:u32) {
+        gl.bind_texture(target, Some(self.texture));
+    }
+
+    fn adjust_texture_coord(&self, region:&Region, x:f32, y:f32) -> (f32,f32)  {
+        let (min_x, min_y) = self.content_min(region);
 
         (
-            (min.x as f32 + x*allocation.rectangle.width() as f32) / self.width as f32, 
-            (min.y as f32 + y*allocation.rectangle.height() as f32) / self.height as f32
+            (min_x as f32 + x*region.width as f32) / self.width as f32, 
+            (min_y as f32 + y*region.height as f32) / self.height as f32
         )
     }
 
-    fn remove(&mut self, allocation:Allocation) {
-        self.atlas.deallocate(allocation.id);
+    fn remove(&mut self, region:u32) {
+        if let Some(removed) = self.regions.remove(&region) {
+            self.atlas.deallocate(removed.allocation.id);
+        }
+    }
+
+    //allocates the regions again in a fresh atlas from the largest down, then copies them on the gpu into a new
+    //texture through a framebuffer reading the old one. Returns false and changes nothing if they don't all fit
+    fn repack(&mut self, gl:&dyn GraphicsBackend) -> bool {
+        let mut regions:Vec<(u32,Allocation)> = self.regions.iter().map(|(region, x)| (*region, x.allocation)).collect();
+        regions.sort_by_key(|(_, x)| -x.rectangle.area());
+        let mut atlas = create_atlas(self.width, self.height, self.gutter() > 0);
+        let mut moved = Vec::with_capacity(regions.len());
+        for (region, old) in regions {
+            match atlas.allocate(old.rectangle.size()) {
+                Some(new) => moved.push((region, old, new)),
+                None => return false
+            }
+        }
+
+        let texture = create_texture(gl, self.format, self.min_filter, self.mag_filter, self.width, self.height);
+        self.configure_texture(gl);
+        copy_regions(gl, self.texture, &moved);
+        gl.delete_texture(self.texture);
+
+        self.texture = texture;
+        self.atlas = atlas;
+        self.regions = moved.into_iter().map(|(region, _, new)| (region, Region { allocation:new, ..self.regions[&region] })).collect();
+        self.mips_dirty = self.min_filter.mipmapped();
+        true
+    }
+
+    //moves every region of other into free space here and deletes its texture. Returns false and leaves other as it
+    //was if they don't all fit
+    fn merge(&mut self, gl:&dyn GraphicsBackend, other:&mut Self) -> bool {
+        if other.format != self.format || other.min_filter != self.min_filter || other.mag_filter != self.mag_filter { return false; }
+        let mut regions:Vec<(u32,Allocation)> = other.regions.iter().map(|(region, x)| (*region, x.allocation)).collect();
+        regions.sort_by_key(|(_, x)| -x.rectangle.area());
+        let mut moved = Vec::with_capacity(regions.len());
+        for (region, old) in regions {
+            match self.atlas.allocate(old.rectangle.size()) {
+                Some(new) => moved.push((region, old, new)),
+                None => {
+                    for (_, _, new) in moved {
+                        self.atlas.deallocate(new.id);
+                    }
+                    return false;
+                }
+            }
+        }
+
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(self.texture));
+        copy_regions(gl, other.texture, &moved);
+        gl.delete_texture(other.texture);
+
+        let id = self.ids[0].get();
+        for texture_id in other.ids.drain(..) {
+            texture_id.set(id);
+            self.ids.push(texture_id);
+        }
+        self.regions.extend(moved.into_iter().map(|(region, _, new)| (region, Region { allocation:new, ..other.regions[&region] })));
+        other.regions.clear();
+        self.mips_dirty = self.min_filter.mipmapped();
+        true
+    }
+
+    fn used_area(&self) -> i32 {
+        self.regions.values().map(|x| x.allocation.rectangle.area()).sum()
+    }
+
+    fn bytes(&self) -> usize {
+        texture_bytes(self.width, self.height, self.format, self.min_filter)
     }
 
     fn empty(&self) -> bool {
         self.atlas.is_empty()
     }
-}
\ No newline at end of file
+}
+
+//mipmapped instances shared by several textures align allocations to the gutter
+fn create_atlas(width:i32, height:i32, aligned:bool) -> AtlasAllocator {
+    let alignment = if aligned { GUTTER } else { 1 };
+    AtlasAllocator::with_options(size2(width, height), &AllocatorOptions { alignment:size2(alignment, alignment), ..AllocatorOptions::default() })
+}
+
+//size src takes in an instance it shares
+fn padded_size(src:&dyn BatchableTextureSource) -> (i32,i32) {
+    match src.min_filter().mipmapped() {
+        true => (aligned(src.width() + 2 * GUTTER), aligned(src.height() + 2 * GUTTER)),
+        false => (src.width(), src.height())
+    }
+}
+
+fn aligned(x:i32) -> i32 {
+    (x + GUTTER - 1) / GUTTER * GUTTER
+}
+
+fn texture_bytes(width:i32, height:i32, format:TextureFormat, min_filter:TextureFilter) -> usize {
+    let bytes = width as usize * height as usize * format.bytes_per_pixel();
+    //the mip levels add up to a third more
+    if min_filter.mipmapped() { bytes * 4 / 3 } else { bytes }
+}
+
+//copies regions from their old place in src to their new one in the bound texture through a framebuffer reading src
+fn copy_regions(gl:&dyn GraphicsBackend, src:TextureHandle, regions:&[(u32,Allocation,Allocation)]) {
+    let framebuffer = gl.create_framebuffer();
+    gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(framebuffer));
+    gl.framebuffer_texture_2d(WebGl2RenderingContext::FRAMEBUFFER, WebGl2RenderingContext::COLOR_ATTACHMENT0, WebGl2RenderingContext::TEXTURE_2D, Some(src), 0);
+    for (_, old, new) in regions.iter() {
+        let (from, to) = (old.rectangle.min, new.rectangle.min);
+        gl.copy_tex_sub_image_2d(WebGl2RenderingContext::TEXTURE_2D, 0, to.x, to.y, from.x, from.y, old.rectangle.width(), old.rectangle.height());
+    }
+    gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    gl.delete_framebuffer(framebuffer);
+}
+
+//leaves the new texture bound
+fn create_texture(gl:&dyn GraphicsBackend, format:TextureFormat, min_filter:TextureFilter, mag_filter:TextureFilter, width:i32, height:i32) -> TextureHandle {
+    let texture = gl.create_texture();
+    gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(texture));
+    gl.tex_image_2d(
+        WebGl2RenderingContext::TEXTURE_2D, 
+        0, 
+        format.get_internal_format(), 
+        width, 
+        height, 
+        format.get_format(),
+        format.get_type(), 
+        None
+    );
+    gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_MIN_FILTER, min_filter.to_wgl());
+    gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_MAG_FILTER, mag_filter.without_mipmaps().to_wgl());
+    gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_WRAP_S, WebGl2RenderingContext::CLAMP_TO_EDGE as i32);
+    gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, WebGl2RenderingContext::TEXTURE_WRAP_T, WebGl2RenderingContext::CLAMP_TO_EDGE as i32);
+    texture
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::{TextureBatcher, BatchedTexture, RawTextureSource, TextureFormat, TextureFilter, GUTTER, TEXTURE_MAX_ANISOTROPY_EXT};
+    use crate::engine::render::backend::recording::{RecordingBackend, GlCall};
+
+    //texels that differ by position, so moved contents can be told apart
+    fn texels(width:i32, height:i32, value:u8) -> Vec<u8> {
+        (0..height).flat_map(|y| (0..width).flat_map(move |x| vec![value, x as u8, y as u8, 255])).collect()
+    }
+
+    fn source(data:&[u8], width:i32, height:i32, min_filter:TextureFilter) -> RawTextureSource<'_> {
+        RawTextureSource { data:data, format:TextureFormat::RGBA, min_filter:min_filter, mag_filter:TextureFilter::Nearest, width:width, height:height, unique:false }
+    }
+
+    //width by height texels of the instance texture starting x, y from where texture's content starts
+    fn read(gl:&RecordingBackend, batcher:&TextureBatcher, texture:&BatchedTexture, x:i32, y:i32, width:i32, height:i32) -> Vec<u8> {
+        let state = gl.state();
+        let stored = &state.textures[&batcher.texture_handle(texture)];
+        let (u, v) = texture.get_texcoord(batcher, 0.0, 0.0);
+        let (x, y) = ((u * stored.width as f32).round() as i32 + x, (v * stored.height as f32).round() as i32 + y);
+        (y..y+height).flat_map(|row| {
+            let start = ((row * stored.width + x) * 4) as usize;
+            stored.pixels[start..start+width as usize*4].to_vec()
+        }).collect()
+    }
+
+    fn contents(gl:&RecordingBackend, batcher:&TextureBatcher, texture:&BatchedTexture, width:i32, height:i32) -> Vec<u8> {
+        read(gl, batcher, texture, 0, 0, width, height)
+    }
+
+    #[test]
+    fn repacking_keeps_contents_and_tells_listeners() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut batcher = TextureBatcher::new(gl.clone(), 16, 16);
+        let (a, b, c) = (texels(8, 8, 1), texels(8, 8, 2), texels(8, 8, 3));
+        let a = BatchedTexture::new(&mut batcher, &source(&a, 8, 8, TextureFilter::Nearest));
+        let b = BatchedTexture::new(&mut batcher, &source(&b, 8, 8, TextureFilter::Nearest));
+        let c = BatchedTexture::new(&mut batcher, &source(&c, 8, 8, TextureFilter::Nearest));
+        assert!(a.same_instance(&b) && b.same_instance(&c));
+        let (b_moved, c_moved) = (b.listen(), c.listen());
+        assert!(!b_moved.moved());
+
+        drop(a);
+        batcher.repack();
+
+        assert!(b_moved.moved() && c_moved.moved());
+        assert!(!b_moved.moved());
+        assert_eq!(contents(&gl, &batcher, &b, 8, 8), texels(8, 8, 2));
+        assert_eq!(contents(&gl, &batcher, &c, 8, 8), texels(8, 8, 3));
+    }
+
+    #[test]
+    fn merges_sparse_instances_to_fit_the_memory_budget() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut batcher = TextureBatcher::new(gl.clone(), 16, 16);
+        let (x, w, y) = (texels(16, 12, 1), texels(4, 4, 2), texels(8, 8, 3));
+        let x = BatchedTexture::new(&mut batcher, &source(&x, 16, 12, TextureFilter::Nearest));
+        let w = BatchedTexture::new(&mut batcher, &source(&w, 4, 4, TextureFilter::Nearest));
+        let y = BatchedTexture::new(&mut batcher, &source(&y, 8, 8, TextureFilter::Nearest));
+        assert!(x.same_instance(&w) && !w.same_instance(&y));
+        assert_eq!(batcher.memory_usage(), 2 * 16 * 16 * 4);
+        let (w_moved, y_moved) = (w.listen(), y.listen());
+
+        drop(x);
+        batcher.set_memory_budget(Some(16 * 16 * 4));
+
+        assert_eq!(batcher.memory_usage(), 16 * 16 * 4);
+        assert!(w.same_instance(&y));
+        assert!(w_moved.moved() && !y_moved.moved());
+        assert_eq!(contents(&gl, &batcher, &w, 4, 4), texels(4, 4, 2));
+        assert_eq!(contents(&gl, &batcher, &y, 8, 8), texels(8, 8, 3));
+    }
+
+    #[test]
+    fn shrinks_new_instances_to_fit_the_memory_budget() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut batcher = TextureBatcher::new(gl.clone(), 16, 16);
+        batcher.set_memory_budget(Some(512));
+        let data = texels(8, 8, 1);
+        let texture = BatchedTexture::new(&mut batcher, &source(&data, 8, 8, TextureFilter::Nearest));
+
+        assert_eq!(batcher.memory_usage(), 8 * 8 * 4);
+        assert_eq!(gl.state().textures[&batcher.texture_handle(&texture)].width, 8);
+        assert_eq!(contents(&gl, &batcher, &texture, 8, 8), data);
+    }
+
+    #[test]
+    fn gutters_repeat_the_edge_texels() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut batcher = TextureBatcher::new(gl.clone(), 16, 16);
+        let data = texels(3, 2, 1);
+        let texture = BatchedTexture::new(&mut batcher, &source(&data, 3, 2, TextureFilter::LinearMipmapLinear));
+
+        let around = read(&gl, &batcher, &texture, -GUTTER, -GUTTER, 3 + 2 * GUTTER, 2 + 2 * GUTTER);
+        let expected:Vec<u8> = (-GUTTER..2+GUTTER).flat_map(|y| (-GUTTER..3+GUTTER).flat_map(move |x| vec![1, x.clamp(0, 2) as u8, y.clamp(0, 1) as u8, 255])).collect();
+        assert_eq!(around, expected);
+
+        gl.take_calls();
+        batcher.update();
+        assert!(gl.calls().iter().any(|x| matches!(x, GlCall::GenerateMipmap(_))));
+        gl.take_calls();
+        batcher.update();
+        assert!(!gl.calls().iter().any(|x| matches!(x, GlCall::GenerateMipmap(_))));
+    }
+
+    #[test]
+    fn anisotropy_is_clamped_to_the_backend_max() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        gl.set_max_anisotropy(Some(8.0));
+        let mut batcher = TextureBatcher::new(gl.clone(), 16, 16);
+        let (small, wide) = (texels(4, 4, 1), texels(64, 4, 2));
+        let before = BatchedTexture::new(&mut batcher, &source(&small, 4, 4, TextureFilter::LinearMipmapLinear));
+
+        batcher.set_anisotropy(16.0);
+        let after = BatchedTexture::new(&mut batcher, &source(&wide, 64, 4, TextureFilter::LinearMipmapLinear));
+
+        let anisotropy = |texture:&BatchedTexture| gl.state().textures[&batcher.texture_handle(texture)].float_parameters.get(&TEXTURE_MAX_ANISOTROPY_EXT).copied();
+        assert!(!before.same_instance(&after));
+        assert_eq!(anisotropy(&before), Some(8.0));
+        assert_eq!(anisotropy(&after), Some(8.0));
+    }
+
+    #[test]
+    fn anisotropy_needs_the_extension() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut batcher = TextureBatcher::new(gl.clone(), 16, 16);
+        let data = texels(4, 4, 1);
+        let _texture = BatchedTexture::new(&mut batcher, &source(&data, 4, 4, TextureFilter::LinearMipmapLinear));
+        gl.take_calls();
+
+        batcher.set_anisotropy(4.0);
+
+        assert!(gl.calls().is_empty());
+    }
+
+    #[test]
+    fn updating_to_another_size_moves_the_texture() {
+        let gl = Rc::new(RecordingBackend::new(64, 64));
+        let mut batcher = TextureBatcher::new(gl.clone(), 16, 16);
+        let (a, b, resized) = (texels(8, 8, 1), texels(8, 8, 2), texels(4, 4, 3));
+        let mut a = BatchedTexture::new(&mut batcher, &source(&a, 8, 8, TextureFilter::Nearest));
+        let _b = BatchedTexture::new(&mut batcher, &source(&b, 8, 8, TextureFilter::Nearest));
+        let moved = a.listen();
+
+        a.update(&mut batcher, &source(&resized, 4, 4, TextureFilter::Nearest));
+        assert!(moved.moved());
+        assert_eq!(contents(&gl, &batcher, &a, 4, 4), resized);
+
+        //the listener follows the texture to its new region
+        batcher.repack();
+        assert!(moved.moved());
+        assert_eq!(contents(&gl, &batcher, &a, 4, 4), resized);
+    }
+}
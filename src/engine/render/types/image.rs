@@ -1,16 +1,20 @@
-use std::rc::Rc;
-
 use cgmath::{Vector2, Matrix3, Vector3, Matrix4, Vector4};
 
-use crate::{engine::render::{render_object::{RenderType, VertexAttrib, ShaderDataTypes, RenderObject, UniformAttrib, UniformRole, AttributeRole}, renderer::{Renderer, UniformData, MappedTexture, VertexData}, texture::{BatchableTextureSource, TextureFilter}}, log_str};
+use wasm_bindgen::UnwrapThrowExt;
+
+use crate::{engine::render::{render_object::{RenderType, BlendMode, VertexAttrib, ShaderDataTypes, RenderObject, UniformAttrib, UniformRole, AttributeRole}, layer::LayerId, error::RenderError, renderer::{Renderer, UniformData, MappedTexture, VertexData, PickId}, texture::{BatchableTextureSource, TextureFilter, PlacementListener}}, log_str};
+
+pub const NAME:&str = "image";
 
-thread_local! {
-    static IMAGE_RENDER_TYPE: Rc<RenderType> = Rc::new(RenderType::new_batched_growable(
+pub fn render_type() -> RenderType {
+    RenderType::new_batched_growable(
         String::from(
             "#version 300 es
 
-            uniform mat4 view;
-            uniform mat4 projection;
+            layout(std140) uniform Camera {
+                mat4 projection;
+                mat4 view;
+            };
     
             in vec3 position;
             in vec2 texCoord;
@@ -32,18 +36,17 @@ thread_local! {
             out vec4 fragColor;
 
             uniform sampler2D texture0;
+            uniform float opacity;
 
             void main() {
                 fragColor = texture(texture0, vTexCoord);
-                if(fragColor.w == 0.0) {
-                    discard;
-                }
+                fragColor.a *= opacity;
             }"
         ),
         vec![
             VertexAttrib { 
                 name: String::from("position"),
-                role:AttributeRole::Custom,
+                role:AttributeRole::Position,
                 data_type:ShaderDataTypes::FloatVec3, 
             }, 
             VertexAttrib { 
@@ -58,12 +61,8 @@ thread_local! {
                 role:UniformRole::Custom
             },
             UniformAttrib {
-                name:String::from("view"),
-                role:UniformRole::View
-            },
-            UniformAttrib {
-                name:String::from("projection"),
-                role:UniformRole::Projection
+                name:String::from("opacity"),
+                role:UniformRole::Opacity
             }
         ],
         Vec::new(),
@@ -73,14 +72,36 @@ thread_local! {
         2000,
         1.1,
         1.1
-    ));
+    ).with_blend_mode(BlendMode::Alpha)
+    //fully transparent texels aren't picked
+    .with_pick_shader(String::from(
+        "#version 300 es
+
+        precision mediump float;
+
+        in vec2 vTexCoord;
+
+        uniform sampler2D texture0;
+        uniform highp uint pickId;
+
+        out highp uint pickOutput;
+
+        void main() {
+            if (texture(texture0, vTexCoord).a <= 0.0) {
+                discard;
+            }
+            pickOutput = pickId;
+        }"
+    ))
 }
 
 pub struct Image {
     obj:RenderObject,
     img:MappedTexture,
     pos:Matrix4<f32>,
-    img_loaded:bool
+    img_loaded:bool,
+    //texcoords are refreshed once the texture moves
+    moved:PlacementListener
 }
 
 impl Image {
@@ -95,7 +116,7 @@ impl Image {
 
         //  log_str(&format!("v0: {:?}, v0: {:?}, v0: {:?}, v0: {:?},",v0,v1,v2,v3));
 
-        let mut render_object = RenderObject::new(IMAGE_RENDER_TYPE.with(|f| f.clone()));
+        let mut render_object = RenderObject::new(renderer.render_type(NAME).expect_throw("Expected the image render type to be registered"));
         
 
         render_object.add_triangle([0,1,2]);
@@ -106,20 +127,20 @@ impl Image {
         render_object
     }
 
-    pub fn from_url(renderer:&mut Renderer, transform:Matrix4<f32>, url:String, min_filter:TextureFilter, mag_filter:TextureFilter) -> Self {
+    pub fn from_url(renderer:&mut Renderer, transform:Matrix4<f32>, url:String, min_filter:TextureFilter, mag_filter:TextureFilter) -> Result<Self,RenderError> {
         let img = renderer.upload_image_from_url(url,min_filter,mag_filter);
 
         let mut render_object = Self::setup_object(renderer, transform,&img);
 
         render_object.set_uniform("texture0", UniformData::Texture(Some(img.clone())));
-        render_object.set_uniform("projection", UniformData::Global);
-        render_object.set_uniform("view", UniformData::Global);
 
         let loaded = img.loaded();
 
-        if loaded { render_object.update(renderer); }
+        if loaded { render_object.update(renderer)?; }
 
-        Self { obj:render_object, img:img, img_loaded:loaded, pos:transform }
+        let moved = img.listen();
+
+        Ok(Self { obj:render_object, img:img, img_loaded:loaded, pos:transform, moved:moved })
     }
 
     pub fn from_mapped(renderer:&mut Renderer, transform:Matrix4<f32>, img:MappedTexture) -> Self {
@@ -127,20 +148,23 @@ impl Image {
         let mut render_object = Self::setup_object(renderer, transform,&img);
 
         render_object.set_uniform("texture0", UniformData::Texture(Some(img.clone())));
-        render_object.set_uniform("projection", UniformData::Global);
-        render_object.set_uniform("view", UniformData::Global);
 
         let loaded = img.loaded();
 
-        Self { obj:render_object, img:img, img_loaded:loaded, pos:transform }
+        let moved = img.listen();
+
+        Self { obj:render_object, img:img, img_loaded:loaded, pos:transform, moved:moved }
     }
 
     pub fn update_texture_src(&mut self, renderer:&mut Renderer, src:&dyn BatchableTextureSource) {
         self.img.update(renderer, src);
     }
 
+    //texcoords of the new texture are set on the next render
     pub fn update_texture_mapped(&mut self, mapped:MappedTexture) {
+        self.moved = mapped.listen();
         self.img = mapped;
+        self.img_loaded = false;
     }
 
     
@@ -187,28 +211,52 @@ impl Image {
         self.obj.set_v_datas(0, "position", vec![VertexData::FloatVec3(v0),VertexData::FloatVec3(v1),VertexData::FloatVec3(v2),VertexData::FloatVec3(v3)]);
     }
 
-    pub fn render(&mut self, renderer:&mut Renderer, transform:Matrix4<f32>) {
+    //an image still loading moves once it is first drawn
+    pub fn set_layer(&mut self, renderer:&mut Renderer, layer:LayerId, z_index:i32) -> Result<(),RenderError> {
+        if self.img_loaded {
+            return self.obj.move_to_layer(renderer, layer, z_index);
+        }
+        renderer.layer(layer).ok_or(RenderError::UnknownLayer(layer))?;
+        self.obj.set_layer(layer, z_index);
+        Ok(())
+    }
+
+    //compared with what Renderer::pick returns
+    pub fn pick_id(&self) -> Option<PickId> {
+        self.obj.pick_id()
+    }
+
+    pub fn render(&mut self, renderer:&mut Renderer, transform:Matrix4<f32>) -> Result<(),RenderError> {
         if (
             if !self.img_loaded && self.img.loaded() {
                 self.update_texcoords(renderer);
                 self.img_loaded = true;
                 true
             } else { false } ||
+            if self.img_loaded && self.moved.moved() {
+                self.update_texcoords(renderer);
+                true
+            } else { false } ||
             if self.pos != transform {
                 self.update_pos(transform);
                 self.pos = transform;
                 true
             } else { false } 
         ) {
-            self.obj.update(renderer);
+            return self.obj.update(renderer);
         }
+        Ok(())
     }
 
-    pub fn render_unchanged(&mut self, renderer:&mut Renderer) {
+    pub fn render_unchanged(&mut self, renderer:&mut Renderer) -> Result<(),RenderError> {
         if !self.img_loaded && self.img.loaded() {
             self.update_texcoords(renderer);
             self.img_loaded = true;
-            self.obj.update(renderer);
+            return self.obj.update(renderer);
+        } else if self.img_loaded && self.moved.moved() {
+            self.update_texcoords(renderer);
+            return self.obj.update(renderer);
         }
+        Ok(())
     }
 }
\ No newline at end of file
@@ -1,17 +1,42 @@
-use std::{rc::Rc,f32::consts::{FRAC_PI_4,FRAC_PI_2, SQRT_2, PI}};
+use std::f32::consts::{FRAC_PI_4,FRAC_PI_2, SQRT_2, PI};
 
 use cgmath::{Vector2, Vector4};
 
-use crate::{engine::render::{render_object::{RenderType, VertexAttrib, ShaderDataTypes, RenderObject, AttributeRole}, renderer::{Renderer, VertexData}}, log_str};
+use wasm_bindgen::UnwrapThrowExt;
 
-thread_local! {
-    static LINE_RENDER_TYPE: Rc<RenderType> = Rc::new(RenderType::new_batched_growable(
+use crate::engine::render::{render_object::{RenderType, BlendMode, VertexAttrib, ShaderDataTypes, RenderObject, AttributeRole, UniformAttrib, UniformRole}, layer::LayerId, error::RenderError, renderer::{Renderer, VertexData, PickId}};
+
+pub const NAME:&str = "line";
+
+//inputs and distance function of the fragment and pick shaders
+const SEGMENT:&str = "
+            precision highp float;
+            // start point
+            in vec2 sf;
+            // end point
+            in vec2 ef;
+            // current position in fragment shader
+            in vec2 posf;
+            // thickness of the curve
+            in float thicknessf;
+            in float fsmooth;
+
+            in vec4 color;
+    
+            // borrowed from here https://www.iquilezles.org/www/articles/distfunctions2d/distfunctions2d.htm
+            float sdSegment( in vec2 p, in vec2 a, in vec2 b )
+            {
+                vec2 pa = p-a, ba = b-a;
+                float h = clamp( dot(pa,ba)/dot(ba,ba), 0.0, 1.0 );
+                return length( pa - ba*h );
+            }
+";
+
+pub fn render_type() -> RenderType {
+    RenderType::new_batched_growable(
         String::from(
             "#version 300 es
 
-            uniform mat4 view;
-            uniform mat4 projection;
-            
             in vec2 pos;
             in vec4 vColor;
             in float vThickness;
@@ -21,7 +46,6 @@ thread_local! {
 
             out vec2 posf;
             out vec2 sf;
-            out vec2 controlf;
             out vec2 ef;
             out float thicknessf;
             out vec4 color;
@@ -37,39 +61,18 @@ thread_local! {
                 fsmooth = vSmooth;
             }"
         ),
-        String::from(
-            "# version 300 es
-            precision highp float;
-            // start point
-            in vec2 sf;
-            // end point
-            in vec2 ef;
-            // current position in fragment shader
-            in vec2 posf;
-            // thickness of the curve
-            in float thicknessf;
-            in float fsmooth;
-
-            in vec4 color;
+        format!("# version 300 es{}{}", SEGMENT,
+            "
+            uniform float opacity;
     
             out vec4 FragColor;
     
-            float dot2( in vec2 v ) { return dot(v,v); }
-    
-            // borrowed from here https://www.iquilezles.org/www/articles/distfunctions2d/distfunctions2d.htm
-            float sdSegment( in vec2 p, in vec2 a, in vec2 b )
-            {
-                vec2 pa = p-a, ba = b-a;
-                float h = clamp( dot(pa,ba)/dot(ba,ba), 0.0, 1.0 );
-                return length( pa - ba*h );
-            }
-    
             void main() {
                 vec4 result = color;
                 float d = sdSegment(posf, sf, ef) - thicknessf;
                 float s = smoothstep(0., fsmooth, -d);
                 if (d < 0.) {
-                    result.a = s;
+                    result.a = s * opacity;
                 } else {
                     discard;
                 }
@@ -79,14 +82,9 @@ thread_local! {
         vec![
             VertexAttrib { 
                 name: String::from("pos"), 
-                role:AttributeRole::Custom,
-                data_type:ShaderDataTypes::FloatVec3, 
+                role:AttributeRole::Position,
+                data_type:ShaderDataTypes::FloatVec2, 
             },
-            VertexAttrib {
-                name: String::from("vTexcoord"),
-                role: AttributeRole::Custom,
-                data_type: ShaderDataTypes::FloatVec2
-            }
             VertexAttrib {
                 name: String::from("vColor"),
                 role:AttributeRole::Custom,
@@ -113,7 +111,12 @@ thread_local! {
                 data_type:ShaderDataTypes::FLOAT, 
             },
         ], 
-        Vec::new(), 
+        vec![
+            UniformAttrib {
+                name:String::from("opacity"),
+                role:UniformRole::Opacity
+            }
+        ],
         Vec::new(),
         20, 
         1000, 
@@ -121,7 +124,20 @@ thread_local! {
         1500, 
         2.0, 
         2.0
-    ));
+    ).with_blend_mode(BlendMode::Alpha)
+    .with_pick_shader(format!("# version 300 es{}{}", SEGMENT,
+        "
+            uniform highp uint pickId;
+
+            out highp uint pickOutput;
+
+            void main() {
+                if (sdSegment(posf, sf, ef) - thicknessf >= 0.) {
+                    discard;
+                }
+                pickOutput = pickId;
+            }"
+    ))
 }
 
 pub struct Line {
@@ -137,7 +153,7 @@ pub enum EndBehavior {
 }
 
 impl Line {
-    pub fn new(renderer:&mut Renderer, points:[Vector2<f32>; 2], color:Vector4<f32>, thickness:f32, smooth:f32, end_behavior:EndBehavior) -> Self {
+    pub fn new(renderer:&mut Renderer, points:[Vector2<f32>; 2], color:Vector4<f32>, thickness:f32, smooth:f32, end_behavior:EndBehavior) -> Result<Self,RenderError> {
         
         let theta = (points[0].y-points[1].y).atan2(points[0].x-points[1].x);
 
@@ -161,7 +177,7 @@ impl Line {
         let p2 = points[1] + Vector2::new(f32::cos(t0+PI)*c, f32::sin(t0+PI)*c);
         let p3 = points[0] + Vector2::new(f32::cos(t1+PI)*c, f32::sin(t1+PI)*c);
 
-        let mut render_object = RenderObject::new(LINE_RENDER_TYPE.with(|f| f.clone()));
+        let mut render_object = RenderObject::new(renderer.render_type(NAME).expect_throw("Expected the line render type to be registered"));
         render_object.add_triangle([0,1,2]);
         render_object.add_triangle([2,3,0]);
 
@@ -172,18 +188,27 @@ impl Line {
         render_object.set_v_datas(0, "points1", vec![VertexData::FloatVec2(points[0].clone()),VertexData::FloatVec2(points[0].clone()),VertexData::FloatVec2(points[0].clone()),VertexData::FloatVec2(points[0].clone())]);
         render_object.set_v_datas(0, "points2", vec![VertexData::FloatVec2(points[1].clone()),VertexData::FloatVec2(points[1].clone()),VertexData::FloatVec2(points[1].clone()),VertexData::FloatVec2(points[1].clone())]);
 
-        render_object.update(renderer);
-        Self { obj:render_object, end_behavior:end_behavior, smooth:smooth, thickness:thickness }
+        render_object.update(renderer)?;
+        Ok(Self { obj:render_object, end_behavior:end_behavior, smooth:smooth, thickness:thickness })
     }
 
-    pub fn update_points(&mut self, renderer: &mut Renderer, p1:Vector2<f32>, p2:Vector2<f32>) {
+    pub fn update_points(&mut self, renderer: &mut Renderer, p1:Vector2<f32>, p2:Vector2<f32>) -> Result<(),RenderError> {
 
         Self::set_bounding_box(&mut self.obj, &self.end_behavior, [p1,p2], self.thickness, self.smooth);
         
         self.obj.set_v_datas(0, "points1", vec![VertexData::FloatVec2(p1.clone()),VertexData::FloatVec2(p1.clone()),VertexData::FloatVec2(p1.clone()),VertexData::FloatVec2(p1.clone())]);
         self.obj.set_v_datas(0, "points2", vec![VertexData::FloatVec2(p2.clone()),VertexData::FloatVec2(p2.clone()),VertexData::FloatVec2(p2.clone()),VertexData::FloatVec2(p2.clone())]);
         
-        self.obj.update(renderer);
+        self.obj.update(renderer)
+    }
+
+    pub fn set_layer(&mut self, renderer:&mut Renderer, layer:LayerId, z_index:i32) -> Result<(),RenderError> {
+        self.obj.move_to_layer(renderer, layer, z_index)
+    }
+
+    //compared with what Renderer::pick returns
+    pub fn pick_id(&self) -> Option<PickId> {
+        self.obj.pick_id()
     }
 
     fn set_bounding_box(render_obj:&mut RenderObject, end_behavior:&EndBehavior, points:[Vector2<f32>; 2], thickness:f32, smooth:f32) {
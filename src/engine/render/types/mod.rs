@@ -1,5 +1,15 @@
 // pub mod rect;
 pub mod image;
 pub mod quadratic_bezier;
-//pub mod line;
-pub mod triangle;
\ No newline at end of file
+pub mod line;
+pub mod triangle;
+
+use super::shader_registry::ShaderRegistry;
+
+//render types every renderer starts with, looked up by the primitives through their NAME
+pub fn register_builtin(shaders:&mut ShaderRegistry) {
+    shaders.register(image::NAME, image::render_type());
+    shaders.register(quadratic_bezier::NAME, quadratic_bezier::render_type());
+    shaders.register(line::NAME, line::render_type());
+    shaders.register(triangle::NAME, triangle::render_type());
+}
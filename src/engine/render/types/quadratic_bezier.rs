@@ -1,42 +1,15 @@
-use std::{rc::Rc,f32::consts::{FRAC_PI_4,FRAC_PI_2, TAU, SQRT_2,PI}};
+use std::{f32::consts::{FRAC_PI_4,FRAC_PI_2, TAU, SQRT_2,PI}};
 
 use cgmath::{Vector2, InnerSpace, Vector4};
 
-use crate::engine::render::{render_object::{RenderType, VertexAttrib, ShaderDataTypes, RenderObject, AttributeRole}, renderer::{Renderer, RenderObjectAllocation, UniformBlock, VertexData}};
+use wasm_bindgen::UnwrapThrowExt;
 
-thread_local! {
-    static QUADRATIC_BEZIER_RENDER_TYPE: Rc<RenderType> = Rc::new(RenderType::new_batched_growable(
-        String::from(
-            "#version 300 es
-            
-            in vec2 pos;
-            in vec4 vColor;
-            in float vThickness;
-            in float vSmooth;
-            in vec2 points1;
-            in vec2 points2;
-            in vec2 points3;
+use crate::engine::render::{render_object::{RenderType, BlendMode, VertexAttrib, ShaderDataTypes, RenderObject, AttributeRole, UniformAttrib, UniformRole}, layer::LayerId, error::RenderError, renderer::{Renderer, RenderObjectAllocation, UniformBlock, VertexData, PickId}};
 
-            out vec2 posf;
-            out vec2 af;
-            out vec2 controlf;
-            out vec2 cf;
-            out float thicknessf;
-            out vec4 color;
-            out float fsmooth;
-    
-            void main() {
-                gl_Position = vec4(pos,0.0,1.0);
-                posf = gl_Position.xy;
-                af = points1;
-                controlf = points2;
-                cf =points3;
-                thicknessf = vThickness;
-                color = vColor;
-                fsmooth = vSmooth;
-            }"
-        ),String::from(
-            "# version 300 es
+pub const NAME:&str = "quadratic_bezier";
+
+//inputs and distance function of the fragment and pick shaders
+const CURVE:&str = "
             precision highp float;
             // start point
             in vec2 af;
@@ -51,9 +24,7 @@ thread_local! {
             in float fsmooth;
 
             in vec4 color;
-    
-            out vec4 FragColor;
-    
+
             float dot2( in vec2 v ) { return dot(v,v); }
     
             // borrowed from here https://www.iquilezles.org/www/articles/distfunctions2d/distfunctions2d.htm
@@ -94,6 +65,44 @@ thread_local! {
                 }
                 return sqrt( res );
             }
+";
+
+pub fn render_type() -> RenderType {
+    RenderType::new_batched_growable(
+        String::from(
+            "#version 300 es
+            
+            in vec2 pos;
+            in vec4 vColor;
+            in float vThickness;
+            in float vSmooth;
+            in vec2 points1;
+            in vec2 points2;
+            in vec2 points3;
+
+            out vec2 posf;
+            out vec2 af;
+            out vec2 controlf;
+            out vec2 cf;
+            out float thicknessf;
+            out vec4 color;
+            out float fsmooth;
+    
+            void main() {
+                gl_Position = vec4(pos,0.0,1.0);
+                posf = gl_Position.xy;
+                af = points1;
+                controlf = points2;
+                cf =points3;
+                thicknessf = vThickness;
+                color = vColor;
+                fsmooth = vSmooth;
+            }"
+        ),format!("# version 300 es{}{}", CURVE,
+            "
+            uniform float opacity;
+    
+            out vec4 FragColor;
     
             void main() {
                 vec4 result = color;
@@ -101,7 +110,7 @@ thread_local! {
                 // bigger value -- more blury
                 float s = smoothstep(0., fsmooth, -d);
                 if (d < 0.) {
-                    result.a = s;
+                    result.a = s * opacity;
                 } else {
                     discard;
                 }
@@ -111,13 +120,13 @@ thread_local! {
         vec![
             VertexAttrib { 
                 name: String::from("pos"), 
-                role:AttributeRole::Custom,
+                role:AttributeRole::Position,
                 data_type:ShaderDataTypes::FloatVec2, 
             },
             VertexAttrib {
                 name: String::from("vColor"),
                 role:AttributeRole::Custom,
-                data_type:ShaderDataTypes::FloatVec4,
+                data_type:ShaderDataTypes::UnsignedByteNormalizedVec4,
             },
             VertexAttrib {
                 name:String::from("vThickness"),
@@ -145,7 +154,12 @@ thread_local! {
                 data_type:ShaderDataTypes::FLOAT, 
             },
         ],
-        Vec::new(),
+        vec![
+            UniformAttrib {
+                name:String::from("opacity"),
+                role:UniformRole::Opacity
+            }
+        ],
         Vec::new(),
         20,
         2000,
@@ -153,7 +167,20 @@ thread_local! {
         2000, 
         2.0,
         2.0
-    ));
+    ).with_blend_mode(BlendMode::Alpha)
+    .with_pick_shader(format!("# version 300 es{}{}", CURVE,
+        "
+            uniform highp uint pickId;
+
+            out highp uint pickOutput;
+
+            void main() {
+                if (sdBezier(posf, af, controlf, cf) - thicknessf >= 0.) {
+                    discard;
+                }
+                pickOutput = pickId;
+            }"
+    ))
 }
 
 pub struct QuadraticBezier {
@@ -161,7 +188,7 @@ pub struct QuadraticBezier {
 }
 
 impl QuadraticBezier {
-    pub fn new(renderer:&mut Renderer, points:[Vector2<f32>; 3], color:Vector4<f32>, thickness:f32, smooth:f32) -> Self {
+    pub fn new(renderer:&mut Renderer, points:[Vector2<f32>; 3], color:Vector4<f32>, thickness:f32, smooth:f32) -> Result<Self,RenderError> {
         let offset = thickness + smooth;
         
         let theta = (points[2].y-points[0].y).atan2(points[2].x-points[0].x);
@@ -182,7 +209,7 @@ impl QuadraticBezier {
         let p2 = p0 + Vector2 {x:f32::cos(theta+FRAC_PI_2),y:f32::sin(theta+FRAC_PI_2)}*c;
         let p3 = p1 + Vector2 {x:f32::cos(theta+FRAC_PI_2),y:f32::sin(theta+FRAC_PI_2)}*c;
 
-        let mut render_object = RenderObject::new(QUADRATIC_BEZIER_RENDER_TYPE.with(|f| f.clone()));
+        let mut render_object = RenderObject::new(renderer.render_type(NAME).expect_throw("Expected the quadratic bezier render type to be registered"));
 
         render_object.add_triangle([0,1,3]);
         render_object.add_triangle([3,2,0]);
@@ -191,13 +218,22 @@ impl QuadraticBezier {
         render_object.set_v_datas(0,"points1", vec![VertexData::FloatVec2(points[0]),VertexData::FloatVec2(points[0]),VertexData::FloatVec2(points[0]),VertexData::FloatVec2(points[0])]);
         render_object.set_v_datas(0,"points2", vec![VertexData::FloatVec2(points[1]),VertexData::FloatVec2(points[1]),VertexData::FloatVec2(points[1]),VertexData::FloatVec2(points[1])]);
         render_object.set_v_datas(0,"points3", vec![VertexData::FloatVec2(points[2]),VertexData::FloatVec2(points[2]),VertexData::FloatVec2(points[2]),VertexData::FloatVec2(points[2])]);
-        render_object.set_v_datas(0, "vColor", vec![VertexData::FloatVec4(color.clone()),VertexData::FloatVec4(color.clone()),VertexData::FloatVec4(color.clone()),VertexData::FloatVec4(color.clone())]);
+        render_object.set_v_datas(0, "vColor", vec![VertexData::packed_color(color),VertexData::packed_color(color),VertexData::packed_color(color),VertexData::packed_color(color)]);
         render_object.set_v_datas(0, "vThickness", vec![VertexData::Float(thickness),VertexData::Float(thickness),VertexData::Float(thickness),VertexData::Float(thickness)]);
         render_object.set_v_datas(0, "vSmooth", vec![VertexData::Float(smooth),VertexData::Float(smooth),VertexData::Float(smooth),VertexData::Float(smooth)]);
 
-        renderer.update(&mut render_object);
+        renderer.update(&mut render_object)?;
+
+        Ok(Self { obj:render_object })
+    }
+
+    pub fn set_layer(&mut self, renderer:&mut Renderer, layer:LayerId, z_index:i32) -> Result<(),RenderError> {
+        self.obj.move_to_layer(renderer, layer, z_index)
+    }
 
-        Self { obj:render_object }
+    //compared with what Renderer::pick returns
+    pub fn pick_id(&self) -> Option<PickId> {
+        self.obj.pick_id()
     }
 
     pub fn render(&mut self) {
@@ -1,11 +1,13 @@
-use std::rc::Rc;
-
 use cgmath::{Vector2, Vector4};
 
-use crate::engine::render::{render_object::{RenderType, VertexAttrib, ShaderDataTypes, RenderObject, AttributeRole}, renderer::{Renderer, RenderObjectAllocation, VertexData}};
+use wasm_bindgen::UnwrapThrowExt;
+
+use crate::engine::render::{render_object::{RenderType, VertexAttrib, ShaderDataTypes, RenderObject, AttributeRole, UniformAttrib, UniformRole}, layer::LayerId, error::RenderError, renderer::{Renderer, RenderObjectAllocation, VertexData, PickId}};
+
+pub const NAME:&str = "triangle";
 
-thread_local! {
-    static TRIANGLE_RENDER_TYPE:Rc<RenderType> = Rc::new(RenderType::new_batched_growable(
+pub fn render_type() -> RenderType {
+    RenderType::new_batched_growable(
         String::from(
             "#version 300 es
             
@@ -22,15 +24,16 @@ thread_local! {
             "# version 300 es
             precision highp float;
             in vec4 color;
+            uniform float opacity;
             out vec4 FragColor;
             void main() {
-                FragColor = color;
+                FragColor = vec4(color.rgb, color.a * opacity);
             }"
         ), 
         vec![
             VertexAttrib { 
                 name: String::from("pos"), 
-                role:AttributeRole::Custom,
+                role:AttributeRole::Position,
                 data_type:ShaderDataTypes::FloatVec2, 
             },
             VertexAttrib {
@@ -40,7 +43,12 @@ thread_local! {
             },
             
         ], 
-        Vec::new(), 
+        vec![
+            UniformAttrib {
+                name:String::from("opacity"),
+                role:UniformRole::Opacity
+            }
+        ], 
         Vec::new(), 
         20, 
         40, 
@@ -48,7 +56,7 @@ thread_local! {
         40, 
         2.0, 
         2.0
-    ));
+    )
 }
 
 pub struct Triangle {
@@ -56,9 +64,9 @@ pub struct Triangle {
 }
 
 impl Triangle {
-    pub fn new(renderer:&mut Renderer, points:[Vector2<f32>; 3], color:Vector4<f32>) -> Self {
+    pub fn new(renderer:&mut Renderer, points:[Vector2<f32>; 3], color:Vector4<f32>) -> Result<Self,RenderError> {
 
-        let mut render_object = RenderObject::new(TRIANGLE_RENDER_TYPE.with(|f| f.clone()));
+        let mut render_object = RenderObject::new(renderer.render_type(NAME).expect_throw("Expected the triangle render type to be registered"));
         render_object.add_triangle([0,1,2]);
         render_object.set_v_datas(0, "pos", vec![
             VertexData::FloatVec2(points[0]),
@@ -71,8 +79,17 @@ impl Triangle {
             VertexData::FloatVec4(color)
         ]);
 
-        render_object.update(renderer);
-        Self { obj:render_object }
+        render_object.update(renderer)?;
+        Ok(Self { obj:render_object })
+    }
+
+    pub fn set_layer(&mut self, renderer:&mut Renderer, layer:LayerId, z_index:i32) -> Result<(),RenderError> {
+        self.obj.move_to_layer(renderer, layer, z_index)
+    }
+
+    //compared with what Renderer::pick returns
+    pub fn pick_id(&self) -> Option<PickId> {
+        self.obj.pick_id()
     }
 
     pub fn render(&mut self) {
@@ -0,0 +1,63 @@
+use web_sys::WebGl2RenderingContext;
+
+use super::backend::{Backend, BufferHandle, ProgramHandle};
+
+//the renderer's camera block, bound to CAMERA_BLOCK_BINDING for every program that declares it:
+//  layout(std140) uniform Camera {
+//      mat4 projection;
+//      mat4 view;
+//  };
+pub const CAMERA_BLOCK:&str = "Camera";
+pub const CAMERA_BLOCK_BINDING:u32 = 0;
+pub const CAMERA_BLOCK_SIZE:usize = 128;
+pub const CAMERA_PROJECTION_OFFSET:usize = 0;
+pub const CAMERA_VIEW_OFFSET:usize = 64;
+
+//buffer behind a std140 uniform block, shared by every program that declares a block with its name.
+//Writes are seen by all of them without setting any uniforms per program
+#[derive(Debug)]
+pub struct UniformBuffer {
+    gl:Backend,
+    handle:BufferHandle,
+    name:String,
+    binding:u32,
+    size:usize
+}
+
+impl Drop for UniformBuffer {
+    fn drop(&mut self) {
+        self.gl.delete_buffer(self.handle);
+    }
+}
+
+impl UniformBuffer {
+    pub fn new(gl:&Backend, name:&str, binding:u32, size:usize) -> Self {
+        let handle = gl.create_buffer();
+        gl.bind_buffer(WebGl2RenderingContext::UNIFORM_BUFFER, Some(handle));
+        gl.buffer_data_with_size(WebGl2RenderingContext::UNIFORM_BUFFER, size as i32, WebGl2RenderingContext::DYNAMIC_DRAW);
+        gl.bind_buffer_base(WebGl2RenderingContext::UNIFORM_BUFFER, binding, Some(handle));
+        Self { gl:gl.clone(), handle:handle, name:name.to_owned(), binding:binding, size:size }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn write(&self, offset:usize, data:&[u8]) {
+        assert!(offset + data.len() <= self.size, "Uniform buffer {} write out of range", self.name);
+        self.gl.bind_buffer(WebGl2RenderingContext::UNIFORM_BUFFER, Some(self.handle));
+        self.gl.buffer_sub_data(WebGl2RenderingContext::UNIFORM_BUFFER, offset as i32, data);
+    }
+
+    pub fn write_floats(&self, offset:usize, data:&[f32]) {
+        let bytes:Vec<u8> = data.iter().flat_map(|x| x.to_ne_bytes()).collect();
+        self.write(offset, &bytes);
+    }
+
+    //points the program's block with this buffer's name at its binding, programs without the block are left alone
+    pub fn bind_program(&self, program:ProgramHandle) {
+        if let Some(index) = self.gl.get_uniform_block_index(program, &self.name) {
+            self.gl.uniform_block_binding(program, index, self.binding);
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::{collections::HashMap, hash::Hash, cmp::Ordering, mem};
+
+use cgmath::Point2;
+
+use super::render::bounds::Rect;
+
+//items a node holds before it is split, ones on the edges between its quadrants stay in it either way
+const MAX_NODE_ITEMS:usize = 8;
+//nodes this deep are never split, so many items in one spot can't split forever
+const MAX_DEPTH:usize = 16;
+
+//quadtree of 2D bounds, keyed by whatever identifies the items like render object allocations or board items.
+//The tree grows outwards to hold bounds outside the area it started with
+#[derive(Debug)]
+pub struct QuadTree<K> {
+    root:Node<K>,
+    bounds:HashMap<K,Rect>
+}
+
+impl<K:Copy + Eq + Hash> QuadTree<K> {
+    //bounds is the area first split up, it should roughly cover the items
+    pub fn new(bounds:Rect) -> Self {
+        assert!(bounds.width() > 0.0 && bounds.height() > 0.0, "QuadTree bounds {:?} have no area", bounds);
+        Self { root:Node::new(bounds), bounds:HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    pub fn get(&self, key:K) -> Option<Rect> {
+        self.bounds.get(&key).copied()
+    }
+
+    //moves the key if it is already in the tree
+    pub fn insert(&mut self, key:K, bounds:Rect) {
+        assert!(bounds.min.x.is_finite() && bounds.min.y.is_finite() && bounds.max.x.is_finite() && bounds.max.y.is_finite(), "QuadTree item bounds {:?} are not finite", bounds);
+        self.remove(key);
+        self.grow_to(&bounds);
+        self.root.insert(key, bounds, 0);
+        self.bounds.insert(key, bounds);
+    }
+
+    pub fn remove(&mut self, key:K) -> Option<Rect> {
+        let bounds = self.bounds.remove(&key)?;
+        self.root.remove(key, &bounds);
+        Some(bounds)
+    }
+
+    pub fn clear(&mut self) {
+        self.root = Node::new(self.root.bounds);
+        self.bounds.clear();
+    }
+
+    //items overlapping area
+    pub fn query_rect(&self, area:&Rect) -> Vec<K> {
+        let mut result = Vec::new();
+        self.root.query(area, &|x| x.intersects(area), &mut result);
+        result
+    }
+
+    //items under point
+    pub fn query_point(&self, point:Point2<f32>) -> Vec<K> {
+        let mut result = Vec::new();
+        self.root.query(&Rect::new(point, point), &|x| x.contains_point(point), &mut result);
+        result
+    }
+
+    //item with the bounds closest to point, ones containing it are at distance 0
+    pub fn nearest(&self, point:Point2<f32>) -> Option<K> {
+        self.nearest_where(point, |_| true)
+    }
+
+    //like nearest but only for items filter accepts, to skip the item being snapped for example
+    pub fn nearest_where(&self, point:Point2<f32>, filter:impl Fn(K) -> bool) -> Option<K> {
+        let mut best = None;
+        self.root.nearest(point, &filter, &mut best);
+        best.map(|(_, key)| key)
+    }
+
+    //doubles the root towards bounds until it holds them, the old root becomes one of the new one's quadrants
+    fn grow_to(&mut self, bounds:&Rect) {
+        while !self.root.bounds.contains(bounds) {
+            let old = self.root.bounds;
+            let left = bounds.min.x < old.min.x;
+            let down = bounds.min.y < old.min.y;
+            let min = Point2::new(
+                if left { old.min.x - old.width() } else { old.min.x },
+                if down { old.min.y - old.height() } else { old.min.y }
+            );
+            let grown = Rect::new(min, min + old.size() * 2.0);
+            let old = mem::replace(&mut self.root, Node::new(grown));
+            let mut children = quadrants(&grown).map(Node::new);
+            children[left as usize + 2 * down as usize] = old;
+            self.root.children = Some(Box::new(children));
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Node<K> {
+    bounds:Rect,
+    items:Vec<(K,Rect)>,
+    //bottom left, bottom right, top left, top right
+    children:Option<Box<[Node<K>; 4]>>
+}
+
+impl<K:Copy + Eq> Node<K> {
+    fn new(bounds:Rect) -> Self {
+        Self { bounds:bounds, items:Vec::new(), children:None }
+    }
+
+    //child that holds bounds entirely
+    fn child_for(&self, bounds:&Rect) -> Option<usize> {
+        self.children.as_ref()?.iter().position(|x| x.bounds.contains(bounds))
+    }
+
+    fn insert(&mut self, key:K, bounds:Rect, depth:usize) {
+        if let Some(i) = self.child_for(&bounds) {
+            self.children.as_mut().unwrap()[i].insert(key, bounds, depth + 1);
+            return;
+        }
+        self.items.push((key, bounds));
+        if self.children.is_none() && self.items.len() > MAX_NODE_ITEMS && depth < MAX_DEPTH {
+            self.children = Some(Box::new(quadrants(&self.bounds).map(Node::new)));
+            for (key, bounds) in mem::take(&mut self.items) {
+                self.insert(key, bounds, depth);
+            }
+        }
+    }
+
+    //follows the same path as insert, which only changes when nodes split or merge and move their items with them
+    fn remove(&mut self, key:K, bounds:&Rect) {
+        match self.child_for(bounds) {
+            Some(i) => {
+                self.children.as_mut().unwrap()[i].remove(key, bounds);
+                self.merge();
+            },
+            None => if let Some(i) = self.items.iter().position(|x| x.0 == key) {
+                self.items.swap_remove(i);
+            }
+        }
+    }
+
+    //takes the children's items back once they fit without splitting
+    fn merge(&mut self) {
+        let children = match &mut self.children {
+            Some(x) => x,
+            None => return
+        };
+        if children.iter().any(|x| x.children.is_some()) { return; }
+        if self.items.len() + children.iter().map(|x| x.items.len()).sum::<usize>() > MAX_NODE_ITEMS { return; }
+        for child in children.iter_mut() {
+            self.items.append(&mut child.items);
+        }
+        self.children = None;
+    }
+
+    //items hit accepts from the nodes overlapping area
+    fn query(&self, area:&Rect, hit:&dyn Fn(&Rect) -> bool, result:&mut Vec<K>) {
+        result.extend(self.items.iter().filter(|x| hit(&x.1)).map(|x| x.0));
+        if let Some(children) = &self.children {
+            for child in children.iter().filter(|x| x.bounds.intersects(area)) {
+                child.query(area, hit, result);
+            }
+        }
+    }
+
+    //children are searched from the closest, skipping ones farther away than the best item found so far
+    fn nearest(&self, point:Point2<f32>, filter:&dyn Fn(K) -> bool, best:&mut Option<(f32,K)>) {
+        for (key, bounds) in self.items.iter().filter(|x| filter(x.0)) {
+            let distance = bounds.distance_to(point);
+            if best.map_or(true, |x| distance < x.0) {
+                *best = Some((distance, *key));
+            }
+        }
+        if let Some(children) = &self.children {
+            let mut order:Vec<(f32,&Node<K>)> = children.iter().map(|x| (x.bounds.distance_to(point), x)).collect();
+            order.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+            for (distance, child) in order {
+                if best.map_or(false, |x| distance >= x.0) { break; }
+                child.nearest(point, filter, best);
+            }
+        }
+    }
+}
+
+fn quadrants(bounds:&Rect) -> [Rect; 4] {
+    let center = bounds.center();
+    [
+        Rect::new(bounds.min, center),
+        Rect::new(Point2::new(center.x, bounds.min.y), Point2::new(bounds.max.x, center.y)),
+        Rect::new(Point2::new(bounds.min.x, center.y), Point2::new(center.x, bounds.max.y)),
+        Rect::new(center, bounds.max)
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point2;
+
+    use super::{QuadTree, Node, MAX_NODE_ITEMS, MAX_DEPTH};
+    use crate::engine::render::bounds::Rect;
+
+    fn rect(x:f32, y:f32, size:f32) -> Rect {
+        Rect::new(Point2::new(x, y), Point2::new(x + size, y + size))
+    }
+
+    //0..1 in both axes
+    fn tree() -> QuadTree<usize> {
+        QuadTree::new(rect(0.0, 0.0, 1.0))
+    }
+
+    fn sorted(mut keys:Vec<usize>) -> Vec<usize> {
+        keys.sort();
+        keys
+    }
+
+    fn depth<K>(node:&Node<K>) -> usize {
+        node.children.as_ref().map_or(0, |x| 1 + x.iter().map(depth).max().unwrap())
+    }
+
+    #[test]
+    fn queries_rects_and_points() {
+        let mut tree = tree();
+        tree.insert(0, rect(0.1, 0.1, 0.1));
+        tree.insert(1, rect(0.6, 0.6, 0.2));
+        tree.insert(2, rect(0.15, 0.15, 0.5));
+        assert_eq!(sorted(tree.query_rect(&rect(0.0, 0.0, 0.3))), vec![0, 2]);
+        assert_eq!(sorted(tree.query_rect(&rect(0.62, 0.62, 0.1))), vec![1, 2]);
+        assert_eq!(sorted(tree.query_point(Point2::new(0.7, 0.7))), vec![1]);
+        assert_eq!(sorted(tree.query_point(Point2::new(0.18, 0.18))), vec![0, 2]);
+        assert!(tree.query_point(Point2::new(0.9, 0.1)).is_empty());
+    }
+
+    #[test]
+    fn finds_the_nearest_bounds() {
+        let mut tree = tree();
+        tree.insert(0, rect(0.1, 0.1, 0.1));
+        tree.insert(1, rect(0.6, 0.6, 0.2));
+        assert_eq!(tree.nearest(Point2::new(0.0, 0.0)), Some(0));
+        assert_eq!(tree.nearest(Point2::new(0.7, 0.7)), Some(1));
+        assert_eq!(tree.nearest(Point2::new(0.9, 0.5)), Some(1));
+        assert_eq!(tree.nearest_where(Point2::new(0.7, 0.7), |x| x != 1), Some(0));
+        assert_eq!(tree.nearest_where(Point2::new(0.7, 0.7), |_| false), None);
+        assert_eq!(QuadTree::<usize>::new(rect(0.0, 0.0, 1.0)).nearest(Point2::new(0.5, 0.5)), None);
+    }
+
+    #[test]
+    fn grows_to_hold_bounds_outside_it() {
+        let mut tree = tree();
+        tree.insert(0, rect(0.1, 0.1, 0.1));
+        tree.insert(1, rect(-1.5, 2.5, 0.2));
+        //doubled twice towards the top left
+        assert_eq!(tree.root.bounds, Rect::new(Point2::new(-3.0, 0.0), Point2::new(1.0, 4.0)));
+        assert_eq!(tree.query_point(Point2::new(-1.4, 2.6)), vec![1]);
+        assert_eq!(tree.query_point(Point2::new(0.15, 0.15)), vec![0]);
+        assert_eq!(tree.nearest(Point2::new(-2.0, 3.0)), Some(1));
+    }
+
+    #[test]
+    fn splits_full_nodes_and_merges_them_again() {
+        let mut tree = tree();
+        for i in 0..=MAX_NODE_ITEMS {
+            tree.insert(i, rect(0.05 + (i % 4) as f32 * 0.25, 0.05 + (i / 4) as f32 * 0.25, 0.1));
+        }
+        //on the edges between quadrants, so it stays in the root
+        tree.insert(100, rect(0.45, 0.45, 0.1));
+        assert!(tree.root.children.is_some());
+        assert_eq!(tree.root.items.iter().map(|x| x.0).collect::<Vec<usize>>(), vec![100]);
+        assert_eq!(sorted(tree.query_rect(&rect(0.0, 0.0, 0.5))), vec![0, 1, 4, 5, 100]);
+
+        tree.remove(0);
+        tree.remove(1);
+        assert!(tree.root.children.is_none());
+        assert_eq!(tree.root.items.len(), MAX_NODE_ITEMS);
+        assert_eq!(sorted(tree.query_rect(&rect(0.0, 0.0, 0.5))), vec![4, 5, 100]);
+    }
+
+    #[test]
+    fn removes_and_moves_items() {
+        let mut tree = tree();
+        tree.insert(0, rect(0.1, 0.1, 0.1));
+        tree.insert(1, rect(0.6, 0.6, 0.2));
+        assert_eq!(tree.remove(0), Some(rect(0.1, 0.1, 0.1)));
+        assert_eq!(tree.remove(0), None);
+        assert_eq!(tree.len(), 1);
+        assert!(tree.query_point(Point2::new(0.15, 0.15)).is_empty());
+
+        //inserting a key again moves it
+        tree.insert(1, rect(0.1, 0.1, 0.1));
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree.get(1), Some(rect(0.1, 0.1, 0.1)));
+        assert!(tree.query_point(Point2::new(0.7, 0.7)).is_empty());
+        assert_eq!(tree.query_point(Point2::new(0.15, 0.15)), vec![1]);
+
+        tree.clear();
+        assert!(tree.is_empty());
+        assert!(tree.query_rect(&rect(0.0, 0.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn stops_splitting_at_max_depth() {
+        let mut tree = tree();
+        for i in 0..MAX_NODE_ITEMS * 4 {
+            tree.insert(i, rect(0.1, 0.1, 0.0));
+        }
+        assert_eq!(depth(&tree.root), MAX_DEPTH);
+        assert_eq!(tree.query_point(Point2::new(0.1, 0.1)).len(), MAX_NODE_ITEMS * 4);
+    }
+}
@@ -1,4 +1,4 @@
-mod engine;
+pub mod engine;
 mod tests;
 
 mod lucas_game;
@@ -7,13 +7,23 @@ extern crate wasm_bindgen;
 
 use wasm_bindgen::prelude::*;
 
-use js_sys::{Float32Array, Uint16Array, ArrayBuffer, Uint8Array};
+use js_sys::{Float32Array, Uint16Array, Uint32Array, ArrayBuffer, Uint8Array};
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console, js_name = log)]
     fn log_str(s: &str);
+}
+
+//native test builds have no console to log to
+#[cfg(not(target_arch = "wasm32"))]
+fn log_str(s: &str) {
+    println!("{}", s);
+}
 
+#[wasm_bindgen]
+extern "C" {
     #[wasm_bindgen(js_namespace = console, js_name = log)]
     fn log_f32_arr(s: Float32Array);
 
@@ -23,6 +33,9 @@ extern "C" {
     #[wasm_bindgen(js_namespace = console, js_name = log)]
     fn log_u16_arr(s: Uint16Array);
 
+    #[wasm_bindgen(js_namespace = console, js_name = log)]
+    fn log_u32_arr(s: Uint32Array);
+
     #[wasm_bindgen(js_namespace = console, js_name = log)]
     fn log_array_buffer(s: &ArrayBuffer);
 
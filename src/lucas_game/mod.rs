@@ -1,8 +1,10 @@
-use cgmath::{Vector4, Vector2, Vector3, Matrix4, Deg, Point3};
+use std::{collections::HashMap, cmp::Ordering};
+
+use cgmath::{Vector4, Vector2, Vector3, Matrix4, Deg, Point2, Point3, EuclideanSpace};
 use instant::Duration;
 use wasm_bindgen::JsValue;
 
-use crate::{engine::{render::{types::image::Image, texture::TextureFilter, renderer::Renderer}, engine::Engine, input::input_collector::InputCollector}, log_str};
+use crate::{engine::{render::{types::image::Image, texture::TextureFilter, renderer::Renderer, bounds::Rect}, engine::Engine, input::input_collector::InputCollector, spatial_index::QuadTree}, log_str};
 
 use wasm_bindgen::prelude::*;
 
@@ -28,7 +30,7 @@ pub fn lucas_game() -> JsValue {
                 Matrix4::from_angle_y(Deg(if j%2 == 0 { 180.0 } else {0.0})) * 
                 Matrix4::from_scale(mountains_width[i])
                 
-            , String::from("./assets/lucas_background.png"),TextureFilter::Linear,TextureFilter::Linear));
+            , String::from("./assets/lucas_background.png"),TextureFilter::LinearMipmapLinear,TextureFilter::Linear).expect_throw("Expected mountain to fit its render type"));
         }
     }
 
@@ -36,7 +38,7 @@ pub fn lucas_game() -> JsValue {
 
     platform_gen.gen_layer(&mut engine.renderer);
 
-    let mut player = Player::new(Vector2::new(platform_gen.layers.last().unwrap().1.last().unwrap().pos.x, -0.5),&mut engine.renderer);
+    let mut player = Player::new(Vector2::new(platform_gen.last_platform().pos.x, -0.5),&mut engine.renderer);
 
     let mut cloud_gen = CloudGenerator::new(player.pos.extend(0.0), &mut engine.renderer);
 
@@ -47,7 +49,9 @@ pub fn lucas_game() -> JsValue {
     let result = Closure::new(move || {
         
         for mountain in mountains.iter_mut() {
-            mountain.render_unchanged(&mut engine.renderer);
+            if let Err(e) = mountain.render_unchanged(&mut engine.renderer) {
+                log_str(&e.to_string());
+            }
         };
 
         platform_gen.process(engine.renderer.camera_mut().position.y, &mut engine.renderer);
@@ -61,19 +65,11 @@ pub fn lucas_game() -> JsValue {
         if pos.y < -1.0 {
             platform_gen.reset();
             platform_gen.gen_layer(&mut engine.renderer);
-            player.pos = platform_gen.layers.last().unwrap().1.last().unwrap().pos;
+            player.pos = platform_gen.last_platform().pos;
             player.vertical_vel = 0.0;
             
         }
-        let b = (-1000.0,Vec::new());
-        let closest_layer = platform_gen.layers.iter().fold(&b, |a,x| if f32::abs(a.0-pos.y) > f32::abs(x.0-pos.y) { x } else { a} );
-        let closest_platform = closest_layer.1.iter().fold(None, |a: Option<&Platform>,x| 
-            if let Some(a) = a {
-                if f32::abs(a.pos.x-pos.x) > f32::abs(x.pos.x-pos.x) { Some(x) } else { Some(a) }
-            } else {
-                Some(x)
-            }
-        ).unwrap();
+        let closest_platform = platform_gen.closest(pos).unwrap();
 
         let diff = (closest_platform.pos-pos);
         let x = f32::abs(diff.x) < 0.2 && f32::abs(diff.y) < 0.05;
@@ -94,7 +90,12 @@ pub fn lucas_game() -> JsValue {
 }
 struct PlatformGenerator {
     next_layer_y:f32,
-    layers:Vec<(f32,Vec<Platform>)>
+    next_id:usize,
+    //heights of the layers with the ids of their platforms
+    layers:Vec<(f32,Vec<usize>)>,
+    platforms:HashMap<usize,Platform>,
+    //platform centers, closest compares their distances
+    index:QuadTree<usize>
 }
 
 const LAYER_PLATFORM_PROBABILITY:[f32; 3] = [0.5,0.4,0.1];
@@ -107,7 +108,13 @@ const LAYER_DROP_DIST:f32 = -1.2;
 const LAYER_START:f32 = -0.5;
 impl PlatformGenerator {
     fn new() -> Self {
-        Self { next_layer_y: LAYER_START, layers: Vec::new() }
+        Self {
+            next_layer_y: LAYER_START,
+            next_id:0,
+            layers: Vec::new(),
+            platforms:HashMap::new(),
+            index:QuadTree::new(Rect::new(Point2::new(-1.0, -1.0), Point2::new(1.0, 1.0)))
+        }
     }
 
     fn process(&mut self, current_height:f32, renderer:&mut Renderer) {
@@ -116,15 +123,31 @@ impl PlatformGenerator {
         }
 
         while self.layers.first().unwrap().0 - current_height < LAYER_DROP_DIST && self.layers.len() > 1 {
-            self.layers.remove(0);
+            for id in self.layers.remove(0).1 {
+                self.platforms.remove(&id);
+                self.index.remove(id);
+            }
         }
     }
 
     fn reset(&mut self) {
         self.layers.clear();
+        self.platforms.clear();
+        self.index.clear();
         self.next_layer_y = LAYER_START
     }
 
+    fn last_platform(&self) -> &Platform {
+        &self.platforms[self.layers.last().unwrap().1.last().unwrap()]
+    }
+
+    //platform of the layer closest in height that is closest horizontally
+    fn closest(&self, pos:Vector2<f32>) -> Option<&Platform> {
+        let (y, ids) = self.layers.iter().min_by(|a, b| f32::abs(a.0-pos.y).partial_cmp(&f32::abs(b.0-pos.y)).unwrap_or(Ordering::Equal))?;
+        //platforms are at the height of their layer
+        self.index.nearest_where(Point2::new(pos.x, *y), |id| ids.contains(&id)).map(|id| &self.platforms[&id])
+    }
+
     fn gen_layer(&mut self, renderer:&mut Renderer) {
         let y = self.next_layer_y;
         self.next_layer_y += LAYER_MAX_HEIGHT - LAYER_HEIGHT_VARIATION * random() as f32;
@@ -143,17 +166,25 @@ impl PlatformGenerator {
 
         if !self.layers.is_empty() {
             let i = platforms.last_mut().unwrap();
-            let a = self.layers.last().unwrap().1.iter().find(|p| f32::abs( p.pos.x - i.pos.x) < PLATFORM_MAX_DIFF_LAYER_DISTANCE);
+            let a = self.layers.last().unwrap().1.iter().find(|p| f32::abs( self.platforms[p].pos.x - i.pos.x) < PLATFORM_MAX_DIFF_LAYER_DISTANCE);
             if a.is_none() {
-                i.pos.x = self.layers.last().unwrap().1.last().unwrap().pos.x;
+                i.pos.x = self.last_platform().pos.x;
             }
         }
 
-        self.layers.push((y, platforms))
+        let mut ids = Vec::new();
+        for platform in platforms {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.index.insert(id, Rect::new(Point2::from_vec(platform.pos), Point2::from_vec(platform.pos)));
+            self.platforms.insert(id, platform);
+            ids.push(id);
+        }
+        self.layers.push((y, ids))
     }
 
     fn render(&mut self, renderer:&mut Renderer) {
-        for p in self.layers.iter_mut().map(|x| &mut x.1).flatten() {
+        for p in self.platforms.values_mut() {
             p.render(renderer);
         }
     }
@@ -184,13 +215,15 @@ impl Platform {
             Matrix4::from_translation(pos.extend(0.0)) * Matrix4::from_nonuniform_scale(PLATFORM_SCALE.x, PLATFORM_SCALE.y, 1.0),
             String::from("./assets/lucas_platform.png"),
             TextureFilter::Nearest,TextureFilter::Nearest
-        );
+        ).expect_throw("Expected platform to fit its render type");
 
         Self { pos: pos, obj: obj }
     }
 
     fn render(&mut self, renderer:&mut Renderer) {
-        self.obj.render(renderer,Matrix4::from_translation(self.pos.extend(0.0)) * Matrix4::from_nonuniform_scale(PLATFORM_SCALE.x, PLATFORM_SCALE.y, 1.0));
+        if let Err(e) = self.obj.render(renderer,Matrix4::from_translation(self.pos.extend(0.0)) * Matrix4::from_nonuniform_scale(PLATFORM_SCALE.x, PLATFORM_SCALE.y, 1.0)) {
+            log_str(&e.to_string());
+        }
     }
 }
 
@@ -210,7 +243,7 @@ impl Player {
             Matrix4::from_translation(pos.extend(0.01)) * Matrix4::from_nonuniform_scale(PLAYER_SCALE.x, PLAYER_SCALE.y, 1.0),
             String::from("./assets/lucas_strawberry.png"),
             TextureFilter::Nearest,TextureFilter::Nearest
-        );
+        ).expect_throw("Expected player to fit its render type");
         Self { pos: pos, obj: obj, vertical_vel:0.0 }
     }
 
@@ -246,7 +279,9 @@ impl Player {
     }
 
     fn render(&mut self, renderer:&mut Renderer) {
-        self.obj.render(renderer, Matrix4::from_translation(self.pos.extend(0.01))  * Matrix4::from_nonuniform_scale(PLAYER_SCALE.x, PLAYER_SCALE.y, 1.0));
+        if let Err(e) = self.obj.render(renderer, Matrix4::from_translation(self.pos.extend(0.01))  * Matrix4::from_nonuniform_scale(PLAYER_SCALE.x, PLAYER_SCALE.y, 1.0)) {
+            log_str(&e.to_string());
+        }
     }
 }
 
@@ -318,7 +353,7 @@ impl Cloud {
             Matrix4::from_translation(pos) * Matrix4::from_nonuniform_scale(scale.x, scale.y, 1.0),
             String::from("./assets/lucas_cloud.png"),
             TextureFilter::Nearest,TextureFilter::Nearest
-        );
+        ).expect_throw("Expected cloud to fit its render type");
 
         Self { obj: obj, speed: speed, scale:scale, pos: pos }
     }
@@ -327,6 +362,8 @@ impl Cloud {
     }
 
     fn render(&mut self, renderer:&mut Renderer) {
-        self.obj.render(renderer, Matrix4::from_translation(self.pos) * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, 1.0));
+        if let Err(e) = self.obj.render(renderer, Matrix4::from_translation(self.pos) * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, 1.0)) {
+            log_str(&e.to_string());
+        }
     }
 }
\ No newline at end of file
@@ -22,11 +22,11 @@ pub fn bezier_test() {
 
     let mut triangle = Triangle::new(&mut renderer, [
         Vector2::new(0.0, 0.0),Vector2::new(0.5, 0.0),Vector2::new(0.5, 0.5)
-    ], Vector4::new(0.0,0.0,1.0,1.0));
+    ], Vector4::new(0.0,0.0,1.0,1.0)).expect_throw("Expected triangle to fit its render type");
 
     let mut curve = QuadraticBezier::new(&mut renderer,[
         Vector2::new(0.0, 0.0),Vector2::new(0.5, 0.),Vector2::new(0.5, 0.5)
-    ],Vector4::new(1.0,0.0,0.0,1.0),0.01,0.01);
+    ],Vector4::new(1.0,0.0,0.0,1.0),0.01,0.01).expect_throw("Expected curve to fit its render type");
 
     // Here we want to call `requestAnimationFrame` in a loop, but only a fixed
     // number of times. After it's done we want all our resources cleaned up. To
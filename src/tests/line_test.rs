@@ -3,7 +3,6 @@ use crate::{log_str, engine::{render::types::line::{Line,EndBehavior}, engine::E
 use cgmath::{Vector4, Vector2};
 
 extern crate wasm_bindgen;
-use std::{ rc::Rc, cell::RefCell};
 
 use wasm_bindgen::prelude::*;
 
@@ -18,17 +17,19 @@ pub fn line_test_func() -> JsValue {
 
     let mut line =  Line::new(&mut engine.renderer,[
         Vector2::new(0.0, 0.0),Vector2::new(0.5, 0.0)
-    ],Vector4::new(1.0,0.0,0.0,1.0), 0.01, 0.01, EndBehavior::Clipped);
+    ],Vector4::new(1.0,0.0,0.0,1.0), 0.01, 0.01, EndBehavior::Clipped).expect_throw("Expected line to fit its render type");
 
     let mut line2 =  Line::new(&mut engine.renderer,[
         Vector2::new(0.5, 0.5),Vector2::new(0.0, 0.0)
-    ],Vector4::new(1.0,0.0,0.0,1.0),0.01,0.01, EndBehavior::Clipped);
+    ],Vector4::new(1.0,0.0,0.0,1.0),0.01,0.01, EndBehavior::Clipped).expect_throw("Expected line to fit its render type");
 
     let result = Closure::new(move || {
         
         line.render();
         line2.render();
-        line2.update_points(&mut engine.renderer, Vector2::new(0.0,0.0), engine.input.mouse_pos);
+        if let Err(e) = line2.update_points(&mut engine.renderer, Vector2::new(0.0,0.0), engine.input.mouse_pos) {
+            log_str(&e.to_string());
+        }
         engine.run();
 
     });
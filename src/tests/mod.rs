@@ -4,7 +4,7 @@ use crate::engine::render::renderer::Renderer;
 
 // mod texture_test;
 mod bezier_test;
-//mod line_test;
+mod line_test;
 // mod texture_update_test;
 mod input_test;
 